@@ -299,6 +299,14 @@ impl StateUpdate {
         )
     }
 
+    /// Whether the update leaves the state unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.contract_updates.is_empty()
+            && self.system_contract_updates.is_empty()
+            && self.declared_cairo_classes.is_empty()
+            && self.declared_sierra_classes.is_empty()
+    }
+
     pub fn state_diff_length(&self) -> u64 {
         let mut len = 0;
         self.contract_updates.iter().for_each(|(_, update)| {
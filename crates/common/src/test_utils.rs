@@ -118,7 +118,8 @@ pub mod metrics {
         }
 
         fn register_gauge(&self, _: &Key) -> Gauge {
-            unimplemented!()
+            // Ignored in tests for now
+            Gauge::noop()
         }
         fn register_histogram(&self, _: &Key) -> Histogram {
             // Ignored in tests for now
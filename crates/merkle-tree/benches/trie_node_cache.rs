@@ -0,0 +1,95 @@
+//! Compares updating the global state trie for consecutive blocks, as sync
+//! does, with and without the storage trie node cache.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use pathfinder_common::{
+    BlockHash,
+    BlockHeader,
+    BlockNumber,
+    ClassHash,
+    ContractAddress,
+    StateUpdate,
+    StorageAddress,
+    StorageValue,
+};
+use pathfinder_crypto::Felt;
+use pathfinder_merkle_tree::starknet_state::update_starknet_state;
+use pathfinder_storage::{Connection, Storage, StorageBuilder, TriePruneMode};
+
+/// Number of contracts deployed in the genesis block.
+const CONTRACTS: u64 = 4096;
+/// Number of contracts with a storage update in every following block.
+const UPDATED_PER_BLOCK: u64 = 64;
+
+fn contract(i: u64) -> ContractAddress {
+    ContractAddress(Felt::from_u64(0x1000 + i))
+}
+
+fn block(number: u64) -> (BlockHeader, StateUpdate) {
+    let header = BlockHeader::builder()
+        .number(BlockNumber::new_or_panic(number))
+        .finalize_with_hash(BlockHash(Felt::from_u64(number)));
+    let state_update = if number == 0 {
+        (0..CONTRACTS).fold(StateUpdate::default(), |state_update, i| {
+            state_update.with_deployed_contract(contract(i), ClassHash(Felt::from_u64(i)))
+        })
+    } else {
+        // Spread the updated contracts over the whole tree, with a different
+        // subset in each block.
+        (0..UPDATED_PER_BLOCK).fold(StateUpdate::default(), |state_update, i| {
+            let i = (number * 7919 + i * 61) % CONTRACTS;
+            state_update.with_storage_update(
+                contract(i),
+                StorageAddress(Felt::from_u64(number % 16)),
+                StorageValue(Felt::from_u64(number)),
+            )
+        })
+    };
+    (header, state_update)
+}
+
+fn insert_block(storage: &Storage, connection: &mut Connection, number: u64) {
+    let (header, state_update) = block(number);
+    let tx = connection.transaction().unwrap();
+    tx.insert_block_header(&header).unwrap();
+    tx.insert_state_update(header.number, &state_update)
+        .unwrap();
+    update_starknet_state(
+        &tx,
+        (&state_update).into(),
+        true,
+        header.number,
+        storage.clone(),
+    )
+    .unwrap();
+    tx.commit().unwrap();
+}
+
+pub fn bench_trie_node_cache(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update_starknet_state");
+
+    for cache_size in [0, 100_000] {
+        let storage = StorageBuilder::in_tempdir_with_trie_node_cache(
+            TriePruneMode::Prune {
+                num_blocks_kept: 20,
+            },
+            cache_size,
+        )
+        .unwrap();
+        let mut connection = storage.connection().unwrap();
+        insert_block(&storage, &mut connection, 0);
+
+        let mut number = 1;
+        group.bench_function(BenchmarkId::new("trie_node_cache_size", cache_size), |b| {
+            b.iter(|| {
+                insert_block(&storage, &mut connection, number);
+                number += 1;
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_trie_node_cache);
+criterion_main!(benches);
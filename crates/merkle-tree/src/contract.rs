@@ -138,6 +138,35 @@ impl<'tx> ContractsStorageTree<'tx> {
     ) -> anyhow::Result<Option<B>> {
         self.tree.dfs(&self.storage, f)
     }
+
+    /// All storage entries of the contract as of the block the tree was
+    /// [loaded](Self::load) at, ordered by storage address.
+    ///
+    /// Walks the entire tree, so this is expensive for contracts with a lot of
+    /// storage.
+    pub fn storage_entries(&self) -> anyhow::Result<Vec<(StorageAddress, StorageValue)>> {
+        let mut leaves = Vec::new();
+        // The left child is visited first, so leaves are found in key order.
+        self.tree.dfs(&self.storage, &mut |node, path| {
+            if let InternalNode::Leaf = node {
+                leaves.push(path.to_owned());
+            }
+            ControlFlow::<(), _>::Continue(Visit::ContinueDeeper)
+        })?;
+
+        leaves
+            .into_iter()
+            .map(|path| {
+                let key = StorageAddress(
+                    Felt::from_bits(&path).context("Mapping leaf path to storage address")?,
+                );
+                let value = crate::storage::Storage::leaf(&self.storage, &path)?
+                    .with_context(|| format!("Storage value missing for {key}"))?;
+
+                Ok((key, StorageValue(value)))
+            })
+            .collect()
+    }
 }
 
 /// A [Patricia Merkle tree](MerkleTree) used to calculate commitments to all of
@@ -318,3 +347,90 @@ impl crate::storage::Storage for StorageTrieStorage<'_> {
         Ok(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{BlockHeader, StateUpdate};
+    use pathfinder_storage::StorageBuilder;
+
+    use super::*;
+    use crate::starknet_state::update_starknet_state;
+
+    #[test]
+    fn storage_entries() {
+        let storage = StorageBuilder::in_tempdir().unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        let contract = contract_address!("0x123");
+        let updates = [
+            StateUpdate::default()
+                .with_deployed_contract(contract, class_hash!("0xabc"))
+                .with_storage_update(contract, storage_address!("0x3"), storage_value!("0x30"))
+                .with_storage_update(contract, storage_address!("0x1"), storage_value!("0x10"))
+                .with_storage_update(contract, storage_address!("0x2"), storage_value!("0x20")),
+            StateUpdate::default()
+                .with_storage_update(contract, storage_address!("0x2"), storage_value!("0x21"))
+                .with_storage_update(contract, storage_address!("0x4"), storage_value!("0x40")),
+        ];
+        let genesis = BlockHeader::builder().finalize_with_hash(block_hash!("0xb0"));
+        let headers = [
+            genesis.clone(),
+            genesis
+                .child_builder()
+                .finalize_with_hash(block_hash!("0xb1")),
+        ];
+        // Contract updates are applied on separate connections which only see
+        // committed data, so each block is committed before the next one builds on
+        // it.
+        for (header, state_update) in headers.iter().zip(&updates) {
+            let tx = connection.transaction().unwrap();
+            tx.insert_block_header(header).unwrap();
+            tx.insert_state_update(header.number, state_update).unwrap();
+            update_starknet_state(
+                &tx,
+                state_update.into(),
+                true,
+                header.number,
+                storage.clone(),
+            )
+            .unwrap();
+            tx.commit().unwrap();
+        }
+
+        let tx = connection.transaction().unwrap();
+        let entries = ContractsStorageTree::load(&tx, contract, BlockNumber::GENESIS)
+            .unwrap()
+            .storage_entries()
+            .unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                (storage_address!("0x1"), storage_value!("0x10")),
+                (storage_address!("0x2"), storage_value!("0x20")),
+                (storage_address!("0x3"), storage_value!("0x30")),
+            ]
+        );
+
+        let entries = ContractsStorageTree::load(&tx, contract, BlockNumber::GENESIS + 1)
+            .unwrap()
+            .storage_entries()
+            .unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                (storage_address!("0x1"), storage_value!("0x10")),
+                (storage_address!("0x2"), storage_value!("0x21")),
+                (storage_address!("0x3"), storage_value!("0x30")),
+                (storage_address!("0x4"), storage_value!("0x40")),
+            ]
+        );
+
+        let unknown =
+            ContractsStorageTree::load(&tx, contract_address!("0x456"), BlockNumber::GENESIS)
+                .unwrap()
+                .storage_entries()
+                .unwrap();
+        assert_eq!(unknown, vec![]);
+    }
+}
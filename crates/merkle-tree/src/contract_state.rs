@@ -224,9 +224,19 @@ pub fn revert_contract_state(
 
 #[cfg(test)]
 mod tests {
-    use pathfinder_common::{felt, ClassHash, ContractNonce, ContractRoot, ContractStateHash};
+    use std::collections::HashMap;
+
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{
+        felt,
+        BlockNumber,
+        ClassHash,
+        ContractNonce,
+        ContractRoot,
+        ContractStateHash,
+    };
 
-    use super::calculate_contract_state_hash;
+    use super::{calculate_contract_state_hash, update_contract_state, StorageRef};
 
     #[test]
     fn hash() {
@@ -245,4 +255,41 @@ mod tests {
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn redeploy_preserves_storage_root() {
+        let storage = pathfinder_storage::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let transaction = connection.transaction().unwrap();
+
+        let address = contract_address!("0x123");
+        let class_hash = class_hash!("0xabc");
+        let updates = HashMap::from([(storage_address!("0x1"), storage_value!("0x2"))]);
+
+        let deployed = update_contract_state(
+            address,
+            (&updates).into(),
+            Some(ContractNonce::ZERO),
+            Some(class_hash),
+            &transaction,
+            false,
+            BlockNumber::GENESIS,
+        )
+        .unwrap();
+        let deployed_hash = deployed.state_hash;
+        deployed.insert(BlockNumber::GENESIS, &transaction).unwrap();
+
+        // Deploying the same contract again must keep the storage written so far.
+        let redeployed = update_contract_state(
+            address,
+            StorageRef::HashMap(&HashMap::new()),
+            Some(ContractNonce::ZERO),
+            Some(class_hash),
+            &transaction,
+            false,
+            BlockNumber::GENESIS + 1,
+        )
+        .unwrap();
+        assert_eq!(redeployed.state_hash, deployed_hash);
+    }
 }
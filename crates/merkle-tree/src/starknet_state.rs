@@ -1,6 +1,14 @@
+use std::collections::BTreeMap;
+
 use anyhow::Context;
 use pathfinder_common::state_update::{StateUpdateError, StateUpdateRef};
-use pathfinder_common::{BlockNumber, ClassCommitment, StorageCommitment};
+use pathfinder_common::{
+    BlockNumber,
+    ClassCommitment,
+    ContractAddress,
+    ContractStateHash,
+    StorageCommitment,
+};
 use pathfinder_storage::{Storage, Transaction};
 
 use crate::contract_state::update_contract_state;
@@ -14,6 +22,53 @@ pub fn update_starknet_state(
     // we need this so that we can create extra read-only transactions for
     // parallel contract state updates
     storage: Storage,
+) -> Result<(StorageCommitment, ClassCommitment), StateUpdateError> {
+    update(
+        transaction,
+        state_update,
+        verify_hashes,
+        block,
+        storage,
+        None,
+    )
+}
+
+/// Like [`update_starknet_state`] but also returns the state hash computed for
+/// each updated contract, which is useful to debug state root mismatches.
+pub fn update_starknet_state_with_contract_hashes(
+    transaction: &Transaction<'_>,
+    state_update: StateUpdateRef<'_>,
+    verify_hashes: bool,
+    block: BlockNumber,
+    storage: Storage,
+) -> Result<
+    (
+        StorageCommitment,
+        ClassCommitment,
+        BTreeMap<ContractAddress, ContractStateHash>,
+    ),
+    StateUpdateError,
+> {
+    let mut contract_hashes = BTreeMap::new();
+    let (storage_commitment, class_commitment) = update(
+        transaction,
+        state_update,
+        verify_hashes,
+        block,
+        storage,
+        Some(&mut contract_hashes),
+    )?;
+
+    Ok((storage_commitment, class_commitment, contract_hashes))
+}
+
+fn update(
+    transaction: &Transaction<'_>,
+    state_update: StateUpdateRef<'_>,
+    verify_hashes: bool,
+    block: BlockNumber,
+    storage: Storage,
+    mut contract_hashes: Option<&mut BTreeMap<ContractAddress, ContractStateHash>>,
 ) -> Result<(StorageCommitment, ClassCommitment), StateUpdateError> {
     use rayon::prelude::*;
 
@@ -64,6 +119,12 @@ pub fn update_starknet_state(
     let contract_update_results = recv.recv().context("Panic on rayon thread")??;
 
     for contract_update_result in contract_update_results.into_iter() {
+        if let Some(contract_hashes) = contract_hashes.as_deref_mut() {
+            contract_hashes.insert(
+                contract_update_result.contract_address,
+                contract_update_result.state_hash,
+            );
+        }
         storage_commitment_tree
             .set(
                 contract_update_result.contract_address,
@@ -87,6 +148,9 @@ pub fn update_starknet_state(
         )
         .context("Update system contract state")?;
 
+        if let Some(contract_hashes) = contract_hashes.as_deref_mut() {
+            contract_hashes.insert(*contract, update_result.state_hash);
+        }
         storage_commitment_tree
             .set(*contract, update_result.state_hash)
             .context("Updating system contract storage commitment tree")?;
@@ -144,3 +208,197 @@ pub fn update_starknet_state(
 
     Ok((storage_commitment, class_commitment))
 }
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{ContractAddress, StateUpdate};
+
+    use super::*;
+
+    #[test]
+    fn contract_hashes_are_returned_for_each_updated_contract() {
+        let storage = pathfinder_storage::StorageBuilder::in_tempdir().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let transaction = connection.transaction().unwrap();
+
+        let deployed = contract_address!("0x123");
+        let with_storage = contract_address!("0x456");
+        let state_update = StateUpdate::default()
+            .with_deployed_contract(deployed, class_hash!("0xabc"))
+            .with_deployed_contract(with_storage, class_hash!("0xdef"))
+            .with_storage_update(with_storage, storage_address!("0x1"), storage_value!("0x2"))
+            .with_system_storage_update(
+                ContractAddress::ONE,
+                storage_address!("0x3"),
+                storage_value!("0x4"),
+            );
+
+        let (storage_commitment, class_commitment, contract_hashes) =
+            update_starknet_state_with_contract_hashes(
+                &transaction,
+                (&state_update).into(),
+                false,
+                BlockNumber::GENESIS,
+                storage.clone(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            contract_hashes.keys().copied().collect::<Vec<_>>(),
+            vec![ContractAddress::ONE, deployed, with_storage]
+        );
+        for (contract, state_hash) in &contract_hashes {
+            let stored = transaction
+                .contract_state_hash(BlockNumber::GENESIS, *contract)
+                .unwrap();
+            assert_eq!(stored, Some(*state_hash));
+        }
+        assert_ne!(storage_commitment, StorageCommitment::ZERO);
+        assert_eq!(class_commitment, ClassCommitment::ZERO);
+    }
+
+    #[test]
+    fn replaced_class_updates_contract_state() {
+        use pathfinder_common::{BlockHeader, ContractNonce, ContractRoot};
+        use pathfinder_storage::StorageBuilder;
+
+        use crate::contract_state::calculate_contract_state_hash;
+
+        let contract = contract_address!("0x123");
+        let original = class_hash!("0xabc");
+        let replacement = class_hash!("0xdef");
+
+        let storage = StorageBuilder::in_tempdir().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let genesis = BlockHeader::builder().finalize_with_hash(block_hash!("0xb0"));
+        let child = genesis
+            .child_builder()
+            .finalize_with_hash(block_hash!("0xb1"));
+        let updates = [
+            (
+                &genesis,
+                StateUpdate::default().with_deployed_contract(contract, original),
+            ),
+            (
+                &child,
+                StateUpdate::default().with_replaced_class(contract, replacement),
+            ),
+        ];
+        let mut storage_commitments = Vec::new();
+        for (header, state_update) in &updates {
+            let tx = connection.transaction().unwrap();
+            tx.insert_block_header(header).unwrap();
+            tx.insert_state_update(header.number, state_update).unwrap();
+            let (storage_commitment, _) = update_starknet_state(
+                &tx,
+                state_update.into(),
+                true,
+                header.number,
+                storage.clone(),
+            )
+            .unwrap();
+            tx.commit().unwrap();
+            storage_commitments.push(storage_commitment);
+        }
+
+        let tx = connection.transaction().unwrap();
+        assert_eq!(
+            tx.contract_class_hash(child.number.into(), contract)
+                .unwrap(),
+            Some(replacement)
+        );
+        assert_eq!(
+            tx.contract_state_hash(child.number, contract).unwrap(),
+            Some(calculate_contract_state_hash(
+                replacement,
+                ContractRoot::ZERO,
+                ContractNonce::ZERO
+            ))
+        );
+        assert_ne!(storage_commitments[0], storage_commitments[1]);
+
+        // The global root matches that of the contract having been deployed with the
+        // replacement class in the first place.
+        let deployed = StorageBuilder::in_tempdir().unwrap();
+        let mut connection = deployed.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        let state_update = StateUpdate::default().with_deployed_contract(contract, replacement);
+        tx.insert_block_header(&genesis).unwrap();
+        tx.insert_state_update(genesis.number, &state_update)
+            .unwrap();
+        let (storage_commitment, _) = update_starknet_state(
+            &tx,
+            (&state_update).into(),
+            true,
+            genesis.number,
+            deployed.clone(),
+        )
+        .unwrap();
+        assert_eq!(storage_commitment, storage_commitments[1]);
+    }
+
+    #[test]
+    fn trie_node_cache_does_not_change_roots() {
+        use pathfinder_common::{BlockHash, BlockHeader, ClassHash, StorageAddress, StorageValue};
+        use pathfinder_crypto::Felt;
+        use pathfinder_storage::{StorageBuilder, TriePruneMode};
+
+        // Pruning removes nodes between blocks, which must also evict them from the
+        // cache.
+        let prune_mode = TriePruneMode::Prune { num_blocks_kept: 1 };
+        let uncached = StorageBuilder::in_tempdir_with_trie_node_cache(prune_mode, 0).unwrap();
+        let cached = StorageBuilder::in_tempdir_with_trie_node_cache(prune_mode, 1024).unwrap();
+
+        let contract = |i: u64| ContractAddress(Felt::from_u64(0x1000 + i));
+        let blocks = (0..6u64).map(|block| {
+            let header = BlockHeader::builder()
+                .number(BlockNumber::new_or_panic(block))
+                .finalize_with_hash(BlockHash(Felt::from_u64(block)));
+            let state_update = if block == 0 {
+                (0..32).fold(StateUpdate::default(), |state_update, i| {
+                    state_update.with_deployed_contract(contract(i), ClassHash(Felt::from_u64(i)))
+                })
+            } else {
+                // Touch an overlapping subset of contracts in each block so that
+                // most of the global tree is shared with the previous one.
+                (block..block + 8).fold(StateUpdate::default(), |state_update, i| {
+                    state_update.with_storage_update(
+                        contract(i),
+                        StorageAddress(Felt::from_u64(i % 3)),
+                        StorageValue(Felt::from_u64(block * 100 + i)),
+                    )
+                })
+            };
+            (header, state_update)
+        });
+
+        let mut uncached_connection = uncached.connection().unwrap();
+        let mut cached_connection = cached.connection().unwrap();
+        for (header, state_update) in blocks {
+            let mut commitments = Vec::new();
+            for (storage, connection) in [
+                (&uncached, &mut uncached_connection),
+                (&cached, &mut cached_connection),
+            ] {
+                let tx = connection.transaction().unwrap();
+                tx.insert_block_header(&header).unwrap();
+                tx.insert_state_update(header.number, &state_update)
+                    .unwrap();
+                let (storage_commitment, _) = update_starknet_state(
+                    &tx,
+                    (&state_update).into(),
+                    true,
+                    header.number,
+                    storage.clone(),
+                )
+                .unwrap();
+                tx.commit().unwrap();
+                commitments.push(storage_commitment);
+            }
+
+            assert_ne!(commitments[0], StorageCommitment::ZERO);
+            assert_eq!(commitments[0], commitments[1], "block {}", header.number);
+        }
+    }
+}
@@ -135,14 +135,22 @@ impl Builder {
             .request_timeout(cfg.stream_timeout)
             .max_concurrent_streams(cfg.max_concurrent_streams);
 
-        let header_sync = header_sync
-            .unwrap_or_else(|| p2p_stream::Behaviour::<codec::Headers>::new(p2p_stream_cfg));
+        let header_sync = header_sync.unwrap_or_else(|| {
+            p2p_stream::Behaviour::with_codec(
+                codec::Headers::with_response_size_limit(cfg.max_response_size),
+                p2p_stream_cfg,
+            )
+        });
         let class_sync = class_sync
             .unwrap_or_else(|| p2p_stream::Behaviour::<codec::Classes>::new(p2p_stream_cfg));
         let state_diff_sync = state_diff_sync
             .unwrap_or_else(|| p2p_stream::Behaviour::<codec::StateDiffs>::new(p2p_stream_cfg));
-        let transaction_sync = transaction_sync
-            .unwrap_or_else(|| p2p_stream::Behaviour::<codec::Transactions>::new(p2p_stream_cfg));
+        let transaction_sync = transaction_sync.unwrap_or_else(|| {
+            p2p_stream::Behaviour::with_codec(
+                codec::Transactions::with_response_size_limit(cfg.max_response_size),
+                p2p_stream_cfg,
+            )
+        });
         let event_sync = event_sync
             .unwrap_or_else(|| p2p_stream::Behaviour::<codec::Events>::new(p2p_stream_cfg));
 
@@ -1,15 +1,16 @@
 //! _High level_ client for p2p interaction.
 //! Frees the caller from managing peers manually.
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+use anyhow::Context;
 use futures::channel::mpsc as fmpsc;
 use futures::{Stream, StreamExt, TryStreamExt};
 use libp2p::PeerId;
 use p2p_proto::class::{ClassesRequest, ClassesResponse};
-use p2p_proto::common::{Direction, Iteration};
+use p2p_proto::common::{BlockNumberOrHash, Direction, Iteration};
 use p2p_proto::event::{EventsRequest, EventsResponse};
 use p2p_proto::header::{BlockHeadersRequest, BlockHeadersResponse};
 use p2p_proto::state::{
@@ -24,11 +25,14 @@ use pathfinder_common::event::Event;
 use pathfinder_common::state_update::{ContractClassUpdate, StateUpdateData};
 use pathfinder_common::transaction::Transaction;
 use pathfinder_common::{
+    BlockHash,
+    BlockHeader,
     BlockNumber,
     CasmHash,
     ClassHash,
     ContractAddress,
     ContractNonce,
+    PublicKey,
     SierraHash,
     SignedBlockHeader,
     StorageAddress,
@@ -51,16 +55,18 @@ use traits::{
     HeaderStream,
     StateDiffStream,
     StreamItem,
+    SyncError,
     TransactionStream,
 };
 
-use crate::client::conv::{CairoDefinition, FromDto, SierraDefinition, TryFromDto};
+use crate::client::conv::{CairoDefinition, FromDto, SierraDefinition, ToDto, TryFromDto};
 use crate::client::peer_aware;
 use crate::client::types::{
     ClassDefinition,
     ClassDefinitionsError,
     EventsForBlockByTransaction,
     EventsResponseStreamFailure,
+    FullBlock,
     Receipt,
     StateDiffsError,
     TransactionData,
@@ -70,39 +76,164 @@ use crate::peer_data::PeerData;
 #[derive(Clone, Debug)]
 pub struct Client {
     inner: peer_aware::Client,
-    block_propagation_topic: Arc<String>,
+    /// Can be replaced at runtime, see [`Client::set_block_propagation_topic`].
+    block_propagation_topic: Arc<std::sync::RwLock<String>>,
     peers: Arc<RwLock<Decaying<HashSet<PeerId>>>>,
+    /// Number of peers providing each capability queried so far.
+    capability_peers: Arc<RwLock<HashMap<String, Decaying<usize>>>>,
+    scores: PeerScores,
+    /// How long to wait for the next response from a peer before moving on to
+    /// another one.
+    response_timeout: Duration,
+    retry_policy: RetryPolicy,
+    /// If set, streamed headers with a signature not matching this key are
+    /// treated as a peer failure.
+    header_public_key: Option<PublicKey>,
+}
+
+/// How persistently streams retry their peer set before giving up.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    /// Number of consecutive passes over the peer set that may fail to yield
+    /// any data before the stream yields an error and ends.
+    pub max_attempts: std::num::NonZeroUsize,
 }
 
 impl Client {
-    pub fn new(inner: peer_aware::Client, block_propagation_topic: String) -> Self {
+    pub fn new(
+        inner: peer_aware::Client,
+        block_propagation_topic: String,
+        response_timeout: Duration,
+        retry_policy: RetryPolicy,
+        header_public_key: Option<PublicKey>,
+    ) -> Self {
         Self {
             inner,
-            block_propagation_topic: Arc::new(block_propagation_topic),
+            block_propagation_topic: Arc::new(std::sync::RwLock::new(block_propagation_topic)),
             peers: Default::default(),
+            capability_peers: Default::default(),
+            scores: Default::default(),
+            response_timeout,
+            retry_policy,
+            header_public_key,
         }
     }
 
+    /// A snapshot of the scores of all peers that have served sync requests.
+    pub fn peer_scores(&self) -> HashMap<PeerId, PeerScore> {
+        self.scores.snapshot()
+    }
+
+    /// Replaces the topic new heads are propagated on, e.g. when the network
+    /// migrates to a new topic. Applies to all clones of this client.
+    pub fn set_block_propagation_topic(&self, topic: String) {
+        *self
+            .block_propagation_topic
+            .write()
+            .expect("Topic lock not to be poisoned") = topic;
+    }
+
+    fn block_propagation_topic(&self) -> String {
+        self.block_propagation_topic
+            .read()
+            .expect("Topic lock not to be poisoned")
+            .clone()
+    }
+
     // Propagate new L2 head head
     pub async fn propagate_new_head(
         &self,
         block_id: p2p_proto::common::BlockId,
     ) -> anyhow::Result<()> {
-        tracing::debug!(number=%block_id.number, hash=%block_id.hash.0, topic=%self.block_propagation_topic,
+        let topic = self.block_propagation_topic();
+        tracing::debug!(number=%block_id.number, hash=%block_id.hash.0, %topic,
             "Propagating head"
         );
 
         self.inner
-            .publish(
-                &self.block_propagation_topic,
-                p2p_proto::header::NewBlock::Id(block_id),
-            )
+            .publish(&topic, p2p_proto::header::NewBlock::Id(block_id))
             .await
     }
 
-    async fn get_random_peers(&self) -> Vec<PeerId> {
-        use rand::seq::SliceRandom;
+    /// Propagate a new L2 head along with its full header, so that subscribers
+    /// don't have to request it separately.
+    ///
+    /// Fails without publishing anything if the header's signature does not
+    /// match `public_key`.
+    pub async fn propagate_new_header(
+        &self,
+        header: SignedBlockHeader,
+        public_key: PublicKey,
+    ) -> anyhow::Result<()> {
+        let topic = self.block_propagation_topic();
+        tracing::debug!(number=%header.header.number, hash=%header.header.hash, %topic,
+            "Propagating header"
+        );
 
+        let new_block = new_block_header(header, public_key)?;
+        self.inner.publish(&topic, new_block).await
+    }
+
+    /// Populates the peer cache up front, so that the first stream started
+    /// afterwards doesn't have to wait for peer discovery. Does nothing if the
+    /// cache is still fresh.
+    pub async fn warm_peer_cache(&self) {
+        if self.peers.read().await.get().is_some() {
+            return;
+        }
+
+        let mut w = self.peers.write().await;
+        // Check again because another task might have updated the peers while we
+        // were waiting for the write lock.
+        if w.get().is_some() {
+            return;
+        }
+
+        let peers = self.discover_peers().await;
+        w.update(peers);
+    }
+
+    /// Number of peers known to this client, discovering them first if the
+    /// peer cache has expired.
+    pub async fn known_peer_count(&self) -> usize {
+        self.warm_peer_cache().await;
+        self.peers.read().await.get().map_or(0, HashSet::len)
+    }
+
+    /// Number of peers providing each of the `capabilities`. Counts are cached
+    /// and only queried from the DHT once expired.
+    pub async fn capability_peer_counts(
+        &self,
+        capabilities: &[&str],
+    ) -> anyhow::Result<HashMap<String, usize>> {
+        let mut counts = HashMap::with_capacity(capabilities.len());
+        for &capability in capabilities {
+            let cached = self
+                .capability_peers
+                .read()
+                .await
+                .get(capability)
+                .and_then(|count| count.get().copied());
+            let count = match cached {
+                Some(count) => count,
+                None => {
+                    let count = self.inner.get_capability_providers(capability).await?.len();
+                    self.capability_peers
+                        .write()
+                        .await
+                        .entry(capability.to_owned())
+                        .or_default()
+                        .update(count);
+                    count
+                }
+            };
+            counts.insert(capability.to_owned(), count);
+        }
+
+        Ok(counts)
+    }
+
+    async fn get_random_peers(&self) -> Vec<PeerId> {
         let r = self.peers.read().await;
         let mut peers = if let Some(peers) = r.get() {
             peers.iter().copied().collect::<Vec<_>>()
@@ -116,57 +247,188 @@ impl Client {
                 return peers.iter().copied().collect::<Vec<_>>();
             }
 
-            // TODO known peers abstraction should not poll
-            //
-            // Loop until we find at least a single peer.
-            // 1. After the process is spawned the first outgoing query may start earlier
-            //    than the `kad` protocol is pushed in from `identify/push` resulting in a
-            //    `kind: ConnectionRefused, error: "protocol not supported"` error.
-            // 2. Initially there may be no other peers but maybe we're running a local test
-            //    and the other peer pops up in a few seconds.
-            // Either way we don't want to wait for the bootstrap timeout or the
-            // `Decaying::DEFAULT_TIMEOUT`, whichever kicks in first.
-            let peers = loop {
-                let mut peers = self
-                    .inner
-                    .get_closest_peers(PeerId::random())
-                    .await
-                    .unwrap_or_default();
-                // We could be on the list
-                peers.remove(self.inner.peer_id());
-
-                if peers.is_empty() {
-                    tracing::info!("No peers found in DHT, retrying");
-                    tokio::time::sleep(Duration::from_secs(3)).await;
-                } else {
-                    break peers;
-                }
-            };
-
+            let peers = self.discover_peers().await;
             let peers_vec = peers.iter().copied().collect::<Vec<_>>();
 
             w.update(peers);
             peers_vec
         };
-        peers.shuffle(&mut rand::thread_rng());
+        self.scores.rank(&mut peers);
 
         peers
     }
-}
 
-impl HeaderStream for Client {
-    fn header_stream(
+    async fn discover_peers(&self) -> HashSet<PeerId> {
+        // TODO known peers abstraction should not poll
+        //
+        // Loop until we find at least a single peer.
+        // 1. After the process is spawned the first outgoing query may start earlier
+        //    than the `kad` protocol is pushed in from `identify/push` resulting in a
+        //    `kind: ConnectionRefused, error: "protocol not supported"` error.
+        // 2. Initially there may be no other peers but maybe we're running a local test
+        //    and the other peer pops up in a few seconds.
+        // Either way we don't want to wait for the bootstrap timeout or the
+        // `Decaying::DEFAULT_TIMEOUT`, whichever kicks in first.
+        loop {
+            let mut peers = self
+                .inner
+                .get_closest_peers(PeerId::random())
+                .await
+                .unwrap_or_default();
+            // We could be on the list
+            peers.remove(self.inner.peer_id());
+
+            if peers.is_empty() {
+                tracing::info!("No peers found in DHT, retrying");
+                tokio::time::sleep(Duration::from_secs(3)).await;
+            } else {
+                break peers;
+            }
+        }
+    }
+
+    /// Like [`HeaderStream::header_stream`] but splits the range into
+    /// sub-ranges of up to `MAX_BLOCKS_COUNT` headers and requests up to
+    /// `fanout` of them from different peers concurrently. Headers are still
+    /// yielded in order.
+    pub fn header_stream_concurrent(
+        self,
+        start: BlockNumber,
+        stop: BlockNumber,
+        reverse: bool,
+        fanout: std::num::NonZeroUsize,
+    ) -> impl Stream<Item = StreamItem<SignedBlockHeader, SyncError>> {
+        let inner = self.inner.clone();
+        let response_timeout = self.response_timeout;
+        let retry_policy = self.retry_policy;
+        let public_key = self.header_public_key;
+        let scores = self.scores.clone();
+        let outer = self;
+        header_stream::make_concurrent(
+            start,
+            stop,
+            reverse,
+            response_timeout,
+            retry_policy,
+            public_key,
+            scores,
+            fanout,
+            std::num::NonZeroU64::new(MAX_BLOCKS_COUNT).expect("MAX_BLOCKS_COUNT > 0"),
+            move || {
+                let outer = outer.clone();
+                async move { outer.get_random_peers().await }
+            },
+            move |peer, request| {
+                let inner = inner.clone();
+                async move { inner.send_headers_sync_request(peer, request).await }
+            },
+        )
+    }
+
+    /// Fetches the header of a single `block`, trying peers until one serves
+    /// it. Cheaper than a one-block [`HeaderStream::header_stream`].
+    ///
+    /// Fails if no peer could serve the header within the configured
+    /// [`RetryPolicy`].
+    pub async fn get_header(
+        self,
+        block: BlockNumber,
+    ) -> anyhow::Result<PeerData<SignedBlockHeader>> {
+        let inner = self.inner.clone();
+        header_stream::get(
+            block,
+            self.response_timeout,
+            self.retry_policy,
+            self.header_public_key,
+            self.scores.clone(),
+            || self.get_random_peers(),
+            |peer, request| inner.send_headers_sync_request(peer, request),
+        )
+        .await
+    }
+
+    /// Streams [full blocks](FullBlock) from `start` to `stop`, or from `stop`
+    /// down to `start` when `reverse` is set.
+    ///
+    /// The transactions and state diff of each block may come from different
+    /// peers than its header, which is the peer a block is attributed to. A
+    /// component that cannot be fetched is retried up to the configured
+    /// [`RetryPolicy`] before the stream yields an error and ends.
+    pub fn block_stream(
+        self,
+        start: BlockNumber,
+        stop: BlockNumber,
+        reverse: bool,
+    ) -> impl Stream<Item = StreamItem<FullBlock>> {
+        let retry_policy = self.retry_policy;
+        let headers = self
+            .clone()
+            .header_stream(start, stop, reverse, std::num::NonZeroU64::MIN);
+        let transactions_client = self.clone();
+        let state_diff_client = self;
+        block_stream::make(
+            headers,
+            retry_policy,
+            move |block, transaction_count| {
+                let client = transactions_client.clone();
+                async move {
+                    let Some((peer, transactions)) = client.transactions_for_block(block).await
+                    else {
+                        return Ok(None);
+                    };
+                    let transactions = transactions.try_collect::<Vec<_>>().await?;
+                    anyhow::ensure!(
+                        transactions.len() == transaction_count,
+                        "Expected {transaction_count} transactions from peer {peer}, got {}",
+                        transactions.len()
+                    );
+                    let transactions = transactions
+                        .into_iter()
+                        .map(|(transaction, receipt)| (transaction, receipt.into()))
+                        .collect();
+                    Ok(Some(PeerData::new(peer, transactions)))
+                }
+            },
+            move |block, state_diff_length| {
+                let client = state_diff_client.clone();
+                async move {
+                    let state_diff = client
+                        .state_diff_for_block(block, state_diff_length)
+                        .await
+                        .map_err(|error| anyhow::anyhow!("{error}"))?;
+                    Ok(state_diff.map(|(peer, state_diff)| PeerData::new(peer, state_diff)))
+                }
+            },
+        )
+    }
+
+    /// Like [`HeaderStream::header_stream`] but peers serving a header at
+    /// `checkpoint`'s block number with a different hash are treated as being
+    /// on another fork and dropped.
+    pub fn header_stream_with_checkpoint(
         self,
         start: BlockNumber,
         stop: BlockNumber,
         reverse: bool,
-    ) -> impl Stream<Item = PeerData<SignedBlockHeader>> {
+        step: std::num::NonZeroU64,
+        checkpoint: Option<(BlockNumber, BlockHash)>,
+    ) -> impl Stream<Item = StreamItem<SignedBlockHeader, SyncError>> {
         let inner = self.inner.clone();
+        let response_timeout = self.response_timeout;
+        let retry_policy = self.retry_policy;
+        let public_key = self.header_public_key;
+        let scores = self.scores.clone();
         let outer = self;
         header_stream::make(
             start,
             stop,
             reverse,
+            step,
+            response_timeout,
+            retry_policy,
+            public_key,
+            checkpoint,
+            scores,
             move || {
                 let outer = outer.clone();
                 async move { outer.get_random_peers().await }
@@ -177,6 +439,132 @@ impl HeaderStream for Client {
             },
         )
     }
+
+    /// Like [`TransactionStream::transaction_stream`] but for the single block
+    /// with the given `hash`.
+    ///
+    /// The hash is first resolved with a header request, which also provides
+    /// the number of transactions to expect. If no peer knows the hash, the
+    /// stream yields an error and ends.
+    pub fn transaction_stream_by_hash(
+        self,
+        hash: BlockHash,
+    ) -> impl Stream<Item = StreamItem<(TransactionData, BlockNumber), SyncError>> {
+        futures::stream::once(async move {
+            let inner = self.inner.clone();
+            let header = header_stream::get_by_hash(
+                hash,
+                self.response_timeout,
+                self.retry_policy,
+                self.header_public_key,
+                self.scores.clone(),
+                || self.get_random_peers(),
+                |peer, request| inner.send_headers_sync_request(peer, request),
+            )
+            .await;
+
+            match header {
+                Ok(header) => {
+                    let BlockHeader {
+                        number,
+                        transaction_count,
+                        ..
+                    } = header.data.header;
+                    self.transaction_stream(
+                        number,
+                        number,
+                        false,
+                        futures::stream::iter([Ok(transaction_count)]),
+                    )
+                    .left_stream()
+                }
+                Err(error) => futures::stream::iter([Err(SyncError::Other(error))]).right_stream(),
+            }
+        })
+        .flatten()
+    }
+
+    /// Like [`TransactionStream::transaction_stream`] but only yields the
+    /// receipts of each block.
+    ///
+    /// Receipts are served along with their transactions, so this requests and
+    /// validates both.
+    pub fn receipt_stream(
+        self,
+        start: BlockNumber,
+        stop: BlockNumber,
+        reverse: bool,
+        transaction_count_stream: impl Stream<Item = anyhow::Result<usize>> + Send + 'static,
+    ) -> impl Stream<Item = StreamItem<(Vec<Receipt>, BlockNumber), SyncError>> {
+        let inner = self.inner.clone();
+        let retry_policy = self.retry_policy;
+        let scores = self.scores.clone();
+        let outer = self;
+        receipt_stream::make(
+            start,
+            stop,
+            reverse,
+            transaction_count_stream,
+            retry_policy,
+            scores,
+            move || {
+                let outer = outer.clone();
+                async move { outer.get_random_peers().await }
+            },
+            move |peer, request| {
+                let inner = inner.clone();
+                async move { inner.send_transactions_sync_request(peer, request).await }
+            },
+        )
+    }
+
+    /// Like [`TransactionStream::transaction_stream`] but yields every
+    /// transaction as soon as it is received, instead of buffering whole
+    /// blocks.
+    ///
+    /// If a peer fails in the middle of a block, the block is requested again
+    /// from the next peer. Its transactions are yielded from the first one
+    /// that was not yielded yet.
+    pub fn transaction_stream_flat(
+        self,
+        start: BlockNumber,
+        stop: BlockNumber,
+        reverse: bool,
+        transaction_count_stream: impl Stream<Item = anyhow::Result<usize>> + Send + 'static,
+    ) -> impl Stream<Item = StreamItem<(BlockNumber, Transaction, Receipt), SyncError>> {
+        let inner = self.inner.clone();
+        let retry_policy = self.retry_policy;
+        let scores = self.scores.clone();
+        let outer = self;
+        transaction_stream::make_flat(
+            start,
+            stop,
+            reverse,
+            transaction_count_stream,
+            retry_policy,
+            scores,
+            move || {
+                let outer = outer.clone();
+                async move { outer.get_random_peers().await }
+            },
+            move |peer, request| {
+                let inner = inner.clone();
+                async move { inner.send_transactions_sync_request(peer, request).await }
+            },
+        )
+    }
+}
+
+impl HeaderStream for Client {
+    fn header_stream(
+        self,
+        start: BlockNumber,
+        stop: BlockNumber,
+        reverse: bool,
+        step: std::num::NonZeroU64,
+    ) -> impl Stream<Item = StreamItem<SignedBlockHeader, SyncError>> {
+        self.header_stream_with_checkpoint(start, stop, reverse, step, None)
+    }
 }
 
 impl TransactionStream for Client {
@@ -184,14 +572,20 @@ impl TransactionStream for Client {
         self,
         start: BlockNumber,
         stop: BlockNumber,
+        reverse: bool,
         transaction_count_stream: impl Stream<Item = anyhow::Result<usize>> + Send + 'static,
-    ) -> impl Stream<Item = StreamItem<(TransactionData, BlockNumber)>> {
+    ) -> impl Stream<Item = StreamItem<(TransactionData, BlockNumber), SyncError>> {
         let inner = self.inner.clone();
+        let retry_policy = self.retry_policy;
+        let scores = self.scores.clone();
         let outer = self;
         transaction_stream::make(
             start,
             stop,
+            reverse,
             transaction_count_stream,
+            retry_policy,
+            scores,
             move || {
                 let outer = outer.clone();
                 async move { outer.get_random_peers().await }
@@ -619,16 +1013,37 @@ impl BlockClient for Client {
 /// Maximum number of blocks to request in a single request
 const MAX_BLOCKS_COUNT: u64 = 500;
 
+fn new_block_header(
+    header: SignedBlockHeader,
+    public_key: PublicKey,
+) -> anyhow::Result<p2p_proto::header::NewBlock> {
+    header
+        .signature
+        .verify(public_key, header.header.hash)
+        .context("Verifying header signature")?;
+
+    Ok(p2p_proto::header::NewBlock::Header(
+        BlockHeadersResponse::Header(Box::new(header.to_dto())),
+    ))
+}
+
 mod header_stream {
     use super::*;
 
+    #[allow(clippy::too_many_arguments)]
     pub fn make<PF, RF>(
         start: BlockNumber,
         stop: BlockNumber,
         reverse: bool,
+        step: std::num::NonZeroU64,
+        response_timeout: Duration,
+        retry_policy: RetryPolicy,
+        public_key: Option<PublicKey>,
+        checkpoint: Option<(BlockNumber, BlockHash)>,
+        scores: PeerScores,
         get_peers: impl Fn() -> PF + Send + 'static,
         send_request: impl Fn(PeerId, BlockHeadersRequest) -> RF + Send + 'static,
-    ) -> impl Stream<Item = PeerData<SignedBlockHeader>>
+    ) -> impl Stream<Item = StreamItem<SignedBlockHeader, SyncError>>
     where
         PF: Future<Output = Vec<PeerId>> + Send,
         RF: Future<Output = anyhow::Result<fmpsc::Receiver<std::io::Result<BlockHeadersResponse>>>>
@@ -636,31 +1051,96 @@ mod header_stream {
     {
         let start: i64 = start.get().try_into().expect("block number <= i64::MAX");
         let stop: i64 = stop.get().try_into().expect("block number <= i64::MAX");
+        let step: i64 = step.get().try_into().expect("step <= i64::MAX");
 
         let (mut start, stop, dir) = match reverse {
             true => (stop, start, Direction::Backward),
             false => (start, stop, Direction::Forward),
         };
 
-        tracing::trace!(?start, ?stop, ?dir, "Streaming headers");
+        tracing::trace!(?start, ?stop, ?dir, ?step, "Streaming headers");
 
         util::make_stream::from_future(move |tx| async move {
+            let mut failed_attempts = 0;
+            // Guards against yielding a header twice when a peer resends headers we
+            // already got from the previous one.
+            let mut last_yielded = None;
+
             // Loop which refreshes peer set once we exhaust it.
             loop {
+                let round_start = start;
+                // Last reason each peer failed for during this pass over the peer set.
+                let mut last_errors = HashMap::new();
+                // Peers which sent headers that could not be converted during this pass.
+                let mut invalid_data = Vec::new();
+
                 'next_peer: for peer in get_peers().await {
-                    let mut responses =
-                        match send_request(peer, make_request(start, stop, dir)).await {
-                            Ok(x) => x,
-                            Err(error) => {
-                                tracing::debug!(%peer, reason=%error, "Headers request failed");
+                    let mut last_response = Instant::now();
+                    let request = make_request(start, stop, dir, step);
+                    let request_start = start;
+                    let mut responses = match send_request(peer, request).await {
+                        Ok(x) => x,
+                        Err(error) => {
+                            tracing::debug!(%peer, reason=%error, "Headers request failed");
+                            last_errors.insert(peer, format!("request failed: {error:#}"));
+                            scores.record_failure(peer);
+                            continue 'next_peer;
+                        }
+                    };
+
+                    loop {
+                        let r = match tokio::time::timeout(response_timeout, responses.next()).await
+                        {
+                            Ok(Some(r)) => r,
+                            Ok(None) => break,
+                            Err(_) => {
+                                tracing::debug!(%peer, "Header stream timed out");
+                                last_errors.insert(peer, "response timed out".to_owned());
+                                scores.record_failure(peer);
                                 continue 'next_peer;
                             }
                         };
 
-                    while let Some(r) = responses.next().await {
-                        match handle_response(peer, r, dir, &mut start, stop, tx.clone()).await {
-                            Action::NextResponse => {}
-                            Action::NextPeer => continue 'next_peer,
+                        match handle_response(
+                            peer,
+                            r,
+                            dir,
+                            step,
+                            public_key,
+                            checkpoint,
+                            &mut start,
+                            stop,
+                            &mut last_yielded,
+                            tx.clone(),
+                        )
+                        .await
+                        {
+                            Action::NextResponse => {
+                                scores.record_success(peer, last_response.elapsed());
+                                last_response = Instant::now();
+                            }
+                            Action::NextPeer(reason) => {
+                                last_errors.insert(peer, reason);
+                                scores.record_failure(peer);
+                                continue 'next_peer;
+                            }
+                            Action::InvalidData(reason) => {
+                                last_errors.insert(peer, reason);
+                                invalid_data.push(peer);
+                                scores.record_failure(peer);
+                                continue 'next_peer;
+                            }
+                            Action::Fin => {
+                                // Requests are capped at `MAX_BLOCKS_COUNT` headers, so a peer
+                                // which served all of them is done rather than failing.
+                                let served = start.abs_diff(request_start) / step.unsigned_abs();
+                                if served < request.iteration.limit {
+                                    last_errors
+                                        .insert(peer, format!("Fin received before block {start}"));
+                                    scores.record_failure(peer);
+                                }
+                                continue 'next_peer;
+                            }
                             Action::TerminateStream => return,
                         }
                     }
@@ -670,20 +1150,273 @@ mod header_stream {
                         return;
                     }
 
-                    // TODO: track how much and how fast this peer responded
-                    // with i.e. don't let them drip feed us etc.
+                    last_errors.insert(peer, "stream closed before Fin".to_owned());
+                }
+
+                if start != round_start {
+                    failed_attempts = 0;
+                    continue;
+                }
+
+                failed_attempts += 1;
+                tracing::warn!(
+                    %start,
+                    %failed_attempts,
+                    errors=?last_errors,
+                    "All peers failed to serve headers"
+                );
+                if failed_attempts >= retry_policy.max_attempts.get() {
+                    tracing::debug!(
+                        %start,
+                        %failed_attempts,
+                        "No peer could serve headers, terminating"
+                    );
+                    let block = BlockNumber::new_or_panic(start.try_into().expect("start >= 0"));
+                    let error = if invalid_data.is_empty() {
+                        SyncError::PeersExhausted { block }
+                    } else {
+                        SyncError::Conversion {
+                            block,
+                            peers: invalid_data,
+                        }
+                    };
+                    _ = tx.send(Err(error)).await;
+                    return;
                 }
             }
         })
     }
 
+    /// Streams disjoint sub-ranges of `chunk_size` headers, each from its own
+    /// [`make`] stream, with up to `fanout` of them in flight at once.
+    ///
+    /// Sub-ranges are yielded in order, so headers are never duplicated and
+    /// arrive in the same order as from [`make`]. The stream ends after the
+    /// first error.
+    #[allow(clippy::too_many_arguments)]
+    pub fn make_concurrent<PF, RF>(
+        start: BlockNumber,
+        stop: BlockNumber,
+        reverse: bool,
+        response_timeout: Duration,
+        retry_policy: RetryPolicy,
+        public_key: Option<PublicKey>,
+        scores: PeerScores,
+        fanout: std::num::NonZeroUsize,
+        chunk_size: std::num::NonZeroU64,
+        get_peers: impl Fn() -> PF + Clone + Send + 'static,
+        send_request: impl Fn(PeerId, BlockHeadersRequest) -> RF + Clone + Send + 'static,
+    ) -> impl Stream<Item = StreamItem<SignedBlockHeader, SyncError>>
+    where
+        PF: Future<Output = Vec<PeerId>> + Send,
+        RF: Future<Output = anyhow::Result<fmpsc::Receiver<std::io::Result<BlockHeadersResponse>>>>
+            + Send,
+    {
+        let chunk_size = chunk_size.get();
+        let mut ranges = (start.get()..=stop.get())
+            .step_by(chunk_size.try_into().expect("chunk size fits in usize"))
+            .map(|first| {
+                let last = first.saturating_add(chunk_size - 1).min(stop.get());
+                (
+                    BlockNumber::new_or_panic(first),
+                    BlockNumber::new_or_panic(last),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if reverse {
+            ranges.reverse();
+        }
+
+        futures::stream::iter(ranges)
+            .map(move |(first, last)| {
+                make(
+                    first,
+                    last,
+                    reverse,
+                    std::num::NonZeroU64::MIN,
+                    response_timeout,
+                    retry_policy,
+                    public_key,
+                    None,
+                    scores.clone(),
+                    get_peers.clone(),
+                    send_request.clone(),
+                )
+                .collect::<Vec<_>>()
+            })
+            .buffered(fanout.get())
+            .flat_map(futures::stream::iter)
+            .scan(false, |failed, item| {
+                if *failed {
+                    return std::future::ready(None);
+                }
+                *failed = item.is_err();
+                std::future::ready(Some(item))
+            })
+    }
+
+    /// Fetches the header of a single `block`, trying peers in order until one
+    /// of them serves it.
+    ///
+    /// Unlike [`make`] no background task or channel is involved. Gives up
+    /// after `retry_policy.max_attempts` passes over the peer set.
+    pub async fn get<PF, RF>(
+        block: BlockNumber,
+        response_timeout: Duration,
+        retry_policy: RetryPolicy,
+        public_key: Option<PublicKey>,
+        scores: PeerScores,
+        get_peers: impl Fn() -> PF,
+        send_request: impl Fn(PeerId, BlockHeadersRequest) -> RF,
+    ) -> anyhow::Result<PeerData<SignedBlockHeader>>
+    where
+        PF: Future<Output = Vec<PeerId>>,
+        RF: Future<Output = anyhow::Result<fmpsc::Receiver<std::io::Result<BlockHeadersResponse>>>>,
+    {
+        fetch(
+            BlockNumberOrHash::Number(block.get()),
+            |header| header.number == block,
+            response_timeout,
+            retry_policy,
+            public_key,
+            scores,
+            get_peers,
+            send_request,
+        )
+        .await
+        .with_context(|| format!("No peer could serve header for block {block}"))
+    }
+
+    /// Like [`get`] but for the block with the given `hash`.
+    pub async fn get_by_hash<PF, RF>(
+        hash: BlockHash,
+        response_timeout: Duration,
+        retry_policy: RetryPolicy,
+        public_key: Option<PublicKey>,
+        scores: PeerScores,
+        get_peers: impl Fn() -> PF,
+        send_request: impl Fn(PeerId, BlockHeadersRequest) -> RF,
+    ) -> anyhow::Result<PeerData<SignedBlockHeader>>
+    where
+        PF: Future<Output = Vec<PeerId>>,
+        RF: Future<Output = anyhow::Result<fmpsc::Receiver<std::io::Result<BlockHeadersResponse>>>>,
+    {
+        fetch(
+            BlockNumberOrHash::Hash(p2p_proto::common::Hash(hash.0)),
+            |header| header.hash == hash,
+            response_timeout,
+            retry_policy,
+            public_key,
+            scores,
+            get_peers,
+            send_request,
+        )
+        .await
+        .with_context(|| format!("No peer could serve header for block {hash}"))
+    }
+
+    /// Requests the header at `block` from each peer in turn until one serves
+    /// a header satisfying `is_expected`.
+    #[allow(clippy::too_many_arguments)]
+    async fn fetch<PF, RF>(
+        block: BlockNumberOrHash,
+        is_expected: impl Fn(&BlockHeader) -> bool,
+        response_timeout: Duration,
+        retry_policy: RetryPolicy,
+        public_key: Option<PublicKey>,
+        scores: PeerScores,
+        get_peers: impl Fn() -> PF,
+        send_request: impl Fn(PeerId, BlockHeadersRequest) -> RF,
+    ) -> Option<PeerData<SignedBlockHeader>>
+    where
+        PF: Future<Output = Vec<PeerId>>,
+        RF: Future<Output = anyhow::Result<fmpsc::Receiver<std::io::Result<BlockHeadersResponse>>>>,
+    {
+        let request = BlockHeadersRequest {
+            iteration: Iteration {
+                start: block,
+                direction: Direction::Forward,
+                limit: 1,
+                step: 1.into(),
+            },
+        };
+
+        for _ in 0..retry_policy.max_attempts.get() {
+            for peer in get_peers().await {
+                let request_start = Instant::now();
+                let mut responses = match send_request(peer, request).await {
+                    Ok(x) => x,
+                    Err(error) => {
+                        tracing::debug!(%peer, reason=%error, "Header request failed");
+                        scores.record_failure(peer);
+                        continue;
+                    }
+                };
+
+                let header = match tokio::time::timeout(response_timeout, responses.next()).await {
+                    Ok(Some(Ok(BlockHeadersResponse::Header(hdr)))) => {
+                        SignedBlockHeader::try_from_dto(*hdr)
+                    }
+                    Ok(Some(Ok(BlockHeadersResponse::Fin))) | Ok(None) => {
+                        tracing::debug!(%peer, ?block, "Peer has no header");
+                        scores.record_failure(peer);
+                        continue;
+                    }
+                    Ok(Some(Err(error))) => {
+                        tracing::debug!(%peer, %error, "Header response failed");
+                        scores.record_failure(peer);
+                        continue;
+                    }
+                    Err(_) => {
+                        tracing::debug!(%peer, "Header request timed out");
+                        scores.record_failure(peer);
+                        continue;
+                    }
+                };
+
+                let header = match header {
+                    Ok(header) if is_expected(&header.header) => header,
+                    Ok(header) => {
+                        tracing::debug!(%peer, expected=?block, actual_number=%header.header.number, actual_hash=%header.header.hash, "Unexpected header");
+                        scores.record_failure(peer);
+                        continue;
+                    }
+                    Err(error) => {
+                        tracing::debug!(%peer, %error, "Invalid header");
+                        scores.record_failure(peer);
+                        continue;
+                    }
+                };
+
+                if let Some(public_key) = public_key {
+                    if let Err(error) = header.signature.verify(public_key, header.header.hash) {
+                        tracing::debug!(%peer, %error, ?block, "Header signature verification failed");
+                        scores.record_failure(peer);
+                        continue;
+                    }
+                }
+
+                scores.record_success(peer, request_start.elapsed());
+                return Some(PeerData::new(peer, header));
+            }
+        }
+
+        None
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn handle_response(
         peer: PeerId,
         signed_header: std::io::Result<BlockHeadersResponse>,
         direction: Direction,
+        step: i64,
+        public_key: Option<PublicKey>,
+        checkpoint: Option<(BlockNumber, BlockHash)>,
         start: &mut i64,
         stop: i64,
-        tx: mpsc::Sender<PeerData<SignedBlockHeader>>,
+        last_yielded: &mut Option<BlockNumber>,
+        tx: mpsc::Sender<StreamItem<SignedBlockHeader, SyncError>>,
     ) -> Action {
         match signed_header {
             Ok(BlockHeadersResponse::Header(hdr)) => match SignedBlockHeader::try_from_dto(*hdr) {
@@ -693,14 +1426,45 @@ mod header_stream {
                         return Action::TerminateStream;
                     }
 
-                    if tx.send(PeerData::new(peer, hdr)).await.is_err() {
+                    let already_yielded = match (direction, *last_yielded) {
+                        (_, None) => false,
+                        (Direction::Forward, Some(last)) => hdr.header.number <= last,
+                        (Direction::Backward, Some(last)) => hdr.header.number >= last,
+                    };
+                    if already_yielded {
+                        tracing::debug!(%peer, block_number=%hdr.header.number, "Skipping already yielded header");
+                        return Action::NextResponse;
+                    }
+
+                    if let Some((number, hash)) = checkpoint {
+                        if hdr.header.number == number && hdr.header.hash != hash {
+                            tracing::debug!(%peer, block_number=%number, expected=%hash, actual=%hdr.header.hash, "Header does not match checkpoint");
+                            return Action::NextPeer(format!(
+                                "header {number} does not match checkpoint"
+                            ));
+                        }
+                    }
+
+                    if let Some(public_key) = public_key {
+                        if let Err(error) = hdr.signature.verify(public_key, hdr.header.hash) {
+                            tracing::debug!(%peer, %error, block_number=%hdr.header.number, "Header signature verification failed");
+                            return Action::NextPeer(format!(
+                                "invalid signature for block {}: {error}",
+                                hdr.header.number
+                            ));
+                        }
+                    }
+
+                    let number = hdr.header.number;
+                    if tx.send(Ok(PeerData::new(peer, hdr))).await.is_err() {
                         tracing::debug!(%peer, "Failed to yield to stream, terminating");
                         return Action::TerminateStream;
                     }
+                    *last_yielded = Some(number);
 
                     *start = match direction {
-                        Direction::Forward => *start + 1,
-                        Direction::Backward => *start - 1,
+                        Direction::Forward => *start + step,
+                        Direction::Backward => *start - step,
                     };
 
                     Action::NextResponse
@@ -711,7 +1475,7 @@ mod header_stream {
                         return Action::TerminateStream;
                     }
 
-                    Action::NextPeer
+                    Action::InvalidData(format!("invalid header: {error:#}"))
                 }
             },
             Ok(BlockHeadersResponse::Fin) => {
@@ -720,7 +1484,7 @@ mod header_stream {
                     return Action::TerminateStream;
                 }
 
-                Action::NextPeer
+                Action::Fin
             }
             Err(error) => {
                 tracing::debug!(%peer, %error, "Header stream failed, terminating");
@@ -728,13 +1492,13 @@ mod header_stream {
                     return Action::TerminateStream;
                 }
 
-                Action::NextPeer
+                Action::NextPeer(format!("response failed: {error}"))
             }
         }
     }
 
-    fn make_request(start: i64, stop: i64, dir: Direction) -> BlockHeadersRequest {
-        let limit = start.abs_diff(stop) + 1;
+    fn make_request(start: i64, stop: i64, dir: Direction, step: i64) -> BlockHeadersRequest {
+        let limit = start.abs_diff(stop) / step.unsigned_abs() + 1;
         let limit = limit.min(MAX_BLOCKS_COUNT);
 
         BlockHeadersRequest {
@@ -742,14 +1506,20 @@ mod header_stream {
                 start: u64::try_from(start).expect("start >= 0").into(),
                 direction: dir,
                 limit,
-                step: 1.into(),
+                step: step.unsigned_abs().into(),
             },
         }
     }
 
     enum Action {
         NextResponse,
-        NextPeer,
+        /// Carries the reason the peer is abandoned.
+        NextPeer(String),
+        /// Like [`Action::NextPeer`], for a header which could not be converted
+        /// from its DTO.
+        InvalidData(String),
+        /// The peer sent Fin before the whole range was streamed.
+        Fin,
         TerminateStream,
     }
 
@@ -761,22 +1531,253 @@ mod header_stream {
     }
 }
 
+mod block_stream {
+    use super::*;
+
+    pub fn make<TF, SF>(
+        headers: impl Stream<Item = StreamItem<SignedBlockHeader, SyncError>> + Send + 'static,
+        retry_policy: RetryPolicy,
+        get_transactions: impl Fn(BlockNumber, usize) -> TF + Send + Sync + 'static,
+        get_state_diff: impl Fn(BlockNumber, u64) -> SF + Send + Sync + 'static,
+    ) -> impl Stream<Item = StreamItem<FullBlock>>
+    where
+        TF: Future<Output = anyhow::Result<Option<PeerData<TransactionData>>>> + Send,
+        SF: Future<Output = anyhow::Result<Option<PeerData<StateUpdateData>>>> + Send,
+    {
+        util::make_stream::from_future(move |tx| async move {
+            let mut headers = std::pin::pin!(headers);
+
+            while let Some(header) = headers.next().await {
+                let PeerData { peer, data: header } = match header {
+                    Ok(header) => header,
+                    Err(error) => {
+                        _ = tx.send(Err(error.into())).await;
+                        return;
+                    }
+                };
+                let block = header.header.number;
+
+                let transactions = fetch(retry_policy, block, "transactions", || {
+                    get_transactions(block, header.header.transaction_count)
+                })
+                .await;
+                let transactions = match transactions {
+                    Ok(transactions) => transactions,
+                    Err(error) => {
+                        _ = tx.send(Err(error)).await;
+                        return;
+                    }
+                };
+
+                let state_diff = fetch(retry_policy, block, "state diff", || {
+                    get_state_diff(block, header.header.state_diff_length)
+                })
+                .await;
+                let state_diff = match state_diff {
+                    Ok(state_diff) => state_diff,
+                    Err(error) => {
+                        _ = tx.send(Err(error)).await;
+                        return;
+                    }
+                };
+
+                let block = FullBlock {
+                    header,
+                    transactions,
+                    state_diff,
+                };
+                if tx.send(Ok(PeerData::new(peer, block))).await.is_err() {
+                    tracing::debug!("Failed to yield to stream, terminating");
+                    return;
+                }
+            }
+        })
+    }
+
+    /// Retries `get` until it yields data or the attempts run out.
+    async fn fetch<T, F>(
+        retry_policy: RetryPolicy,
+        block: BlockNumber,
+        component: &str,
+        get: impl Fn() -> F,
+    ) -> anyhow::Result<T>
+    where
+        F: Future<Output = anyhow::Result<Option<PeerData<T>>>>,
+    {
+        for attempt in 1..=retry_policy.max_attempts.get() {
+            match get().await {
+                Ok(Some(data)) => return Ok(data.data),
+                Ok(None) => {
+                    tracing::debug!(%block, %attempt, "No peer could serve block {component}")
+                }
+                Err(error) => {
+                    tracing::debug!(%block, %attempt, %error, "Fetching block {component} failed")
+                }
+            }
+        }
+
+        anyhow::bail!("Failed to fetch {component} for block {block}")
+    }
+}
+
 mod transaction_stream {
     use super::*;
 
     pub fn make<PF, RF>(
-        mut start: BlockNumber,
+        start: BlockNumber,
+        stop: BlockNumber,
+        reverse: bool,
+        counts_stream: impl Stream<Item = anyhow::Result<usize>> + Send + 'static,
+        retry_policy: RetryPolicy,
+        scores: PeerScores,
+        get_peers: impl Fn() -> PF + Send + 'static,
+        send_request: impl Fn(PeerId, TransactionsRequest) -> RF + Send + 'static,
+    ) -> impl Stream<Item = StreamItem<(TransactionData, BlockNumber), SyncError>>
+    where
+        PF: Future<Output = Vec<PeerId>> + Send,
+        RF: Future<Output = anyhow::Result<fmpsc::Receiver<std::io::Result<TransactionsResponse>>>>
+            + Send,
+    {
+        make_items(
+            start,
+            stop,
+            reverse,
+            counts_stream,
+            retry_policy,
+            scores,
+            get_peers,
+            send_request,
+        )
+        .scan(Vec::new(), |transactions, item| {
+            let block = match item {
+                Ok(PeerData {
+                    peer: _,
+                    data:
+                        Item::Transaction {
+                            index,
+                            transaction,
+                            receipt,
+                            ..
+                        },
+                }) => {
+                    // The block is resent from its start by the next peer, drop what we got
+                    // from the previous one.
+                    if index == 0 {
+                        transactions.clear();
+                    }
+                    transactions.push((transaction, receipt));
+                    None
+                }
+                Ok(PeerData {
+                    peer,
+                    data: Item::BlockEnd(block),
+                }) => Some(Ok(PeerData::new(
+                    peer,
+                    (std::mem::take(transactions), block),
+                ))),
+                Err(error) => Some(Err(error)),
+            };
+            std::future::ready(Some(block))
+        })
+        .filter_map(std::future::ready)
+    }
+
+    /// See [`Client::transaction_stream_flat`].
+    pub fn make_flat<PF, RF>(
+        start: BlockNumber,
         stop: BlockNumber,
+        reverse: bool,
         counts_stream: impl Stream<Item = anyhow::Result<usize>> + Send + 'static,
+        retry_policy: RetryPolicy,
+        scores: PeerScores,
         get_peers: impl Fn() -> PF + Send + 'static,
         send_request: impl Fn(PeerId, TransactionsRequest) -> RF + Send + 'static,
-    ) -> impl Stream<Item = StreamItem<(TransactionData, BlockNumber)>>
+    ) -> impl Stream<Item = StreamItem<(BlockNumber, Transaction, Receipt), SyncError>>
     where
         PF: Future<Output = Vec<PeerId>> + Send,
         RF: Future<Output = anyhow::Result<fmpsc::Receiver<std::io::Result<TransactionsResponse>>>>
             + Send,
     {
-        tracing::trace!(?start, ?stop, "Streaming Transactions");
+        make_items(
+            start,
+            stop,
+            reverse,
+            counts_stream,
+            retry_policy,
+            scores,
+            get_peers,
+            send_request,
+        )
+        // The block and index of the next transaction to yield.
+        .scan(None, |next, item| {
+            let transaction = match item {
+                Ok(PeerData {
+                    peer,
+                    data:
+                        Item::Transaction {
+                            block,
+                            index,
+                            transaction,
+                            receipt,
+                        },
+                }) => {
+                    // Skip the transactions the next peer resends after the previous one
+                    // failed in the middle of the block.
+                    if next.is_some_and(|(next_block, next_index)| {
+                        next_block == block && index < next_index
+                    }) {
+                        None
+                    } else {
+                        *next = Some((block, index + 1));
+                        Some(Ok(PeerData::new(peer, (block, transaction, receipt))))
+                    }
+                }
+                Ok(PeerData {
+                    data: Item::BlockEnd(_),
+                    ..
+                }) => None,
+                Err(error) => Some(Err(error)),
+            };
+            std::future::ready(Some(transaction))
+        })
+        .filter_map(std::future::ready)
+    }
+
+    /// Items of the stream underlying both [`make`] and [`make_flat`].
+    enum Item {
+        /// The transaction at `index` within `block`. If a peer fails in the
+        /// middle of a block, the next peer sends the block again from index 0.
+        Transaction {
+            block: BlockNumber,
+            index: usize,
+            transaction: Transaction,
+            receipt: Receipt,
+        },
+        /// All transactions of the block have been received from the peer.
+        BlockEnd(BlockNumber),
+    }
+
+    fn make_items<PF, RF>(
+        start: BlockNumber,
+        stop: BlockNumber,
+        reverse: bool,
+        counts_stream: impl Stream<Item = anyhow::Result<usize>> + Send + 'static,
+        retry_policy: RetryPolicy,
+        scores: PeerScores,
+        get_peers: impl Fn() -> PF + Send + 'static,
+        send_request: impl Fn(PeerId, TransactionsRequest) -> RF + Send + 'static,
+    ) -> impl Stream<Item = StreamItem<Item, SyncError>>
+    where
+        PF: Future<Output = Vec<PeerId>> + Send,
+        RF: Future<Output = anyhow::Result<fmpsc::Receiver<std::io::Result<TransactionsResponse>>>>
+            + Send,
+    {
+        let (mut start, stop, dir) = match reverse {
+            true => (stop, start, Direction::Backward),
+            false => (start, stop, Direction::Forward),
+        };
+
+        tracing::trace!(?start, ?stop, ?dir, "Streaming Transactions");
 
         util::make_stream::from_future(move |tx| async move {
             let mut expected_transaction_counts_stream = Box::pin(counts_stream);
@@ -784,74 +1785,150 @@ mod transaction_stream {
             let cnt = match try_next(&mut expected_transaction_counts_stream).await {
                 Ok(x) => x,
                 Err(e) => {
-                    _ = tx.send(Err(e)).await;
+                    _ = tx.send(Err(SyncError::Other(e))).await;
                     return;
                 }
             };
 
             // Transaction counter for the currently received block
             let mut progress = BlockProgress::new(cnt);
+            let mut failed_attempts = 0;
 
             // Loop which refreshes peer set once we exhaust it.
             loop {
+                let round_start = start;
+                // Peers which sent transactions that could not be converted during this pass.
+                let mut invalid_data = Vec::new();
+
                 'next_peer: for peer in get_peers().await {
-                    let mut responses = match send_request(peer, make_request(start, stop)).await {
+                    let mut last_response = Instant::now();
+                    let request = make_request(start, stop, dir);
+                    let mut responses = match send_request(peer, request).await {
                         Ok(x) => x,
                         Err(error) => {
                             tracing::debug!(%peer, reason=%error, "Transactions request failed");
+                            scores.record_failure(peer);
                             continue 'next_peer;
                         }
                     };
                     // If the previous peer failed to provide the entire block we need to start over
                     progress.rollback();
+                    let mut blocks_served = 0;
 
-                    while start <= stop {
+                    while !done(dir, start, stop) {
                         tracing::trace!(block_number=%start, num_responses=%progress.get(), "Expecting");
-                        let mut transactions = Vec::new();
+                        let mut received = 0;
 
                         while progress.get() > 0 {
-                            match responses.next().await {
-                                Some(r) => {
-                                    let i = into_idx(transactions.len());
-                                    match handle_response(peer, r, i) {
-                                        Some(x) => transactions.push(x),
-                                        None => continue 'next_peer,
+                            let Some(response) = responses.next().await else {
+                                scores.record_failure(peer);
+                                continue 'next_peer;
+                            };
+                            let (transaction, receipt) =
+                                match handle_response(peer, response, into_idx(received)) {
+                                    Ok(x) => x,
+                                    Err(Failure::InvalidData) => {
+                                        invalid_data.push(peer);
+                                        scores.record_failure(peer);
+                                        continue 'next_peer;
                                     }
-                                }
-                                None => continue 'next_peer,
-                            }
+                                    Err(Failure::Fin | Failure::Response) => {
+                                        scores.record_failure(peer);
+                                        continue 'next_peer;
+                                    }
+                                };
+                            scores.record_success(peer, last_response.elapsed());
+                            last_response = Instant::now();
                             *progress.as_mut() -= 1;
+
+                            let item = Item::Transaction {
+                                block: start,
+                                index: received,
+                                transaction,
+                                receipt,
+                            };
+                            received += 1;
+                            if tx.send(Ok(PeerData::new(peer, item))).await.is_err() {
+                                tracing::debug!(%peer, "Failed to yield to stream, terminating");
+                                return;
+                            }
                         }
 
-                        if yield_block(
-                            peer,
-                            &mut progress,
-                            &mut expected_transaction_counts_stream,
-                            transactions,
-                            &mut start,
-                            stop,
-                            tx.clone(),
-                        )
-                        .await
+                        tracing::trace!(block_number=%start, "All transactions received for block");
+                        if tx
+                            .send(Ok(PeerData::new(peer, Item::BlockEnd(start))))
+                            .await
+                            .is_err()
                         {
+                            tracing::debug!(%peer, "Failed to yield to stream, terminating");
                             return;
                         }
+
+                        if start == stop {
+                            return;
+                        }
+
+                        start = match dir {
+                            Direction::Forward => start + 1,
+                            Direction::Backward => start.parent().unwrap_or_default(),
+                        };
+                        progress = match try_next(&mut expected_transaction_counts_stream).await {
+                            Ok(x) => BlockProgress::new(x),
+                            Err(e) => {
+                                _ = tx.send(Err(SyncError::Other(e))).await;
+                                return;
+                            }
+                        };
+
+                        // Requests are capped at `MAX_BLOCKS_COUNT` blocks, so a peer which
+                        // served all of them is done rather than failing.
+                        blocks_served += 1;
+                        if blocks_served == request.iteration.limit {
+                            continue 'next_peer;
+                        }
                     }
 
                     return;
                 }
+
+                if start != round_start {
+                    failed_attempts = 0;
+                    continue;
+                }
+
+                failed_attempts += 1;
+                tracing::debug!(%start, %failed_attempts, "All peers failed to serve transactions");
+                if failed_attempts >= retry_policy.max_attempts.get() {
+                    let error = if invalid_data.is_empty() {
+                        SyncError::PeersExhausted { block: start }
+                    } else {
+                        SyncError::Conversion {
+                            block: start,
+                            peers: invalid_data,
+                        }
+                    };
+                    _ = tx.send(Err(error)).await;
+                    return;
+                }
             }
         })
     }
 
-    /// ### Important
-    ///
-    /// Return None if the caller should move to the next peer
+    /// Why the caller should move to the next peer.
+    enum Failure {
+        /// The peer will not send more blocks.
+        Fin,
+        /// The transaction or receipt could not be converted from its DTO.
+        InvalidData,
+        /// The response stream failed.
+        Response,
+    }
+
     fn handle_response(
         peer: PeerId,
         response: std::io::Result<TransactionsResponse>,
         txn_idx: TransactionIndex,
-    ) -> Option<(Transaction, Receipt)> {
+    ) -> Result<(Transaction, Receipt), Failure> {
         match response {
             Ok(TransactionsResponse::TransactionWithReceipt(TransactionWithReceipt {
                 transaction,
@@ -861,25 +1938,21 @@ mod transaction_stream {
                     Transaction::try_from_dto(transaction),
                     Receipt::try_from((receipt, txn_idx)),
                 ) {
-                    Some((t, r))
+                    Ok((t, r))
                 } else {
-                    // TODO punish the peer
                     tracing::debug!(%peer, "Transaction or receipt failed to parse");
-                    None
+                    Err(Failure::InvalidData)
                 }
             }
-            Ok(TransactionsResponse::Fin) => {
-                // This peer will not give us more blocks, move to the next peer
-                None
-            }
+            Ok(TransactionsResponse::Fin) => Err(Failure::Fin),
             Err(error) => {
                 tracing::debug!(%peer, %error, "Transaction response stream failed");
-                None
+                Err(Failure::Response)
             }
         }
     }
 
-    fn make_request(start: BlockNumber, stop: BlockNumber) -> TransactionsRequest {
+    fn make_request(start: BlockNumber, stop: BlockNumber, dir: Direction) -> TransactionsRequest {
         let start = start.get();
         let stop = stop.get();
         let limit = start.abs_diff(stop) + 1;
@@ -888,59 +1961,62 @@ mod transaction_stream {
         TransactionsRequest {
             iteration: Iteration {
                 start: start.into(),
-                direction: Direction::Forward,
+                direction: dir,
                 limit,
                 step: 1.into(),
             },
         }
     }
 
+    fn done(direction: Direction, start: BlockNumber, stop: BlockNumber) -> bool {
+        match direction {
+            Direction::Forward => start > stop,
+            Direction::Backward => start < stop,
+        }
+    }
+
     fn into_idx(len: usize) -> TransactionIndex {
         TransactionIndex::new_or_panic(len.try_into().expect("ptr size is 64bits"))
     }
+}
 
-    /// ### Important
-    ///
-    /// Returns true if the stream should be terminated
-    async fn yield_block(
-        peer: PeerId,
-        progress: &mut BlockProgress,
-        count_stream: &mut (impl Stream<Item = anyhow::Result<usize>> + Unpin + Send + 'static),
-        transactions: Vec<(Transaction, Receipt)>,
-        start: &mut BlockNumber,
-        stop: BlockNumber,
-        tx: mpsc::Sender<StreamItem<(TransactionData, BlockNumber)>>,
-    ) -> bool {
-        tracing::trace!(block_number=%start, "All transactions received for block");
-
-        if tx
-            .send(Ok(PeerData::new(peer, (transactions, *start))))
-            .await
-            .is_err()
-        {
-            tracing::debug!(%peer, "Failed to yield to stream, terminating");
-            return true;
-        }
-
-        if *start == stop {
-            return true;
-        }
-
-        *start += 1;
-
-        let x = match try_next(count_stream).await {
-            Ok(x) => x,
-            Err(e) => {
-                _ = tx.send(Err(e)).await;
-                return true;
-            }
-        };
-
-        *progress = BlockProgress::new(x);
-
-        tracing::trace!(block_number=%start, num_responses=%progress.get(), "Expecting");
+mod receipt_stream {
+    use super::*;
 
-        false
+    pub fn make<PF, RF>(
+        start: BlockNumber,
+        stop: BlockNumber,
+        reverse: bool,
+        counts_stream: impl Stream<Item = anyhow::Result<usize>> + Send + 'static,
+        retry_policy: RetryPolicy,
+        scores: PeerScores,
+        get_peers: impl Fn() -> PF + Send + 'static,
+        send_request: impl Fn(PeerId, TransactionsRequest) -> RF + Send + 'static,
+    ) -> impl Stream<Item = StreamItem<(Vec<Receipt>, BlockNumber), SyncError>>
+    where
+        PF: Future<Output = Vec<PeerId>> + Send,
+        RF: Future<Output = anyhow::Result<fmpsc::Receiver<std::io::Result<TransactionsResponse>>>>
+            + Send,
+    {
+        transaction_stream::make(
+            start,
+            stop,
+            reverse,
+            counts_stream,
+            retry_policy,
+            scores,
+            get_peers,
+            send_request,
+        )
+        .map_ok(|data| {
+            data.map(|(transactions, block_number)| {
+                let receipts = transactions
+                    .into_iter()
+                    .map(|(_, receipt)| receipt)
+                    .collect();
+                (receipts, block_number)
+            })
+        })
     }
 }
 
@@ -1604,6 +2680,124 @@ impl AsMut<usize> for BlockProgress {
     }
 }
 
+/// How well a peer has served sync requests so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PeerScore {
+    /// Number of responses successfully received.
+    pub successes: u64,
+    /// Number of times the peer failed a request or stopped serving it early.
+    pub failures: u64,
+    /// Moving average of the time between responses.
+    pub avg_latency: Option<Duration>,
+}
+
+impl PeerScore {
+    /// Weight of the latest latency sample in [`Self::avg_latency`].
+    const LATENCY_WEIGHT: f64 = 0.2;
+
+    fn record_success(&mut self, latency: Duration) {
+        self.successes += 1;
+        self.avg_latency = Some(match self.avg_latency {
+            Some(avg) => {
+                avg.mul_f64(1.0 - Self::LATENCY_WEIGHT) + latency.mul_f64(Self::LATENCY_WEIGHT)
+            }
+            None => latency,
+        });
+    }
+
+    fn record_failure(&mut self) {
+        self.failures += 1;
+    }
+
+    /// Higher is better. Peers without any history score 0.5.
+    pub fn value(&self) -> f64 {
+        // Smoothed so that a single success or failure doesn't dominate.
+        let success_rate =
+            (self.successes as f64 + 1.0) / ((self.successes + self.failures) as f64 + 2.0);
+        let latency = self.avg_latency.unwrap_or_default().as_secs_f64();
+
+        success_rate / (1.0 + latency)
+    }
+}
+
+/// Scores of the peers that served sync requests, shared by all streams of a
+/// [`Client`].
+#[derive(Clone, Debug)]
+struct PeerScores {
+    scores: Arc<Mutex<HashMap<PeerId, PeerScore>>>,
+    /// Source of the randomization in [`Self::rank`].
+    rng: Arc<Mutex<rand::rngs::StdRng>>,
+}
+
+impl Default for PeerScores {
+    fn default() -> Self {
+        use rand::SeedableRng;
+
+        Self {
+            scores: Default::default(),
+            rng: Arc::new(Mutex::new(rand::rngs::StdRng::from_entropy())),
+        }
+    }
+}
+
+impl PeerScores {
+    /// Ranks peers reproducibly, for a given `seed` and sequence of calls.
+    #[cfg(test)]
+    fn with_seed(seed: u64) -> Self {
+        use rand::SeedableRng;
+
+        Self {
+            scores: Default::default(),
+            rng: Arc::new(Mutex::new(rand::rngs::StdRng::seed_from_u64(seed))),
+        }
+    }
+
+    fn record_success(&self, peer: PeerId, latency: Duration) {
+        self.scores
+            .lock()
+            .unwrap()
+            .entry(peer)
+            .or_default()
+            .record_success(latency);
+    }
+
+    fn record_failure(&self, peer: PeerId) {
+        self.scores
+            .lock()
+            .unwrap()
+            .entry(peer)
+            .or_default()
+            .record_failure();
+    }
+
+    fn snapshot(&self) -> HashMap<PeerId, PeerScore> {
+        self.scores.lock().unwrap().clone()
+    }
+
+    /// Orders peers by score, best first.
+    ///
+    /// Each score is scaled by a random factor in `[0.5, 1.0]` so that lower
+    /// ranked peers are still tried first occasionally and are not starved.
+    fn rank(&self, peers: &mut [PeerId]) {
+        use rand::Rng;
+
+        let mut rng = self.rng.lock().unwrap();
+        let scores = self.scores.lock().unwrap();
+        let mut ranked = peers
+            .iter()
+            .map(|peer| {
+                let score = scores.get(peer).copied().unwrap_or_default().value();
+                (score * rng.gen_range(0.5..=1.0), *peer)
+            })
+            .collect::<Vec<_>>();
+        ranked.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        for (dst, (_, peer)) in peers.iter_mut().zip(ranked) {
+            *dst = peer;
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 struct Decaying<T> {
     data: T,
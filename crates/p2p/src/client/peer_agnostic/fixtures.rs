@@ -37,7 +37,7 @@ use tagged::Tagged;
 use tagged_debug_derive::TaggedDebug;
 use tokio::sync::Mutex;
 
-use super::ClassDefinition;
+use super::{ClassDefinition, RetryPolicy};
 use crate::client::conv::{CairoDefinition, SierraDefinition, ToDto, TryFromDto};
 use crate::client::peer_agnostic::Receipt;
 
@@ -94,6 +94,11 @@ impl TestTxn {
     }
 }
 
+/// Gives up after the first pass over the peer set that yields no data.
+pub const SINGLE_ATTEMPT: RetryPolicy = RetryPolicy {
+    max_attempts: std::num::NonZeroUsize::MIN,
+};
+
 pub fn peer(tag: i32) -> TestPeer {
     tagged::init();
     Tagged::<TestPeer>::get(format!("peer {tag}"), || TestPeer(PeerId::random()))
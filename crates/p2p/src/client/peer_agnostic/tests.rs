@@ -106,6 +106,34 @@ use crate::client::peer_agnostic::fixtures::*;
     ],
     vec![(peer(1), hdr(11))]
 )]
+#[case::replayed_header_is_skipped(
+    2,
+    vec![
+        // First peer stops after block 0
+        Ok((peer(0), vec![hdr_resp(14)])),
+        // Second peer resends block 0 before block 1
+        Ok((peer(1), vec![hdr_resp(14), hdr_resp(15), HdrFin]))
+    ],
+    vec![
+        (peer(0), hdr(14)), // block 0
+        (peer(1), hdr(15))  // block 1
+    ]
+)]
+#[case::header_two_blocks_back_is_skipped(
+    4,
+    vec![
+        // First peer stops after block 2
+        Ok((peer(0), vec![hdr_resp(16), hdr_resp(17), hdr_resp(18)])),
+        // Second peer resends block 0 before block 3
+        Ok((peer(1), vec![hdr_resp(16), hdr_resp(19), HdrFin]))
+    ],
+    vec![
+        (peer(0), hdr(16)), // block 0
+        (peer(0), hdr(17)), // block 1
+        (peer(0), hdr(18)), // block 2
+        (peer(1), hdr(19))  // block 3
+    ]
+)]
 #[test_log::test(tokio::test)]
 async fn make_header_stream(
     #[case] num_blocks: usize,
@@ -113,7 +141,26 @@ async fn make_header_stream(
     #[case] expected_stream: Vec<(TestPeer, SignedBlockHeader)>,
 ) {
     for (reverse, direction) in [(false, "forward"), (true, "backward")] {
-        let (peers, responses) = unzip_fixtures(responses.clone());
+        let (responses, expected_stream) = if reverse {
+            let responses = responses
+                .iter()
+                .cloned()
+                .map(|r| {
+                    r.map(|(peer, responses)| {
+                        (peer, responses.into_iter().map(mirror_hdr_resp).collect())
+                    })
+                })
+                .collect();
+            let expected_stream = expected_stream
+                .iter()
+                .cloned()
+                .map(|(peer, header)| (peer, mirror_hdr(header)))
+                .collect();
+            (responses, expected_stream)
+        } else {
+            (responses.clone(), expected_stream.clone())
+        };
+        let (peers, responses) = unzip_fixtures(responses);
         let get_peers = move || {
             let peers = peers.clone();
             async move { peers }
@@ -125,15 +172,554 @@ async fn make_header_stream(
         let start = BlockNumber::GENESIS;
         let stop = start + (num_blocks - 1) as u64;
 
-        let actual = super::header_stream::make(start, stop, reverse, get_peers, send_request)
-            .map(|x| (TestPeer(x.peer), x.data))
-            .collect::<Vec<_>>()
-            .await;
+        let actual = super::header_stream::make(
+            start,
+            stop,
+            reverse,
+            std::num::NonZeroU64::MIN,
+            Duration::from_secs(10),
+            SINGLE_ATTEMPT,
+            None,
+            None,
+            PeerScores::default(),
+            get_peers,
+            send_request,
+        )
+        .map_ok(|x| (TestPeer(x.peer), x.data))
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
 
         pretty_assertions_sorted::assert_eq!(actual, expected_stream, "Direction: {}", direction);
     }
 }
 
+/// Reverses the order of the fixture header numbers, so that headers listed in
+/// ascending order for a forward stream are in descending order for a backward
+/// one.
+fn mirror_hdr(header: SignedBlockHeader) -> SignedBlockHeader {
+    SignedBlockHeader {
+        header: BlockHeader {
+            number: BlockNumber::new_or_panic(1000 - header.header.number.get()),
+            ..header.header
+        },
+        ..header
+    }
+}
+
+fn mirror_hdr_resp(response: BlockHeadersResponse) -> BlockHeadersResponse {
+    match response {
+        BlockHeadersResponse::Header(header) => {
+            let header = SignedBlockHeader::try_from_dto(*header).unwrap();
+            BlockHeadersResponse::Header(Box::new(mirror_hdr(header).to_dto()))
+        }
+        HdrFin => HdrFin,
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn make_header_stream_skips_stalled_peer() {
+    let stalled = peer(0).0;
+    let responsive = peer(1).0;
+    let get_peers = move || async move { vec![stalled, responsive] };
+
+    // Keeps the stalled peer's response channel open for the whole test.
+    let stalled_sender = Arc::new(std::sync::Mutex::new(None));
+    let send_request = {
+        let stalled_sender = stalled_sender.clone();
+        move |peer: PeerId, _: BlockHeadersRequest| {
+            let stalled_sender = stalled_sender.clone();
+            async move {
+                let (mut tx, rx) = fmpsc::channel::<std::io::Result<BlockHeadersResponse>>(2);
+                if peer == stalled {
+                    // Never yields a response.
+                    *stalled_sender.lock().unwrap() = Some(tx);
+                } else {
+                    tx.try_send(Ok(hdr_resp(12))).unwrap();
+                    tx.try_send(Ok(HdrFin)).unwrap();
+                }
+                anyhow::Ok(rx)
+            }
+        }
+    };
+
+    let actual = super::header_stream::make(
+        BlockNumber::GENESIS,
+        BlockNumber::GENESIS,
+        false,
+        std::num::NonZeroU64::MIN,
+        Duration::from_secs(10),
+        SINGLE_ATTEMPT,
+        None,
+        None,
+        PeerScores::default(),
+        get_peers,
+        send_request,
+    )
+    .map_ok(|x| (TestPeer(x.peer), x.data))
+    .try_collect::<Vec<_>>()
+    .await
+    .unwrap();
+
+    pretty_assertions_sorted::assert_eq!(actual, vec![(TestPeer(responsive), hdr(12))]);
+}
+
+#[test_log::test(tokio::test)]
+async fn make_header_stream_fails_when_all_peers_fail() {
+    for reverse in [false, true] {
+        let (peers, responses) = unzip_fixtures(vec![
+            Err(peer(0)),
+            Ok((peer(1), vec![HdrFin])),
+            Ok((peer(2), vec![])),
+        ]);
+        let get_peers = move || {
+            let peers = peers.clone();
+            async move { peers }
+        };
+        let send_request = move |_: PeerId, _: BlockHeadersRequest| {
+            let responses = responses.clone();
+            async move { send_request(responses).await }
+        };
+
+        let actual = super::header_stream::make(
+            BlockNumber::GENESIS,
+            BlockNumber::GENESIS + 1,
+            reverse,
+            std::num::NonZeroU64::MIN,
+            Duration::from_secs(10),
+            SINGLE_ATTEMPT,
+            None,
+            None,
+            PeerScores::default(),
+            get_peers,
+            send_request,
+        )
+        .collect::<Vec<_>>()
+        .await;
+
+        let first = if reverse {
+            BlockNumber::GENESIS + 1
+        } else {
+            BlockNumber::GENESIS
+        };
+        assert_eq!(actual.len(), 1);
+        assert!(
+            matches!(actual[0], Err(SyncError::PeersExhausted { block }) if block == first),
+            "{actual:?}"
+        );
+    }
+}
+
+#[rstest]
+#[case::forward(false, 0, vec![0, 2, 4])]
+// Stepping back from block 5 would go below genesis after block 1.
+#[case::backward(true, 5, vec![5, 3, 1])]
+#[test_log::test(tokio::test)]
+async fn make_header_stream_with_step(
+    #[case] reverse: bool,
+    #[case] first: u64,
+    #[case] expected: Vec<i32>,
+) {
+    let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let get_peers = move || async move { vec![peer(0).0] };
+    let send_request = {
+        let requests = requests.clone();
+        let expected = expected.clone();
+        move |_: PeerId, request: BlockHeadersRequest| {
+            requests.lock().unwrap().push(request.iteration);
+            let expected = expected.clone();
+            async move {
+                let (mut tx, rx) = fmpsc::channel::<std::io::Result<BlockHeadersResponse>>(4);
+                for tag in expected {
+                    tx.try_send(Ok(hdr_resp(tag))).unwrap();
+                }
+                tx.try_send(Ok(HdrFin)).unwrap();
+                anyhow::Ok(rx)
+            }
+        }
+    };
+
+    let actual = super::header_stream::make(
+        BlockNumber::GENESIS,
+        BlockNumber::new_or_panic(5),
+        reverse,
+        std::num::NonZeroU64::new(2).unwrap(),
+        Duration::from_secs(10),
+        SINGLE_ATTEMPT,
+        None,
+        None,
+        PeerScores::default(),
+        get_peers,
+        send_request,
+    )
+    .map_ok(|x| x.data)
+    .try_collect::<Vec<_>>()
+    .await
+    .unwrap();
+
+    let expected_headers = expected.into_iter().map(hdr).collect::<Vec<_>>();
+    pretty_assertions_sorted::assert_eq!(actual, expected_headers);
+    pretty_assertions_sorted::assert_eq!(
+        *requests.lock().unwrap(),
+        vec![Iteration {
+            start: first.into(),
+            direction: if reverse {
+                Direction::Backward
+            } else {
+                Direction::Forward
+            },
+            limit: 3,
+            step: 2.into(),
+        }]
+    );
+}
+
+#[test_log::test(tokio::test)]
+async fn make_header_stream_gives_up_after_max_attempts() {
+    let requests = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let get_peers = move || async move { vec![peer(0).0, peer(1).0] };
+    let send_request = {
+        let requests = requests.clone();
+        move |_: PeerId, _: BlockHeadersRequest| {
+            let requests = requests.clone();
+            async move {
+                requests.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                let (mut tx, rx) = fmpsc::channel::<std::io::Result<BlockHeadersResponse>>(1);
+                tx.try_send(Ok(HdrFin)).unwrap();
+                anyhow::Ok(rx)
+            }
+        }
+    };
+
+    let actual = super::header_stream::make(
+        BlockNumber::GENESIS,
+        BlockNumber::GENESIS + 1,
+        false,
+        std::num::NonZeroU64::MIN,
+        Duration::from_secs(10),
+        RetryPolicy {
+            max_attempts: std::num::NonZeroUsize::new(3).unwrap(),
+        },
+        None,
+        None,
+        PeerScores::default(),
+        get_peers,
+        send_request,
+    )
+    .collect::<Vec<_>>()
+    .await;
+
+    assert_eq!(actual.len(), 1);
+    assert!(actual[0].is_err());
+    // Three passes over both peers
+    assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 6);
+}
+
+// Not a `test_log` test, the subscriber below has to be the one capturing the
+// stream task's output. The default runtime is single threaded, so the
+// thread-local subscriber also applies to the spawned task.
+#[tokio::test]
+async fn make_header_stream_warns_when_all_peers_fail() {
+    #[derive(Clone, Default)]
+    struct Logs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Logs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let logs = Logs::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer({
+            let logs = logs.clone();
+            move || logs.clone()
+        })
+        .with_max_level(tracing::Level::WARN)
+        .with_ansi(false)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let (unreachable, finishes_early) = (peer(0).0, peer(1).0);
+    let get_peers = move || async move { vec![unreachable, finishes_early] };
+    let send_request = move |peer: PeerId, _: BlockHeadersRequest| async move {
+        if peer == unreachable {
+            anyhow::bail!("connection refused");
+        }
+        let (mut tx, rx) = fmpsc::channel::<std::io::Result<BlockHeadersResponse>>(1);
+        tx.try_send(Ok(HdrFin)).unwrap();
+        anyhow::Ok(rx)
+    };
+
+    let actual = super::header_stream::make(
+        BlockNumber::GENESIS,
+        BlockNumber::GENESIS + 1,
+        false,
+        std::num::NonZeroU64::MIN,
+        Duration::from_secs(10),
+        RetryPolicy {
+            max_attempts: std::num::NonZeroUsize::new(2).unwrap(),
+        },
+        None,
+        None,
+        PeerScores::default(),
+        get_peers,
+        send_request,
+    )
+    .collect::<Vec<_>>()
+    .await;
+
+    assert_eq!(actual.len(), 1);
+    assert!(actual[0].is_err());
+
+    let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    let warnings = logs
+        .lines()
+        .filter(|line| line.contains("All peers failed to serve headers"))
+        .collect::<Vec<_>>();
+    // One summary per pass over the peer set
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].contains("failed_attempts=1"));
+    assert!(warnings[1].contains("failed_attempts=2"));
+    for warning in warnings {
+        assert!(warning.contains("request failed: connection refused"));
+        assert!(warning.contains("Fin received before block 0"));
+    }
+}
+
+#[test_log::test(tokio::test)]
+async fn make_header_stream_drops_peer_on_other_fork() {
+    let forked = SignedBlockHeader {
+        header: BlockHeader {
+            hash: BlockHash(pathfinder_crypto::Felt::ONE),
+            ..hdr(91).header
+        },
+        ..hdr(91)
+    };
+    let (peers, responses) = unzip_fixtures(vec![
+        Ok((
+            peer(0),
+            vec![
+                hdr_resp(90),
+                BlockHeadersResponse::Header(Box::new(forked.to_dto())),
+                HdrFin,
+            ],
+        )),
+        Ok((peer(1), vec![hdr_resp(91), HdrFin])),
+    ]);
+    let get_peers = move || {
+        let peers = peers.clone();
+        async move { peers }
+    };
+    let send_request = move |_: PeerId, _: BlockHeadersRequest| {
+        let responses = responses.clone();
+        async move { send_request(responses).await }
+    };
+
+    let actual = super::header_stream::make(
+        BlockNumber::new_or_panic(90),
+        BlockNumber::new_or_panic(91),
+        false,
+        std::num::NonZeroU64::MIN,
+        Duration::from_secs(10),
+        SINGLE_ATTEMPT,
+        None,
+        Some((BlockNumber::new_or_panic(91), hdr(91).header.hash)),
+        PeerScores::default(),
+        get_peers,
+        send_request,
+    )
+    .map_ok(|x| (TestPeer(x.peer), x.data))
+    .try_collect::<Vec<_>>()
+    .await
+    .unwrap();
+
+    pretty_assertions_sorted::assert_eq!(actual, vec![(peer(0), hdr(90)), (peer(1), hdr(91))]);
+}
+
+#[test_log::test(tokio::test)]
+async fn make_header_stream_concurrent() {
+    use p2p_proto::common::BlockNumberOrHash;
+
+    // Each peer only serves its own sub-range
+    let peers = vec![(peer(0).0, 0..=1), (peer(1).0, 2..=3)];
+    let get_peers = {
+        let peer_ids = peers.iter().map(|(p, _)| *p).collect::<Vec<_>>();
+        move || {
+            let peer_ids = peer_ids.clone();
+            async move { peer_ids }
+        }
+    };
+    let send_request = {
+        let peers = peers.clone();
+        move |peer: PeerId, request: BlockHeadersRequest| {
+            let peers = peers.clone();
+            async move {
+                let BlockNumberOrHash::Number(start) = request.iteration.start else {
+                    panic!("Expected block number");
+                };
+                let (_, range) = peers.iter().find(|(p, _)| *p == peer).unwrap();
+                anyhow::ensure!(range.contains(&start), "Not serving block {start}");
+
+                let numbers = match request.iteration.direction {
+                    Direction::Forward => (start..=*range.end()).collect::<Vec<_>>(),
+                    Direction::Backward => (*range.start()..=start).rev().collect(),
+                };
+                let (mut tx, rx) =
+                    fmpsc::channel::<std::io::Result<BlockHeadersResponse>>(numbers.len() + 1);
+                for n in numbers {
+                    tx.try_send(Ok(hdr_resp(n as i32))).unwrap();
+                }
+                tx.try_send(Ok(HdrFin)).unwrap();
+                Ok(rx)
+            }
+        }
+    };
+
+    for (reverse, direction) in [(false, "forward"), (true, "backward")] {
+        let actual = super::header_stream::make_concurrent(
+            BlockNumber::GENESIS,
+            BlockNumber::new_or_panic(3),
+            reverse,
+            Duration::from_secs(10),
+            SINGLE_ATTEMPT,
+            None,
+            PeerScores::default(),
+            std::num::NonZeroUsize::new(2).unwrap(),
+            std::num::NonZeroU64::new(2).unwrap(),
+            get_peers.clone(),
+            send_request.clone(),
+        )
+        .map_ok(|x| (TestPeer(x.peer), x.data))
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+        let mut expected = vec![
+            (TestPeer(peers[0].0), hdr(0)),
+            (TestPeer(peers[0].0), hdr(1)),
+            (TestPeer(peers[1].0), hdr(2)),
+            (TestPeer(peers[1].0), hdr(3)),
+        ];
+        if reverse {
+            expected.reverse();
+        }
+
+        pretty_assertions_sorted::assert_eq!(actual, expected, "Direction: {}", direction);
+    }
+}
+
+#[test_log::test(tokio::test)]
+async fn get_header_from_first_responsive_peer() {
+    let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let get_peers = || async { vec![peer(0).0, peer(1).0, peer(2).0, peer(3).0] };
+    let send_request = |p: PeerId, request: BlockHeadersRequest| {
+        requests.lock().unwrap().push(TestPeer(p));
+        async move {
+            assert_eq!(request.iteration.start, 7u64.into());
+            assert_eq!(request.iteration.limit, 1);
+            // Peer 0 is unreachable and peer 1 doesn't have the header.
+            anyhow::ensure!(p != peer(0).0, "Unreachable");
+            let (mut tx, rx) = fmpsc::channel::<std::io::Result<BlockHeadersResponse>>(2);
+            if p != peer(1).0 {
+                tx.try_send(Ok(hdr_resp(7))).unwrap();
+            }
+            tx.try_send(Ok(HdrFin)).unwrap();
+            Ok(rx)
+        }
+    };
+
+    let actual = super::header_stream::get(
+        BlockNumber::new_or_panic(7),
+        Duration::from_secs(10),
+        SINGLE_ATTEMPT,
+        None,
+        PeerScores::default(),
+        get_peers,
+        send_request,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(TestPeer(actual.peer), peer(2));
+    pretty_assertions_sorted::assert_eq!(actual.data, hdr(7));
+    assert_eq!(*requests.lock().unwrap(), vec![peer(0), peer(1), peer(2)]);
+}
+
+#[test_log::test(tokio::test)]
+async fn make_header_stream_skips_invalid_signature() {
+    use fake::{Fake, Faker};
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{BlockCommitmentSignature, BlockHeader};
+
+    // From https://integration-sepolia.starknet.io/feeder_gateway/get_public_key
+    let public_key =
+        public_key!("0x4e4856eb36dbd5f4a7dca29f7bb5232974ef1fb7eb5b597c58077174c294da1");
+    // From https://integration-sepolia.starknet.io/feeder_gateway/get_signature?blockNumber=35748
+    let valid = SignedBlockHeader {
+        header: BlockHeader {
+            hash: block_hash!("0x1ea2a9cfa3df5297d58c0a04d09d276bc68d40fe64701305bbe2ed8f417e869"),
+            number: BlockNumber::new_or_panic(35748),
+            ..Faker.fake()
+        },
+        signature: BlockCommitmentSignature {
+            r: block_commitment_signature_elem!(
+                "0x45161746eecbeae297f45a1f407ab702310f4e52c5e9350ed6f542fa8e98413"
+            ),
+            s: block_commitment_signature_elem!(
+                "0x3e67cfbc5b179ba55a3b687228d8fe40626233f6691b4aabe308fcd6d71dcdb"
+            ),
+        },
+    };
+    let mut invalid = valid.clone();
+    invalid.signature.r = block_commitment_signature_elem!("0x1");
+
+    // The peer serves the invalid header first, and the valid one when retried.
+    let responses = Arc::new(std::sync::Mutex::new(vec![valid.clone(), invalid]));
+    let get_peers = move || async move { vec![peer(0).0] };
+    let send_request = {
+        let responses = responses.clone();
+        move |_: PeerId, _: BlockHeadersRequest| {
+            let header = responses.lock().unwrap().pop().unwrap();
+            async move {
+                let (mut tx, rx) = fmpsc::channel::<std::io::Result<BlockHeadersResponse>>(2);
+                tx.try_send(Ok(BlockHeadersResponse::Header(Box::new(header.to_dto()))))
+                    .unwrap();
+                tx.try_send(Ok(HdrFin)).unwrap();
+                anyhow::Ok(rx)
+            }
+        }
+    };
+
+    let actual = super::header_stream::make(
+        valid.header.number,
+        valid.header.number,
+        false,
+        std::num::NonZeroU64::MIN,
+        Duration::from_secs(10),
+        RetryPolicy {
+            max_attempts: std::num::NonZeroUsize::new(2).unwrap(),
+        },
+        Some(public_key),
+        None,
+        PeerScores::default(),
+        get_peers,
+        send_request,
+    )
+    .map_ok(|x| x.data)
+    .try_collect::<Vec<_>>()
+    .await
+    .unwrap();
+
+    pretty_assertions_sorted::assert_eq!(actual, vec![valid]);
+    assert!(responses.lock().unwrap().is_empty());
+}
+
 #[rstest]
 #[case::one_peer_1_block(
     1,
@@ -309,7 +895,10 @@ async fn make_transaction_stream(
     let actual = super::transaction_stream::make(
         start,
         stop,
+        false,
         stream::iter(num_txns_per_block.into_iter().map(Ok)),
+        SINGLE_ATTEMPT,
+        PeerScores::default(),
         get_peers,
         send_request,
     )
@@ -330,6 +919,314 @@ async fn make_transaction_stream(
     pretty_assertions_sorted::assert_eq!(actual, expected_stream);
 }
 
+#[test_log::test(tokio::test)]
+async fn make_transaction_stream_reverse() {
+    let get_peers = || async { vec![peer(0).0] };
+    let send_request = |_: PeerId, request: TransactionsRequest| async move {
+        assert_eq!(request.iteration.direction, Direction::Backward);
+        assert_eq!(request.iteration.start, 2u64.into());
+        let (mut tx, rx) = fmpsc::channel::<std::io::Result<TransactionsResponse>>(4);
+        for tag in [32, 31, 30] {
+            tx.try_send(Ok(txn_resp(tag, 0))).unwrap();
+        }
+        tx.try_send(Ok(TxnFin)).unwrap();
+        anyhow::Ok(rx)
+    };
+
+    let actual = super::transaction_stream::make(
+        BlockNumber::GENESIS,
+        BlockNumber::new_or_panic(2),
+        true,
+        stream::iter([1, 1, 1].map(Ok)),
+        SINGLE_ATTEMPT,
+        PeerScores::default(),
+        get_peers,
+        send_request,
+    )
+    .map_ok(|x| {
+        let (transactions, block_number) = x.data;
+        (
+            block_number.get(),
+            transactions
+                .into_iter()
+                .map(|(t, r)| TestTxn::new((t.variant, r)))
+                .collect::<Vec<_>>(),
+        )
+    })
+    .try_collect::<Vec<_>>()
+    .await
+    .unwrap();
+
+    pretty_assertions_sorted::assert_eq!(
+        actual,
+        vec![
+            (2, vec![txn(32, 0)]),
+            (1, vec![txn(31, 0)]),
+            (0, vec![txn(30, 0)])
+        ]
+    );
+}
+
+#[test_log::test(tokio::test)]
+async fn make_transaction_stream_flat_yields_before_block_completes() {
+    let (mut response_tx, response_rx) = fmpsc::channel::<std::io::Result<TransactionsResponse>>(4);
+    let response_rx = Arc::new(std::sync::Mutex::new(Some(response_rx)));
+    let get_peers = || async { vec![peer(0).0] };
+    let send_request = move |_: PeerId, _: TransactionsRequest| {
+        let response_rx = response_rx.lock().unwrap().take();
+        async move { response_rx.context("Requested twice") }
+    };
+
+    let mut stream = Box::pin(super::transaction_stream::make_flat(
+        BlockNumber::GENESIS,
+        BlockNumber::GENESIS,
+        false,
+        stream::iter([Ok(2)]),
+        SINGLE_ATTEMPT,
+        PeerScores::default(),
+        get_peers,
+        send_request,
+    ));
+    let to_test = |item: PeerData<(BlockNumber, Transaction, Receipt)>| {
+        let (block_number, transaction, receipt) = item.data;
+        (
+            TestPeer(item.peer),
+            block_number,
+            TestTxn::new((transaction.variant, receipt)),
+        )
+    };
+
+    // The first transaction is yielded while the rest of the block is pending.
+    response_tx.try_send(Ok(txn_resp(50, 0))).unwrap();
+    let first = stream.next().await.unwrap().unwrap();
+    assert_eq!(to_test(first), (peer(0), BlockNumber::GENESIS, txn(50, 0)));
+
+    response_tx.try_send(Ok(txn_resp(51, 1))).unwrap();
+    response_tx.try_send(Ok(TxnFin)).unwrap();
+    let second = stream.next().await.unwrap().unwrap();
+    assert_eq!(to_test(second), (peer(0), BlockNumber::GENESIS, txn(51, 1)));
+    assert!(stream.next().await.is_none());
+}
+
+#[test_log::test(tokio::test)]
+async fn make_transaction_stream_flat_resumes_block_from_next_peer() {
+    let (peers, responses) = unzip_fixtures(vec![
+        // First peer stops after the first transaction
+        Ok((peer(0), vec![txn_resp(52, 0)])),
+        // Second peer resends the block from its start
+        Ok((peer(1), vec![txn_resp(52, 0), txn_resp(53, 1), TxnFin])),
+    ]);
+    let get_peers = move || {
+        let peers = peers.clone();
+        async move { peers }
+    };
+    let send_request = move |_: PeerId, _: TransactionsRequest| {
+        let responses = responses.clone();
+        async move { send_request(responses).await }
+    };
+
+    let actual = super::transaction_stream::make_flat(
+        BlockNumber::GENESIS,
+        BlockNumber::GENESIS,
+        false,
+        stream::iter([Ok(2)]),
+        SINGLE_ATTEMPT,
+        PeerScores::default(),
+        get_peers,
+        send_request,
+    )
+    .map_ok(|x| {
+        let (_, transaction, receipt) = x.data;
+        (
+            TestPeer(x.peer),
+            TestTxn::new((transaction.variant, receipt)),
+        )
+    })
+    .try_collect::<Vec<_>>()
+    .await
+    .unwrap();
+
+    // The first transaction is not yielded again.
+    pretty_assertions_sorted::assert_eq!(
+        actual,
+        vec![(peer(0), txn(52, 0)), (peer(1), txn(53, 1))]
+    );
+}
+
+#[rstest]
+#[case::peers_exhausted(
+    vec![
+        Err(peer(0)),
+        Ok((peer(1), vec![TxnFin])),
+        Ok((peer(2), vec![]))
+    ],
+    vec![]
+)]
+#[case::invalid_data(
+    vec![
+        Ok((peer(0), vec![malformed_receipt_resp(60), TxnFin])),
+        Ok((peer(1), vec![TxnFin]))
+    ],
+    vec![peer(0)]
+)]
+#[test_log::test(tokio::test)]
+async fn make_transaction_stream_fails_when_all_peers_fail(
+    #[case] responses: Vec<Result<(TestPeer, Vec<TransactionsResponse>), TestPeer>>,
+    #[case] invalid_data: Vec<TestPeer>,
+) {
+    let (peers, responses) = unzip_fixtures(responses);
+    let get_peers = move || {
+        let peers = peers.clone();
+        async move { peers }
+    };
+    let send_request = move |_: PeerId, _: TransactionsRequest| {
+        let responses = responses.clone();
+        async move { send_request(responses).await }
+    };
+
+    let actual = super::transaction_stream::make(
+        BlockNumber::GENESIS,
+        BlockNumber::GENESIS + 1,
+        false,
+        stream::iter([1, 1].map(Ok)),
+        SINGLE_ATTEMPT,
+        PeerScores::default(),
+        get_peers,
+        send_request,
+    )
+    .collect::<Vec<_>>()
+    .await;
+
+    assert_eq!(actual.len(), 1);
+    match &actual[0] {
+        Err(SyncError::PeersExhausted { block }) if invalid_data.is_empty() => {
+            assert_eq!(*block, BlockNumber::GENESIS);
+        }
+        Err(SyncError::Conversion { block, peers }) => {
+            assert_eq!(*block, BlockNumber::GENESIS);
+            let expected = invalid_data.into_iter().map(|p| p.0).collect::<Vec<_>>();
+            assert_eq!(*peers, expected);
+        }
+        other => panic!("Unexpected item: {other:?}"),
+    }
+}
+
+#[test_log::test(tokio::test)]
+async fn make_transaction_stream_fin_after_full_request_is_not_a_failure() {
+    let (first, second) = (peer(0).0, peer(1).0);
+    let get_peers = move || async move { vec![first, second] };
+    let send_request = move |peer: PeerId, request: TransactionsRequest| async move {
+        let (mut tx, rx) =
+            fmpsc::channel::<std::io::Result<TransactionsResponse>>(MAX_BLOCKS_COUNT as usize + 1);
+        let (tag, blocks) = if peer == first {
+            (70, MAX_BLOCKS_COUNT)
+        } else {
+            (71, 1)
+        };
+        assert_eq!(request.iteration.limit, blocks);
+        for _ in 0..blocks {
+            tx.try_send(Ok(txn_resp(tag, 0))).unwrap();
+        }
+        tx.try_send(Ok(TxnFin)).unwrap();
+        anyhow::Ok(rx)
+    };
+    let scores = PeerScores::default();
+
+    let actual = super::transaction_stream::make(
+        BlockNumber::GENESIS,
+        BlockNumber::new_or_panic(MAX_BLOCKS_COUNT),
+        false,
+        stream::iter(std::iter::repeat(Ok(1)).take(MAX_BLOCKS_COUNT as usize + 1)),
+        SINGLE_ATTEMPT,
+        scores.clone(),
+        get_peers,
+        send_request,
+    )
+    .map_ok(|x| (TestPeer(x.peer), x.data.1))
+    .try_collect::<Vec<_>>()
+    .await
+    .unwrap();
+
+    assert_eq!(actual.len(), MAX_BLOCKS_COUNT as usize + 1);
+    assert_eq!(
+        actual.last().unwrap(),
+        &(peer(1), BlockNumber::new_or_panic(MAX_BLOCKS_COUNT))
+    );
+    let scores = scores.snapshot();
+    assert_eq!(scores[&first].failures, 0);
+    assert_eq!(scores[&second].failures, 0);
+}
+
+/// A response whose receipt fails to convert because of an out of range gas
+/// amount.
+fn malformed_receipt_resp(tag: i32) -> TransactionsResponse {
+    use p2p_proto::receipt::Receipt::{Declare, Deploy, DeployAccount, Invoke, L1Handler};
+
+    let mut response = txn_resp(tag, 0);
+    let TransactionsResponse::TransactionWithReceipt(TransactionWithReceipt { receipt, .. }) =
+        &mut response
+    else {
+        unreachable!("Fixture is a transaction with receipt");
+    };
+    let common = match receipt {
+        Invoke(r) => &mut r.common,
+        Declare(r) => &mut r.common,
+        L1Handler(r) => &mut r.common,
+        Deploy(r) => &mut r.common,
+        DeployAccount(r) => &mut r.common,
+    };
+    let mut too_large = [0u8; 32];
+    too_large[0] = 1;
+    common.execution_resources.l1_gas =
+        Some(pathfinder_crypto::Felt::from_be_bytes(too_large).unwrap());
+    response
+}
+
+#[rstest]
+#[case::one_peer_2_blocks(
+    vec![Ok((peer(0), vec![txn_resp(40, 0), txn_resp(41, 0), txn_resp(42, 1), TxnFin]))],
+    vec![Ok((peer(0), vec![txn(40, 0).r])), Ok((peer(0), vec![txn(41, 0).r, txn(42, 1).r]))]
+)]
+#[case::malformed_receipt_falls_back_to_next_peer(
+    vec![
+        Ok((peer(0), vec![txn_resp(40, 0), malformed_receipt_resp(41), TxnFin])),
+        Ok((peer(1), vec![txn_resp(41, 0), txn_resp(42, 1), TxnFin]))
+    ],
+    vec![Ok((peer(0), vec![txn(40, 0).r])), Ok((peer(1), vec![txn(41, 0).r, txn(42, 1).r]))]
+)]
+#[test_log::test(tokio::test)]
+async fn make_receipt_stream(
+    #[case] responses: Vec<Result<(TestPeer, Vec<TransactionsResponse>), TestPeer>>,
+    #[case] expected_stream: Vec<Result<(TestPeer, Vec<Receipt>), ()>>,
+) {
+    let (peers, responses) = unzip_fixtures(responses);
+    let get_peers = move || {
+        let peers = peers.clone();
+        async move { peers }
+    };
+    let send_request = move |_: PeerId, _: TransactionsRequest| {
+        let responses = responses.clone();
+        async move { send_request(responses).await }
+    };
+
+    let actual = super::receipt_stream::make(
+        BlockNumber::GENESIS,
+        BlockNumber::new_or_panic(1),
+        false,
+        stream::iter([1, 2].map(Ok)),
+        SINGLE_ATTEMPT,
+        PeerScores::default(),
+        get_peers,
+        send_request,
+    )
+    .map_ok(|x| (TestPeer(x.peer), x.data.0))
+    .map_err(|_| ())
+    .collect::<Vec<_>>()
+    .await;
+
+    pretty_assertions_sorted::assert_eq!(actual, expected_stream);
+}
+
 #[rstest]
 #[case::one_peer_1_block(
     1,
@@ -508,6 +1405,16 @@ async fn make_transaction_stream(
     vec![len(20)],
     vec![Ok((peer(1), state_diff(20)))]
 )]
+#[case::empty_state_diff_is_yielded(
+    2,
+    // Block 0 has an empty state diff so no responses are expected for it
+    vec![Ok((peer(0), vec![contract_diff(21), declared_class(21), SDFin]))],
+    vec![0, len(21)],
+    vec![
+        Ok((peer(0), StateUpdateData::default())),
+        Ok((peer(0), state_diff(21)))
+    ]
+)]
 #[test_log::test(tokio::test)]
 async fn make_state_diff_stream(
     #[case] num_blocks: usize,
@@ -892,6 +1799,16 @@ async fn make_class_definition_stream(
         Ok((peer(0), events(vec![(vec![30], 30)], 0)))
     ]
 )]
+#[case::block_without_events(
+    3,
+    vec![Ok((peer(0), vec![event_resp(31, 31), event_resp(32, 32), EventFin]))],
+    vec![1, 0, 1],
+    vec![
+        Ok((peer(0), events(vec![(vec![31], 31)], 0))),
+        Ok((peer(0), events(vec![], 1))),
+        Ok((peer(0), events(vec![(vec![32], 32)], 2))),
+    ]
+)]
 #[test_log::test(tokio::test)]
 async fn make_event_stream(
     #[case] num_blocks: usize,
@@ -938,3 +1855,406 @@ async fn make_event_stream(
 
     pretty_assertions_sorted::assert_eq!(actual, expected_stream);
 }
+
+#[test]
+fn peer_score_tracks_responses() {
+    let mut score = PeerScore::default();
+    assert_eq!(score.value(), 0.5);
+
+    score.record_success(Duration::from_millis(100));
+    assert_eq!(score.avg_latency, Some(Duration::from_millis(100)));
+    score.record_success(Duration::from_millis(600));
+    let avg_latency = score.avg_latency.unwrap().as_secs_f64();
+    assert!((avg_latency - 0.2).abs() < 1e-6);
+    score.record_failure();
+
+    assert_eq!(score.successes, 2);
+    assert_eq!(score.failures, 1);
+    // (2 + 1) / (3 + 2) / (1 + 0.2)
+    assert!((score.value() - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn peers_are_ranked_by_score() {
+    let good = peer(0).0;
+    let bad = peer(1).0;
+    let unknown = peer(2).0;
+
+    let scores = PeerScores::with_seed(0);
+    for _ in 0..10 {
+        scores.record_success(good, Duration::from_millis(10));
+        scores.record_failure(bad);
+    }
+
+    let snapshot = scores.snapshot();
+    assert_eq!(snapshot[&good].successes, 10);
+    assert_eq!(snapshot[&bad].failures, 10);
+    assert!(!snapshot.contains_key(&unknown));
+
+    let mut good_first = 0;
+    for _ in 0..100 {
+        let mut peers = vec![bad, unknown, good];
+        scores.rank(&mut peers);
+        // The randomization never outweighs a difference this large.
+        assert_eq!(peers[2], bad);
+        if peers[0] == good {
+            good_first += 1;
+        }
+    }
+    // But it occasionally lets a peer without history go first.
+    assert_eq!(good_first, 99);
+}
+
+#[test]
+fn seeded_peer_ranking_is_reproducible() {
+    let peers = (0..5).map(|tag| peer(tag).0).collect::<Vec<_>>();
+    let rankings = |scores: PeerScores| {
+        (0..10)
+            .map(|_| {
+                let mut ranked = peers.clone();
+                scores.rank(&mut ranked);
+                ranked
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let first = rankings(PeerScores::with_seed(1));
+    assert_eq!(first, rankings(PeerScores::with_seed(1)));
+    assert_ne!(first, rankings(PeerScores::with_seed(2)));
+    // Peers without history are still shuffled between calls.
+    assert!(first.iter().any(|ranked| ranked != &first[0]));
+}
+
+#[tokio::test]
+async fn warm_peer_cache_avoids_later_discovery() {
+    let (sender, mut receiver) = mpsc::channel(1);
+    let queries = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    tokio::spawn({
+        let queries = queries.clone();
+        async move {
+            while let Some(command) = receiver.recv().await {
+                let crate::Command::GetClosestPeers { sender, .. } = command else {
+                    panic!("Unexpected command");
+                };
+                queries.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                sender.send(Ok(vec![peer(0).0])).await.unwrap();
+            }
+        }
+    });
+    let client = Client::new(
+        peer_aware::Client::new(sender, PeerId::random()),
+        "blocks".to_owned(),
+        Duration::from_secs(10),
+        SINGLE_ATTEMPT,
+        None,
+    );
+
+    client.warm_peer_cache().await;
+    assert_eq!(queries.load(std::sync::atomic::Ordering::Relaxed), 1);
+
+    // Neither warming again nor starting a stream discovers peers again.
+    client.warm_peer_cache().await;
+    assert_eq!(client.get_random_peers().await, vec![peer(0).0]);
+    assert_eq!(queries.load(std::sync::atomic::Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn peer_counts() {
+    let (sender, mut receiver) = mpsc::channel(1);
+    let queries = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    tokio::spawn({
+        let queries = queries.clone();
+        async move {
+            while let Some(command) = receiver.recv().await {
+                let crate::Command::GetCapabilityProviders { capability, sender } = command else {
+                    panic!("Unexpected command");
+                };
+                queries.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                let providers = match capability.as_str() {
+                    "headers" => [peer(0).0, peer(1).0].into(),
+                    _ => [peer(0).0].into(),
+                };
+                sender.send(Ok(providers)).await.unwrap();
+            }
+        }
+    });
+    let client = Client::new(
+        peer_aware::Client::new(sender, PeerId::random()),
+        "blocks".to_owned(),
+        Duration::from_secs(10),
+        SINGLE_ATTEMPT,
+        None,
+    );
+
+    // A fresh peer cache is used as is.
+    client
+        .peers
+        .write()
+        .await
+        .update([peer(0).0, peer(1).0, peer(2).0].into());
+    assert_eq!(client.known_peer_count().await, 3);
+
+    let expected = HashMap::from([("headers".to_owned(), 2), ("events".to_owned(), 1)]);
+    let counts = client
+        .capability_peer_counts(&["headers", "events"])
+        .await
+        .unwrap();
+    assert_eq!(counts, expected);
+    assert_eq!(queries.load(std::sync::atomic::Ordering::Relaxed), 2);
+
+    // Cached counts are not queried again.
+    let counts = client
+        .capability_peer_counts(&["headers", "events"])
+        .await
+        .unwrap();
+    assert_eq!(counts, expected);
+    assert_eq!(queries.load(std::sync::atomic::Ordering::Relaxed), 2);
+}
+
+#[test]
+fn new_block_header_is_serialized_as_header_variant() {
+    use p2p_proto::proto::header::new_block::MaybeFull;
+    use p2p_proto::ToProtobuf;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{BlockCommitmentSignature, BlockHeader};
+
+    // From https://integration-sepolia.starknet.io/feeder_gateway/get_public_key
+    let public_key =
+        public_key!("0x4e4856eb36dbd5f4a7dca29f7bb5232974ef1fb7eb5b597c58077174c294da1");
+    // From https://integration-sepolia.starknet.io/feeder_gateway/get_signature?blockNumber=35748
+    let header = SignedBlockHeader {
+        header: BlockHeader {
+            hash: block_hash!("0x1ea2a9cfa3df5297d58c0a04d09d276bc68d40fe64701305bbe2ed8f417e869"),
+            number: BlockNumber::new_or_panic(35748),
+            ..Default::default()
+        },
+        signature: BlockCommitmentSignature {
+            r: block_commitment_signature_elem!(
+                "0x45161746eecbeae297f45a1f407ab702310f4e52c5e9350ed6f542fa8e98413"
+            ),
+            s: block_commitment_signature_elem!(
+                "0x3e67cfbc5b179ba55a3b687228d8fe40626233f6691b4aabe308fcd6d71dcdb"
+            ),
+        },
+    };
+
+    let new_block = super::new_block_header(header.clone(), public_key)
+        .unwrap()
+        .to_protobuf();
+    assert!(matches!(new_block.maybe_full, Some(MaybeFull::Header(_))));
+
+    let mut tampered = header;
+    tampered.header.hash = block_hash!("0x1");
+    super::new_block_header(tampered, public_key).unwrap_err();
+}
+
+#[test_log::test(tokio::test)]
+async fn make_block_stream_retries_failed_components() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let headers = vec![hdr(0), hdr(1)];
+    let header_stream = stream::iter(
+        headers
+            .clone()
+            .into_iter()
+            .map(|header| Ok(PeerData::new(peer(0).0, header))),
+    );
+
+    let transaction_requests = Arc::new(AtomicUsize::new(0));
+    let get_transactions = {
+        let requests = transaction_requests.clone();
+        move |_: BlockNumber, _: usize| {
+            // The first peer asked for transactions has none to give.
+            let attempt = requests.fetch_add(1, Ordering::Relaxed);
+            async move {
+                anyhow::Ok(
+                    (attempt > 0).then(|| PeerData::new(peer(1).0, TransactionData::default())),
+                )
+            }
+        }
+    };
+
+    let state_diff_requests = Arc::new(AtomicUsize::new(0));
+    let get_state_diff = {
+        let requests = state_diff_requests.clone();
+        move |_: BlockNumber, _: u64| {
+            // The first state diff response is invalid.
+            let attempt = requests.fetch_add(1, Ordering::Relaxed);
+            async move {
+                anyhow::ensure!(attempt > 0, "Invalid state diff");
+                Ok(Some(PeerData::new(peer(2).0, StateUpdateData::default())))
+            }
+        }
+    };
+
+    let actual = super::block_stream::make(
+        header_stream,
+        RetryPolicy {
+            max_attempts: std::num::NonZeroUsize::new(2).unwrap(),
+        },
+        get_transactions,
+        get_state_diff,
+    )
+    .map_ok(|x| (TestPeer(x.peer), x.data))
+    .try_collect::<Vec<_>>()
+    .await
+    .unwrap();
+
+    let expected = headers
+        .into_iter()
+        .map(|header| {
+            (
+                peer(0),
+                FullBlock {
+                    header,
+                    transactions: Default::default(),
+                    state_diff: Default::default(),
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+    pretty_assertions_sorted::assert_eq!(actual, expected);
+    assert_eq!(transaction_requests.load(Ordering::Relaxed), 3);
+    assert_eq!(state_diff_requests.load(Ordering::Relaxed), 3);
+}
+
+#[test_log::test(tokio::test)]
+async fn make_block_stream_gives_up_after_max_attempts() {
+    let header_stream = stream::iter([Ok(PeerData::new(peer(0).0, hdr(0)))]);
+
+    let actual = super::block_stream::make(
+        header_stream,
+        SINGLE_ATTEMPT,
+        |_, _| async { anyhow::Ok(None::<PeerData<TransactionData>>) },
+        |_, _| async { anyhow::Ok(Some(PeerData::new(peer(1).0, StateUpdateData::default()))) },
+    )
+    .collect::<Vec<_>>()
+    .await;
+
+    assert_eq!(actual.len(), 1);
+    assert!(actual[0].is_err());
+}
+
+#[tokio::test]
+async fn propagate_new_head_uses_current_topic() {
+    let (sender, mut receiver) = mpsc::channel(1);
+    let topics = tokio::spawn(async move {
+        let mut topics = Vec::new();
+        while let Some(command) = receiver.recv().await {
+            let crate::Command::PublishPropagationMessage { topic, sender, .. } = command else {
+                panic!("Unexpected command");
+            };
+            topics.push(topic.to_string());
+            sender.send(Ok(())).unwrap();
+        }
+        topics
+    });
+    let client = Client::new(
+        peer_aware::Client::new(sender, PeerId::random()),
+        "blocks/old".to_owned(),
+        Duration::from_secs(10),
+        SINGLE_ATTEMPT,
+        None,
+    );
+    let block_id = p2p_proto::common::BlockId {
+        number: 1,
+        hash: p2p_proto::common::Hash(pathfinder_crypto::Felt::ONE),
+    };
+
+    client.propagate_new_head(block_id).await.unwrap();
+    // The change is visible through clones as well.
+    client
+        .clone()
+        .set_block_propagation_topic("blocks/new".to_owned());
+    client.propagate_new_head(block_id).await.unwrap();
+
+    drop(client);
+    assert_eq!(topics.await.unwrap(), vec!["blocks/old", "blocks/new"]);
+}
+
+#[rstest]
+#[case::known_hash(true)]
+#[case::unknown_hash(false)]
+#[test_log::test(tokio::test)]
+async fn transaction_stream_by_hash(#[case] known: bool) {
+    let header = SignedBlockHeader {
+        header: BlockHeader {
+            transaction_count: 1,
+            ..hdr(80).header
+        },
+        ..hdr(80)
+    };
+    let known_hash = header.header.hash;
+    let (sender, mut receiver) = mpsc::channel(1);
+    tokio::spawn(async move {
+        while let Some(command) = receiver.recv().await {
+            match command {
+                crate::Command::SendHeadersSyncRequest {
+                    request, sender, ..
+                } => {
+                    let (mut tx, rx) = fmpsc::channel(2);
+                    if request.iteration.start
+                        == BlockNumberOrHash::Hash(p2p_proto::common::Hash(known_hash.0))
+                    {
+                        tx.try_send(Ok(BlockHeadersResponse::Header(Box::new(
+                            header.clone().to_dto(),
+                        ))))
+                        .unwrap();
+                    }
+                    tx.try_send(Ok(HdrFin)).unwrap();
+                    sender.send(Ok(rx)).unwrap();
+                }
+                crate::Command::SendTransactionsSyncRequest {
+                    request, sender, ..
+                } => {
+                    assert_eq!(request.iteration.start, 80u64.into());
+                    assert_eq!(request.iteration.limit, 1);
+                    let (mut tx, rx) = fmpsc::channel(2);
+                    tx.try_send(Ok(txn_resp(80, 0))).unwrap();
+                    tx.try_send(Ok(TxnFin)).unwrap();
+                    sender.send(Ok(rx)).unwrap();
+                }
+                _ => panic!("Unexpected command"),
+            }
+        }
+    });
+    let client = Client::new(
+        peer_aware::Client::new(sender, PeerId::random()),
+        "blocks".to_owned(),
+        Duration::from_secs(10),
+        SINGLE_ATTEMPT,
+        None,
+    );
+    client.peers.write().await.update([peer(0).0].into());
+
+    let hash = if known {
+        known_hash
+    } else {
+        BlockHash(pathfinder_crypto::Felt::ONE)
+    };
+    let actual = client
+        .transaction_stream_by_hash(hash)
+        .map_ok(|x| {
+            (
+                TestPeer(x.peer),
+                x.data.1,
+                x.data
+                    .0
+                    .into_iter()
+                    .map(|(t, r)| TestTxn::new((t.variant, r)))
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>()
+        .await;
+
+    if known {
+        pretty_assertions_sorted::assert_eq!(
+            actual.into_iter().collect::<Result<Vec<_>, _>>().unwrap(),
+            vec![(peer(0), BlockNumber::new_or_panic(80), vec![txn(80, 0)])]
+        );
+    } else {
+        assert_eq!(actual.len(), 1);
+        assert!(actual[0].is_err());
+    }
+}
@@ -16,24 +16,58 @@ use crate::client::types::{
 };
 use crate::PeerData;
 
-pub type StreamItem<T> = Result<PeerData<T>, anyhow::Error>;
+pub type StreamItem<T, E = anyhow::Error> = Result<PeerData<T>, E>;
+
+/// The error yielded by [`HeaderStream::header_stream`] and
+/// [`TransactionStream::transaction_stream`] right before they end.
+#[derive(Debug, thiserror::Error)]
+pub enum SyncError {
+    /// The configured number of consecutive passes over the peer set did not
+    /// yield any data starting at `block`.
+    #[error("No peer could serve data starting at block {block}")]
+    PeersExhausted { block: BlockNumber },
+    /// Like [`SyncError::PeersExhausted`], but during the last pass `peers`
+    /// sent data for `block` which could not be converted from its DTO. This
+    /// points at a protocol mismatch rather than at the data being
+    /// unavailable.
+    #[error("No peer could serve block {block}, invalid data from {peers:?}")]
+    Conversion {
+        block: BlockNumber,
+        peers: Vec<PeerId>,
+    },
+    /// Any other failure, such as the stream of expected counts failing.
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
 
 pub trait HeaderStream {
+    /// Yields every `step`-th header, starting from `start`, or from `stop`
+    /// when `reverse` is set.
+    ///
+    /// Yields a [`SyncError`] and ends once the configured number of
+    /// consecutive passes over the peer set did not yield a single header.
     fn header_stream(
         self,
         start: BlockNumber,
         stop: BlockNumber,
         reverse: bool,
-    ) -> impl Stream<Item = PeerData<SignedBlockHeader>> + Send;
+        step: std::num::NonZeroU64,
+    ) -> impl Stream<Item = StreamItem<SignedBlockHeader, SyncError>> + Send;
 }
 
 pub trait TransactionStream {
+    /// With `reverse` set, blocks are streamed from `stop` down to `start` and
+    /// `transaction_count_stream` must yield counts in that order too.
+    ///
+    /// Yields a [`SyncError`] and ends once the configured number of
+    /// consecutive passes over the peer set did not complete a single block.
     fn transaction_stream(
         self,
         start: BlockNumber,
         stop: BlockNumber,
+        reverse: bool,
         transaction_count_stream: impl Stream<Item = anyhow::Result<usize>> + Send + 'static,
-    ) -> impl Stream<Item = StreamItem<(TransactionData, BlockNumber)>> + Send;
+    ) -> impl Stream<Item = StreamItem<(TransactionData, BlockNumber), SyncError>> + Send;
 }
 
 pub trait StateDiffStream {
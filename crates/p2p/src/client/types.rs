@@ -3,6 +3,7 @@ use fake::Dummy;
 use libp2p::PeerId;
 use pathfinder_common::event::Event;
 use pathfinder_common::receipt::{ExecutionResources, ExecutionStatus, L2ToL1Message};
+use pathfinder_common::state_update::StateUpdateData;
 use pathfinder_common::transaction::Transaction;
 use pathfinder_common::{
     BlockCommitmentSignature,
@@ -79,6 +80,15 @@ impl From<pathfinder_common::receipt::Receipt> for Receipt {
 
 pub type TransactionData = Vec<(Transaction, Receipt)>;
 
+/// A signed block header together with the block's transactions and state
+/// diff.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FullBlock {
+    pub header: SignedBlockHeader,
+    pub transactions: TransactionData,
+    pub state_diff: StateUpdateData,
+}
+
 pub type EventsForBlockByTransaction = (BlockNumber, Vec<(TransactionHash, Vec<Event>)>);
 
 impl TryFromDto<p2p_proto::header::SignedBlockHeader> for SignedBlockHeader {
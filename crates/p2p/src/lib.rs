@@ -71,6 +71,9 @@ pub struct Config {
     pub stream_timeout: Duration,
     /// Applies to each of the p2p-stream protocols separately
     pub max_concurrent_streams: usize,
+    /// Maximum size in bytes of a single header or transaction response.
+    /// Larger responses are rejected before they are decoded.
+    pub max_response_size: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -91,14 +91,27 @@ pub(crate) mod codec {
         ONE_MIB,
     >;
 
+    /// Rejects responses larger than `RESPONSE_SIZE_LIMIT` bytes before
+    /// decoding them, unless [created](Self::with_response_size_limit) with a
+    /// different limit.
     #[derive(Clone)]
-    pub struct ProdCodec<Protocol, Req, Resp, ProstReq, ProstResp, const RESPONSE_SIZE_LIMIT: usize>(
-        PhantomData<(Protocol, Req, Resp, ProstReq, ProstResp)>,
-    );
+    pub struct ProdCodec<Protocol, Req, Resp, ProstReq, ProstResp, const RESPONSE_SIZE_LIMIT: usize> {
+        response_size_limit: usize,
+        _x: PhantomData<(Protocol, Req, Resp, ProstReq, ProstResp)>,
+    }
 
     impl<A, B, C, D, E, const F: usize> Default for ProdCodec<A, B, C, D, E, F> {
         fn default() -> Self {
-            Self(Default::default())
+            Self::with_response_size_limit(F)
+        }
+    }
+
+    impl<A, B, C, D, E, const F: usize> ProdCodec<A, B, C, D, E, F> {
+        pub fn with_response_size_limit(response_size_limit: usize) -> Self {
+            Self {
+                response_size_limit,
+                _x: Default::default(),
+            }
         }
     }
 
@@ -129,6 +142,13 @@ pub(crate) mod codec {
         }
     }
 
+    impl<A, B, C, D, E, const F: usize> SyncCodec<A, B, C, D, E, F> {
+        /// See [`ProdCodec::with_response_size_limit`].
+        pub fn with_response_size_limit(response_size_limit: usize) -> Self {
+            Self::Prod(ProdCodec::with_response_size_limit(response_size_limit))
+        }
+    }
+
     #[cfg(test)]
     impl<A, B, C, D, E, const F: usize> SyncCodec<A, B, C, D, E, F> {
         pub fn for_test() -> Self {
@@ -180,12 +200,12 @@ pub(crate) mod codec {
                 .await
                 .map_err(Into::<std::io::Error>::into)?;
 
-            if encoded_len > RESPONSE_SIZE_LIMIT {
+            if encoded_len > self.response_size_limit {
                 return Err(std::io::Error::new(
                     std::io::ErrorKind::InvalidData,
                     format!(
                         "Encoded length {} exceeds the maximum buffer size {}",
-                        encoded_len, RESPONSE_SIZE_LIMIT
+                        encoded_len, self.response_size_limit
                     ),
                 ));
             }
@@ -305,3 +325,34 @@ pub(crate) mod codec {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use p2p_proto::header::BlockHeadersResponse;
+    use p2p_stream::Codec;
+
+    use super::{codec, protocol};
+
+    #[tokio::test]
+    async fn response_over_size_limit_is_rejected() {
+        let mut encoded = Vec::new();
+        codec::Headers::default()
+            .write_response(&protocol::Headers, &mut encoded, BlockHeadersResponse::Fin)
+            .await
+            .unwrap();
+        // Excluding the length prefix.
+        let response_size = encoded.len() - 1;
+
+        let error = codec::Headers::with_response_size_limit(response_size - 1)
+            .read_response(&protocol::Headers, &mut encoded.as_slice())
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+        let response = codec::Headers::with_response_size_limit(response_size)
+            .read_response(&protocol::Headers, &mut encoded.as_slice())
+            .await
+            .unwrap();
+        assert_eq!(response, BlockHeadersResponse::Fin);
+    }
+}
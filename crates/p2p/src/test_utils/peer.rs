@@ -45,6 +45,7 @@ impl Config {
             kad_name: Default::default(),
             stream_timeout: Duration::from_secs(10),
             max_concurrent_streams: 100,
+            max_response_size: crate::sync::codec::ONE_MIB,
         }
     }
 }
@@ -140,6 +140,43 @@ Examples:
     )]
     l1_poll_interval: std::num::NonZeroU64,
 
+    #[arg(
+        long = "sync.poll-backoff-base",
+        long_help = "Delay in seconds before polling for a new block again after a failed \
+                     request. The delay doubles after each consecutive failure, up to \
+                     `sync.poll-backoff-cap`",
+        default_value = "1",
+        env = "PATHFINDER_HEAD_POLL_BACKOFF_BASE_SECONDS"
+    )]
+    poll_backoff_base: std::num::NonZeroU64,
+
+    #[arg(
+        long = "sync.poll-backoff-cap",
+        long_help = "Maximum delay in seconds between new block polls while requests keep failing",
+        default_value = "30",
+        env = "PATHFINDER_HEAD_POLL_BACKOFF_CAP_SECONDS"
+    )]
+    poll_backoff_cap: std::num::NonZeroU64,
+
+    #[arg(
+        long = "sync.restart-backoff-base",
+        long_help = "Delay in seconds before restarting a failed L1 or L2 sync task. The delay \
+                     doubles after each consecutive failure, up to `sync.restart-backoff-cap`",
+        default_value = "5",
+        env = "PATHFINDER_SYNC_RESTART_BACKOFF_BASE_SECONDS"
+    )]
+    restart_backoff_base: std::num::NonZeroU64,
+
+    #[arg(
+        long = "sync.restart-backoff-cap",
+        long_help = "Maximum delay in seconds before restarting a failed L1 or L2 sync task. The \
+                     delay goes back to `sync.restart-backoff-base` once the task has produced an \
+                     update",
+        default_value = "60",
+        env = "PATHFINDER_SYNC_RESTART_BACKOFF_CAP_SECONDS"
+    )]
+    restart_backoff_cap: std::num::NonZeroU64,
+
     #[arg(
         long = "color",
         long_help = "This flag controls when to use colors in the output logs.",
@@ -268,6 +305,16 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     event_filter_cache_size: std::num::NonZeroUsize,
 
+    #[arg(
+        long = "storage.trie-node-cache-size",
+        long_help = "The number of global state trie nodes to cache in memory. Nodes shared \
+                     between consecutive blocks are then not re-read from the database when \
+                     updating the state trie. Zero disables the cache.",
+        env = "PATHFINDER_STORAGE_TRIE_NODE_CACHE_SIZE",
+        default_value = "0"
+    )]
+    trie_node_cache_size: usize,
+
     #[arg(
         long = "rpc.get-events-max-blocks-to-scan",
         long_help = "The number of blocks to scan when querying for events. This limit is used to \
@@ -317,6 +364,103 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     fetch_casm_from_fgw: bool,
 
+    #[arg(
+        long = "sync.redownload-retries",
+        long_help = "How many times a block is downloaded again after it failed verification, \
+                     before sync is terminated. A block fails verification if its state root does \
+                     not match the locally computed one or its transactions and receipts do not \
+                     pair up",
+        env = "PATHFINDER_SYNC_REDOWNLOAD_RETRIES",
+        default_value = "3"
+    )]
+    sync_redownload_retries: usize,
+
+    #[arg(
+        long = "sync.state-root-mismatch-retries",
+        long_help = "Deprecated, use `sync.redownload-retries` instead",
+        env = "PATHFINDER_SYNC_STATE_ROOT_MISMATCH_RETRIES",
+        conflicts_with = "sync_redownload_retries",
+        hide = true
+    )]
+    state_root_mismatch_retries: Option<usize>,
+
+    #[arg(
+        long = "sync.block-time-weight",
+        long_help = "Weight of the latest block in the moving average of block processing time \
+                     reported in the logs. Must be in the range (0.0, 1.0]; larger values make \
+                     the average follow recent blocks more closely",
+        env = "PATHFINDER_SYNC_BLOCK_TIME_WEIGHT",
+        default_value = "0.05",
+        value_parser = parse_block_time_weight
+    )]
+    block_time_weight: f32,
+
+    #[arg(
+        long = "sync.event-channel-capacity",
+        long_help = "Number of downloaded L1 and L2 updates that may be queued while earlier ones \
+                     are written to the database",
+        env = "PATHFINDER_SYNC_EVENT_CHANNEL_CAPACITY",
+        default_value = "8"
+    )]
+    sync_event_channel_capacity: NonZeroUsize,
+
+    #[arg(
+        long = "sync.status-update-capacity",
+        long_help = "The capacity of the channel publishing sync status changes to subscribers. \
+                     Subscribers lagging behind by more than this many updates miss the \
+                     intermediate ones",
+        env = "PATHFINDER_SYNC_STATUS_UPDATE_CAPACITY",
+        default_value = "100"
+    )]
+    sync_status_update_capacity: NonZeroUsize,
+
+    #[arg(
+        long = "sync.max-reorg-depth",
+        long_help = "Maximum number of blocks a single L2 reorg may remove. Sync pauses instead \
+                     of applying a deeper reorg, leaving the database untouched while RPC keeps \
+                     being served. Unlimited by default",
+        env = "PATHFINDER_SYNC_MAX_REORG_DEPTH"
+    )]
+    sync_max_reorg_depth: Option<std::num::NonZeroU64>,
+
+    #[arg(
+        long = "sync.store-transactions",
+        long_help = "Whether to store transactions, receipts and events of synced blocks. When \
+                     disabled only block headers and state are stored",
+        env = "PATHFINDER_SYNC_STORE_TRANSACTIONS",
+        default_value = "true",
+        action = clap::ArgAction::Set
+    )]
+    sync_store_transactions: bool,
+
+    #[arg(
+        long = "sync.prefer-l1",
+        long_help = "Process queued L1 updates ahead of queued L2 blocks, so that the block \
+                     confirmed on L1 advances promptly while catching up",
+        env = "PATHFINDER_SYNC_PREFER_L1",
+        default_value = "false",
+        action = clap::ArgAction::Set
+    )]
+    sync_prefer_l1: bool,
+
+    #[arg(
+        long = "sync.wal-checkpoint-blocks",
+        long_help = "Number of stored blocks after which sync checkpoints and truncates the \
+                     database WAL",
+        default_value = "1000",
+        env = "PATHFINDER_SYNC_WAL_CHECKPOINT_BLOCKS"
+    )]
+    sync_wal_checkpoint_blocks: std::num::NonZeroU64,
+
+    #[arg(
+        long = "sync.wal-checkpoint-interval",
+        long_help = "Seconds after which sync checkpoints and truncates the database WAL, even if \
+                     fewer than `sync.wal-checkpoint-blocks` blocks have been stored",
+        default_value = "60",
+        env = "PATHFINDER_SYNC_WAL_CHECKPOINT_INTERVAL_SECONDS"
+    )]
+    sync_wal_checkpoint_interval: std::num::NonZeroU64,
+
     #[arg(
         long = "shutdown.grace-period",
         value_name = "Seconds",
@@ -372,6 +516,15 @@ fn parse_state_tries(s: &str) -> Result<StateTries, String> {
     }
 }
 
+fn parse_block_time_weight(s: &str) -> Result<f32, String> {
+    let value: f32 = s.parse().map_err(|_| "Expected a number".to_string())?;
+    if value > 0.0 && value <= 1.0 {
+        Ok(value)
+    } else {
+        Err("Expected a value in the range (0.0, 1.0]".to_string())
+    }
+}
+
 #[derive(clap::Args)]
 struct NetworkCli {
     #[arg(
@@ -534,6 +687,37 @@ Example:
     )]
     stream_timeout: u32,
 
+    #[arg(
+        long = "p2p.experimental.response-timeout",
+        long_help = "Maximum time to wait for the next response from a peer during sync before \
+                     switching to another peer.",
+        value_name = "SECONDS",
+        default_value = "10",
+        env = "PATHFINDER_P2P_EXPERIMENTAL_RESPONSE_TIMEOUT"
+    )]
+    response_timeout: u32,
+
+    #[arg(
+        long = "p2p.experimental.max-sync-attempts",
+        long_help = "Number of consecutive rounds over all known peers that may fail to yield any \
+                     data before a sync stream gives up.",
+        value_name = "ATTEMPTS",
+        default_value = "3",
+        env = "PATHFINDER_P2P_EXPERIMENTAL_MAX_SYNC_ATTEMPTS"
+    )]
+    max_sync_attempts: NonZeroUsize,
+
+    #[arg(
+        long = "p2p.experimental.max-response-size",
+        long_help = "Maximum size of a single header or transaction response message received \
+                     from a peer during sync. Larger messages are rejected before they are \
+                     decoded.",
+        value_name = "BYTES",
+        default_value = "1048576",
+        env = "PATHFINDER_P2P_EXPERIMENTAL_MAX_RESPONSE_SIZE"
+    )]
+    max_response_size: usize,
+
     #[arg(
         long = "p2p.experimental.max-concurrent-streams",
         long_help = "Maximum allowed number of concurrent streams per each \
@@ -577,12 +761,13 @@ struct DebugCli {
 
     #[arg(
         long = "debug.restart-delay",
-        long_help = "L2 restart delay after failure, in seconds",
+        long_help = "Deprecated, use `sync.restart-backoff-base` instead",
         action = clap::ArgAction::Set,
-        default_value = "60",
         env = "PATHFINDER_RESTART_DELAY",
+        conflicts_with = "restart_backoff_base",
+        hide = true,
     )]
-    restart_delay: u64,
+    restart_delay: Option<std::num::NonZeroU64>,
 }
 
 #[derive(clap::ValueEnum, Clone, serde::Deserialize)]
@@ -715,6 +900,10 @@ pub struct Config {
     pub sqlite_wal: JournalMode,
     pub max_rpc_connections: std::num::NonZeroUsize,
     pub poll_interval: Duration,
+    pub poll_backoff_base: Duration,
+    pub poll_backoff_cap: Duration,
+    pub restart_backoff_base: Duration,
+    pub restart_backoff_cap: Duration,
     pub l1_poll_interval: Duration,
     pub color: Color,
     pub log_output_json: bool,
@@ -728,12 +917,23 @@ pub struct Config {
     pub gateway_api_key: Option<String>,
     pub gateway_timeout: Duration,
     pub event_filter_cache_size: NonZeroUsize,
+    pub trie_node_cache_size: usize,
     pub get_events_max_blocks_to_scan: NonZeroUsize,
     pub get_events_max_uncached_event_filters_to_load: NonZeroUsize,
     pub state_tries: Option<StateTries>,
     pub custom_versioned_constants: Option<VersionedConstants>,
     pub feeder_gateway_fetch_concurrency: NonZeroUsize,
     pub fetch_casm_from_fgw: bool,
+    pub sync_redownload_retries: usize,
+    pub deprecated_state_root_mismatch_retries: Option<usize>,
+    pub block_time_weight: f32,
+    pub sync_event_channel_capacity: NonZeroUsize,
+    pub sync_status_update_capacity: NonZeroUsize,
+    pub sync_max_reorg_depth: Option<std::num::NonZeroU64>,
+    pub sync_store_transactions: bool,
+    pub sync_prefer_l1: bool,
+    pub sync_wal_checkpoint_blocks: std::num::NonZeroU64,
+    pub sync_wal_checkpoint_interval: Duration,
     pub shutdown_grace_period: Duration,
 }
 
@@ -769,6 +969,9 @@ pub struct P2PConfig {
     pub kad_name: Option<String>,
     pub l1_checkpoint_override: Option<pathfinder_ethereum::EthereumStateUpdate>,
     pub stream_timeout: Duration,
+    pub response_timeout: Duration,
+    pub max_sync_attempts: NonZeroUsize,
+    pub max_response_size: usize,
     pub max_concurrent_streams: usize,
     pub direct_connection_timeout: Duration,
     pub eviction_timeout: Duration,
@@ -780,7 +983,8 @@ pub struct P2PConfig;
 
 pub struct DebugConfig {
     pub pretty_log: bool,
-    pub restart_delay: Duration,
+    /// Deprecated alias of [`Config::restart_backoff_base`].
+    pub restart_delay: Option<Duration>,
 }
 
 impl NetworkConfig {
@@ -919,6 +1123,9 @@ impl P2PConfig {
             kad_name: args.kad_name,
             l1_checkpoint_override,
             stream_timeout: Duration::from_secs(args.stream_timeout.into()),
+            response_timeout: Duration::from_secs(args.response_timeout.into()),
+            max_sync_attempts: args.max_sync_attempts,
+            max_response_size: args.max_response_size,
             max_concurrent_streams: args.max_concurrent_streams,
             direct_connection_timeout: Duration::from_secs(args.direct_connection_timeout.into()),
             eviction_timeout: Duration::from_secs(args.eviction_timeout.into()),
@@ -966,7 +1173,7 @@ impl DebugConfig {
     fn parse(_: ()) -> Self {
         Self {
             pretty_log: false,
-            restart_delay: Duration::from_secs(60),
+            restart_delay: None,
         }
     }
 }
@@ -976,7 +1183,9 @@ impl DebugConfig {
     fn parse(args: DebugCli) -> Self {
         Self {
             pretty_log: args.pretty_log,
-            restart_delay: Duration::from_secs(args.restart_delay),
+            restart_delay: args
+                .restart_delay
+                .map(|delay| Duration::from_secs(delay.get())),
         }
     }
 }
@@ -987,6 +1196,7 @@ impl Config {
         let cli = Cli::parse();
 
         let network = NetworkConfig::from_components(cli.network);
+        let debug = DebugConfig::parse(cli.debug);
 
         Config {
             data_directory: cli.data_directory,
@@ -1007,18 +1217,25 @@ impl Config {
             },
             max_rpc_connections: cli.max_rpc_connections,
             poll_interval: Duration::from_secs(cli.poll_interval.get()),
+            poll_backoff_base: Duration::from_secs(cli.poll_backoff_base.get()),
+            poll_backoff_cap: Duration::from_secs(cli.poll_backoff_cap.get()),
+            restart_backoff_base: debug
+                .restart_delay
+                .unwrap_or(Duration::from_secs(cli.restart_backoff_base.get())),
+            restart_backoff_cap: Duration::from_secs(cli.restart_backoff_cap.get()),
             l1_poll_interval: Duration::from_secs(cli.l1_poll_interval.get()),
             color: cli.color,
             log_output_json: cli.log_output_json,
             disable_version_update_check: cli.disable_version_update_check,
             p2p: P2PConfig::parse_or_exit(cli.p2p),
-            debug: DebugConfig::parse(cli.debug),
+            debug,
             verify_tree_hashes: cli.verify_tree_node_data,
             rpc_batch_concurrency_limit: cli.rpc_batch_concurrency_limit,
             is_sync_enabled: cli.is_sync_enabled,
             is_rpc_enabled: cli.is_rpc_enabled,
             gateway_api_key: cli.gateway_api_key,
             event_filter_cache_size: cli.event_filter_cache_size,
+            trie_node_cache_size: cli.trie_node_cache_size,
             get_events_max_blocks_to_scan: cli.get_events_max_blocks_to_scan,
             get_events_max_uncached_event_filters_to_load: cli
                 .get_events_max_uncached_event_filters_to_load,
@@ -1029,6 +1246,20 @@ impl Config {
                 .custom_versioned_constants_path
                 .map(parse_versioned_constants_or_exit),
             fetch_casm_from_fgw: cli.fetch_casm_from_fgw,
+            sync_redownload_retries: cli
+                .state_root_mismatch_retries
+                .unwrap_or(cli.sync_redownload_retries),
+            deprecated_state_root_mismatch_retries: cli.state_root_mismatch_retries,
+            block_time_weight: cli.block_time_weight,
+            sync_event_channel_capacity: cli.sync_event_channel_capacity,
+            sync_status_update_capacity: cli.sync_status_update_capacity,
+            sync_max_reorg_depth: cli.sync_max_reorg_depth,
+            sync_store_transactions: cli.sync_store_transactions,
+            sync_prefer_l1: cli.sync_prefer_l1,
+            sync_wal_checkpoint_blocks: cli.sync_wal_checkpoint_blocks,
+            sync_wal_checkpoint_interval: Duration::from_secs(
+                cli.sync_wal_checkpoint_interval.get(),
+            ),
             shutdown_grace_period: Duration::from_secs(cli.shutdown_grace_period.get()),
         }
     }
@@ -1071,7 +1302,7 @@ mod tests {
     use assert_matches::assert_matches;
 
     use super::{AllowedOrigins, RpcCorsDomainsParseError};
-    use crate::config::{parse_cors, ParseVersionedConstantsError};
+    use crate::config::{parse_block_time_weight, parse_cors, ParseVersionedConstantsError};
 
     #[test]
     fn parse_cors_domains() {
@@ -1148,6 +1379,17 @@ mod tests {
         });
     }
 
+    #[test]
+    fn parse_block_time_weight_range() {
+        assert_eq!(parse_block_time_weight("0.05"), Ok(0.05));
+        assert_eq!(parse_block_time_weight("1"), Ok(1.0));
+        assert!(parse_block_time_weight("0").is_err());
+        assert!(parse_block_time_weight("-0.5").is_err());
+        assert!(parse_block_time_weight("1.01").is_err());
+        assert!(parse_block_time_weight("NaN").is_err());
+        assert!(parse_block_time_weight("abc").is_err());
+    }
+
     #[test]
     fn parse_versioned_constants_fails_if_file_not_found() {
         assert_matches!(
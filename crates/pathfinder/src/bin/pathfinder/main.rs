@@ -66,6 +66,17 @@ async fn async_main() -> anyhow::Result<Storage> {
         "🏁 Starting node."
     );
 
+    if config.debug.restart_delay.is_some() {
+        warn!("`--debug.restart-delay` is deprecated, use `--sync.restart-backoff-base` instead");
+    }
+
+    if config.deprecated_state_root_mismatch_retries.is_some() {
+        warn!(
+            "`--sync.state-root-mismatch-retries` is deprecated, use `--sync.redownload-retries` \
+             instead"
+        );
+    }
+
     if !config.data_directory.exists() {
         std::fs::DirBuilder::new()
             .create(&config.data_directory)
@@ -85,7 +96,9 @@ async fn async_main() -> anyhow::Result<Storage> {
     // monitoring.
     let readiness = Arc::new(AtomicBool::new(false));
 
-    let sync_state = Arc::new(SyncState::default());
+    let sync_state = Arc::new(SyncState::with_updates_capacity(
+        config.sync_status_update_capacity,
+    ));
 
     let ethereum = EthereumContext::setup(config.ethereum.url.clone(), &config.ethereum.password)
         .await
@@ -128,6 +141,7 @@ async fn async_main() -> anyhow::Result<Storage> {
         pathfinder_storage::StorageBuilder::file(pathfinder_context.database.clone())
             .journal_mode(config.sqlite_wal)
             .event_filter_cache_size(config.event_filter_cache_size.get())
+            .trie_node_cache_size(config.trie_node_cache_size)
             .trie_prune_mode(match config.state_tries {
                 Some(StateTries::Pruned(num_blocks_kept)) => {
                     Some(pathfinder_storage::TriePruneMode::Prune { num_blocks_kept })
@@ -277,6 +291,7 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
 
     let (p2p_handle, gossiper, p2p_client) = start_p2p(
         pathfinder_context.network_id,
+        gateway_public_key,
         p2p_storage,
         config.p2p.clone(),
     )
@@ -441,6 +456,7 @@ fn permission_check(base: &std::path::Path) -> Result<(), anyhow::Error> {
 #[cfg(feature = "p2p")]
 async fn start_p2p(
     chain_id: ChainId,
+    gateway_public_key: pathfinder_common::PublicKey,
     storage: Storage,
     config: config::P2PConfig,
 ) -> anyhow::Result<(
@@ -502,7 +518,13 @@ async fn start_p2p(
             kad_name: config.kad_name,
             stream_timeout: config.stream_timeout,
             max_concurrent_streams: config.max_concurrent_streams,
+            max_response_size: config.max_response_size,
+        },
+        response_timeout: config.response_timeout,
+        retry_policy: p2p::client::peer_agnostic::RetryPolicy {
+            max_attempts: config.max_sync_attempts,
         },
+        header_public_key: Some(gateway_public_key),
         chain_id,
         storage,
         proxy: config.proxy,
@@ -525,6 +547,7 @@ async fn start_p2p(
 #[cfg(not(feature = "p2p"))]
 async fn start_p2p(
     _: ChainId,
+    _: pathfinder_common::PublicKey,
     _: Storage,
     _: config::P2PConfig,
 ) -> anyhow::Result<(
@@ -632,21 +655,43 @@ fn start_feeder_gateway_sync(
         sequencer: pathfinder_context.gateway,
         state: sync_state.clone(),
         head_poll_interval: config.poll_interval,
+        head_poll_backoff: state::l2::PollBackoff {
+            base: config.poll_backoff_base,
+            cap: config.poll_backoff_cap,
+        },
         l1_poll_interval: config.l1_poll_interval,
         pending_data: tx_pending,
         block_validation_mode: state::l2::BlockValidationMode::Strict,
         websocket_txs,
         notifications,
         block_cache_size: 1_000,
-        restart_delay: config.debug.restart_delay,
+        restart_backoff: state::RestartBackoff {
+            base: config.restart_backoff_base,
+            cap: config.restart_backoff_cap,
+        },
         verify_tree_hashes: config.verify_tree_hashes,
         gossiper,
         sequencer_public_key: gateway_public_key,
         fetch_concurrency: config.feeder_gateway_fetch_concurrency,
         fetch_casm_from_fgw: config.fetch_casm_from_fgw,
+        redownload_retries: config.sync_redownload_retries,
+        block_time_weight: config.block_time_weight,
+        event_channel_capacity: config.sync_event_channel_capacity,
+        max_reorg_depth: config.sync_max_reorg_depth,
+        reorg_window: std::time::Duration::from_millis(200),
+        store_transactions: config.sync_store_transactions,
+        wal_checkpoint: state::WalCheckpointCadence {
+            blocks: config.sync_wal_checkpoint_blocks,
+            interval: config.sync_wal_checkpoint_interval,
+        },
+        clock: Arc::new(state::SystemClock),
+        prefer_l1: config.sync_prefer_l1,
+        shutdown: util::task::cancellation_token(),
     };
 
-    util::task::spawn(state::sync(sync_context, state::l1::sync, state::l2::sync))
+    // Sync stops its tasks itself once cancelled, so that graceful shutdown does
+    // not drop them at an arbitrary point.
+    util::task::spawn_uncancelled(state::sync(sync_context, state::l1::sync, state::l2::sync))
 }
 
 #[cfg(feature = "p2p")]
@@ -661,6 +706,7 @@ fn start_p2p_sync(
 ) -> tokio::task::JoinHandle<anyhow::Result<()>> {
     use pathfinder_block_hashes::BlockHashDb;
 
+    let warm_up_client = p2p_client.clone();
     let sync = pathfinder_lib::sync::Sync {
         storage,
         p2p: p2p_client,
@@ -673,6 +719,8 @@ fn start_p2p_sync(
         verify_tree_hashes,
         block_hash_db: Some(BlockHashDb::new(pathfinder_context.network)),
     };
+    // Discover peers while the L1 checkpoint is being fetched.
+    util::task::spawn(async move { warm_up_client.warm_peer_cache().await });
     util::task::spawn(sync.run())
 }
 
@@ -89,7 +89,6 @@ mod tests {
     use pathfinder_common::BlockNumber;
     use pathfinder_rpc::types::syncing::{NumberedBlock, Status, Syncing};
     use pathfinder_rpc::SyncState;
-    use tokio::sync::RwLock;
 
     async fn wait_healthy(client: &reqwest::Client, url: reqwest::Url) {
         let url = url.join("health").unwrap();
@@ -158,9 +157,7 @@ mod tests {
     async fn synced() {
         let readiness = Arc::new(AtomicBool::new(false));
         let handle = PrometheusBuilder::new().build_recorder().handle();
-        let sync_state = Arc::new(SyncState {
-            status: RwLock::new(Syncing::False),
-        });
+        let sync_state = Arc::new(SyncState::default());
         let (addr, _) = super::spawn_server(
             ([127, 0, 0, 1], 0),
             readiness.clone(),
@@ -197,6 +194,8 @@ mod tests {
                 hash: Default::default(),
                 number: BlockNumber::new_or_panic(100),
             },
+            current_block_time_avg: None,
+            l1_l2_confirmed_block: None,
         });
         let resp = client.get(url.clone()).send().await.unwrap();
         assert_eq!(resp.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
@@ -214,6 +213,8 @@ mod tests {
                 hash: Default::default(),
                 number: BlockNumber::new_or_panic(100),
             },
+            current_block_time_avg: None,
+            l1_l2_confirmed_block: None,
         });
         let resp = client.get(url.clone()).send().await.unwrap();
         assert_eq!(resp.status(), reqwest::StatusCode::OK);
@@ -4,7 +4,7 @@ use p2p::libp2p::identity::Keypair;
 use p2p::libp2p::multiaddr::Multiaddr;
 use p2p::{HeadRx, HeadTx};
 use p2p_proto::header::BlockHeadersResponse;
-use pathfinder_common::{BlockHash, BlockNumber, ChainId};
+use pathfinder_common::{BlockHash, BlockNumber, ChainId, PublicKey};
 use pathfinder_storage::Storage;
 use tracing::Instrument;
 
@@ -21,6 +21,11 @@ pub type P2PNetworkHandle = (
 
 pub struct P2PContext {
     pub cfg: p2p::Config,
+    /// Timeout for each response of a sync request sent to a peer.
+    pub response_timeout: std::time::Duration,
+    pub retry_policy: peer_agnostic::RetryPolicy,
+    /// If set, headers received from peers are checked against this key.
+    pub header_public_key: Option<PublicKey>,
     pub chain_id: ChainId,
     pub storage: Storage,
     pub proxy: bool,
@@ -34,6 +39,9 @@ pub struct P2PContext {
 pub async fn start(context: P2PContext) -> anyhow::Result<P2PNetworkHandle> {
     let P2PContext {
         cfg,
+        response_timeout,
+        retry_policy,
+        header_public_key,
         chain_id,
         storage,
         proxy,
@@ -122,7 +130,13 @@ pub async fn start(context: P2PContext) -> anyhow::Result<P2PNetworkHandle> {
     };
 
     Ok((
-        peer_agnostic::Client::new(p2p_client, block_propagation_topic),
+        peer_agnostic::Client::new(
+            p2p_client,
+            block_propagation_topic,
+            response_timeout,
+            retry_policy,
+            header_public_key,
+        ),
         rx,
         join_handle,
     ))
@@ -1,4 +1,21 @@
 pub mod block_hash;
+mod replay;
 mod sync;
 
-pub use sync::{l1, l2, revert, sync, Gossiper, SyncContext, RESET_DELAY_ON_FAILURE};
+pub use replay::replay;
+pub use sync::{
+    l1,
+    l2,
+    reorg_to,
+    revert,
+    sync,
+    test_utils,
+    Clock,
+    Gossiper,
+    RestartBackoff,
+    SyncContext,
+    SyncEvent,
+    SystemClock,
+    WalCheckpointCadence,
+    RESET_DELAY_ON_FAILURE,
+};
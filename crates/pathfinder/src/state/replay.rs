@@ -0,0 +1,142 @@
+use anyhow::Context;
+use pathfinder_common::{BlockNumber, StateCommitment};
+use pathfinder_merkle_tree::starknet_state::update_starknet_state;
+use pathfinder_storage::{Connection, StorageBuilder, TriePruneMode};
+
+use super::sync::StateRootMismatch;
+
+/// Re-applies the stored state updates of blocks up to and including `to` to
+/// fresh in-memory tries, and checks that the recomputed state commitment of
+/// every block in `from..=to` matches the stored one.
+///
+/// The blocks before `from` are replayed as well, since their state is needed
+/// to compute the tries of `from`, but their commitments are not checked. The
+/// database behind `connection` is only read from.
+///
+/// Returns a [StateRootMismatch] error for the first block that doesn't match.
+pub fn replay(
+    connection: &mut Connection,
+    from: BlockNumber,
+    to: BlockNumber,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(from <= to, "Invalid block range {from}..={to}");
+
+    let source = connection
+        .transaction()
+        .context("Creating database transaction")?;
+
+    let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+        TriePruneMode::Archive,
+        std::num::NonZeroU32::new(5).unwrap(),
+    )
+    .context("Creating in-memory storage")?;
+    let mut replay_connection = storage
+        .connection()
+        .context("Creating in-memory database connection")?;
+
+    let mut block = BlockNumber::GENESIS;
+    while block <= to {
+        let header = source
+            .block_header(block.into())
+            .context("Fetching block header")?
+            .with_context(|| format!("Block header {block} is missing"))?;
+        let state_update = source
+            .state_update(block.into())
+            .context("Fetching state update")?
+            .with_context(|| format!("State update {block} is missing"))?;
+
+        let transaction = replay_connection
+            .transaction()
+            .context("Creating in-memory database transaction")?;
+        transaction
+            .insert_block_header(&header)
+            .context("Inserting block header")?;
+        transaction
+            .insert_state_update(block, &state_update)
+            .context("Inserting state update")?;
+        let (storage_commitment, class_commitment) = update_starknet_state(
+            &transaction,
+            (&state_update).into(),
+            false,
+            block,
+            storage.clone(),
+        )
+        .with_context(|| format!("Updating Starknet state for block {block}"))?;
+        transaction
+            .commit()
+            .context("Committing in-memory database transaction")?;
+
+        let computed = StateCommitment::calculate(storage_commitment, class_commitment);
+        if block >= from && computed != header.state_commitment {
+            return Err(StateRootMismatch {
+                block_number: block,
+                expected: header.state_commitment,
+                computed,
+            }
+            .into());
+        }
+
+        block += 1;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_storage::fake::{fill, generate, Config};
+
+    use super::*;
+
+    fn setup() -> (
+        pathfinder_storage::Storage,
+        Vec<pathfinder_storage::fake::Block>,
+    ) {
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+        let blocks = generate::with_config(
+            2,
+            Config {
+                update_tries: Box::new(update_starknet_state),
+                ..Default::default()
+            },
+        );
+        (storage, blocks)
+    }
+
+    #[test]
+    fn replayed_roots_match() {
+        let (storage, blocks) = setup();
+        fill(&storage, &blocks, Some(Box::new(update_starknet_state)));
+
+        let mut connection = storage.connection().unwrap();
+        replay(
+            &mut connection,
+            BlockNumber::GENESIS,
+            BlockNumber::new_or_panic(1),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn mismatch_is_reported() {
+        let (storage, mut blocks) = setup();
+        let expected = StateCommitment(pathfinder_crypto::Felt::from_u64(1));
+        blocks[1].header.header.state_commitment = expected;
+        fill(&storage, &blocks, Some(Box::new(update_starknet_state)));
+
+        let mut connection = storage.connection().unwrap();
+        let error = replay(
+            &mut connection,
+            BlockNumber::GENESIS,
+            BlockNumber::new_or_panic(1),
+        )
+        .unwrap_err();
+        let mismatch = error.downcast_ref::<StateRootMismatch>().unwrap();
+        assert_eq!(mismatch.block_number, BlockNumber::new_or_panic(1));
+        assert_eq!(mismatch.expected, expected);
+    }
+}
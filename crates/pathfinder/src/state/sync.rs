@@ -1,8 +1,10 @@
 mod class;
+mod clock;
 pub mod l1;
 pub mod l2;
 mod pending;
 pub mod revert;
+pub mod test_utils;
 
 use std::future::Future;
 use std::sync::Arc;
@@ -19,16 +21,20 @@ use pathfinder_common::{
 };
 use pathfinder_crypto::Felt;
 use pathfinder_ethereum::{EthereumApi, EthereumStateUpdate};
-use pathfinder_merkle_tree::starknet_state::update_starknet_state;
-use pathfinder_rpc::types::syncing::{self, NumberedBlock, Syncing};
+use pathfinder_merkle_tree::starknet_state::{
+    update_starknet_state,
+    update_starknet_state_with_contract_hashes,
+};
+use pathfinder_rpc::types::syncing::{self, NumberedBlock, SyncTask, Syncing};
 use pathfinder_rpc::{Notifications, PendingData, Reorg, SyncState, TopicBroadcasters};
-use pathfinder_storage::{Connection, Storage, TransactionBehavior};
+use pathfinder_storage::{BlockTimings, Connection, Storage, TransactionBehavior};
 use primitive_types::H160;
 use starknet_gateway_client::GatewayApi;
 use starknet_gateway_types::reply::{Block, PendingBlock};
 use tokio::sync::mpsc::{self, Receiver};
 use tokio::sync::watch::Sender as WatchSender;
 
+pub use self::clock::{Clock, SystemClock};
 use crate::state::l1::L1SyncContext;
 use crate::state::l2::{BlockChain, L2SyncContext};
 
@@ -58,11 +64,17 @@ pub enum SyncEvent {
     /// i.e. reorg-tail + 1 should be the new head.
     Reorg(BlockNumber),
     /// A new unique L2 Cairo 0.x class was found.
+    ///
+    /// The definition is compressed using
+    /// [compress_class_definition](pathfinder_storage::compress_class_definition).
     CairoClass {
         definition: Vec<u8>,
         hash: ClassHash,
     },
     /// A new unique L2 Cairo 1.x class was found.
+    ///
+    /// The definitions are compressed using
+    /// [compress_class_definition](pathfinder_storage::compress_class_definition).
     SierraClass {
         sierra_definition: Vec<u8>,
         sierra_hash: SierraHash,
@@ -73,6 +85,131 @@ pub enum SyncEvent {
     Pending((Arc<PendingBlock>, Arc<StateUpdate>)),
 }
 
+/// The state commitment computed after applying a block's state diff did not
+/// match the one advertised by the block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateRootMismatch {
+    pub block_number: BlockNumber,
+    pub expected: StateCommitment,
+    pub computed: StateCommitment,
+}
+
+impl std::fmt::Display for StateRootMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "State root mismatch at block {}: expected {}, computed {}",
+            self.block_number, self.expected, self.computed
+        )
+    }
+}
+
+impl std::error::Error for StateRootMismatch {}
+
+/// A block did not contain a receipt for every transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionCountMismatch {
+    pub block_number: BlockNumber,
+    pub transactions: usize,
+    pub receipts: usize,
+}
+
+impl std::fmt::Display for TransactionCountMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Transactions and receipts mismatch at block {}. There were {} transactions and {} \
+             receipts",
+            self.block_number, self.transactions, self.receipts
+        )
+    }
+}
+
+impl std::error::Error for TransactionCountMismatch {}
+
+/// A block which could not be stored and has to be downloaded again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Redownload {
+    StateRootMismatch(StateRootMismatch),
+    TransactionCountMismatch(TransactionCountMismatch),
+}
+
+impl Redownload {
+    pub fn block_number(&self) -> BlockNumber {
+        match self {
+            Redownload::StateRootMismatch(mismatch) => mismatch.block_number,
+            Redownload::TransactionCountMismatch(mismatch) => mismatch.block_number,
+        }
+    }
+}
+
+impl std::fmt::Display for Redownload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Redownload::StateRootMismatch(mismatch) => mismatch.fmt(f),
+            Redownload::TransactionCountMismatch(mismatch) => mismatch.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Redownload {}
+
+/// A block did not directly follow the latest stored block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockGap {
+    pub expected: BlockNumber,
+    pub received: BlockNumber,
+}
+
+impl std::fmt::Display for BlockGap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Gap in L2 blocks: expected block {}, received block {}",
+            self.expected, self.received
+        )
+    }
+}
+
+impl std::error::Error for BlockGap {}
+
+/// Exponential backoff applied before restarting the L1 or L2 sync tasks while
+/// they keep failing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartBackoff {
+    /// Delay after the first failure, doubled after each consecutive one.
+    pub base: Duration,
+    /// Upper bound for the delay.
+    pub cap: Duration,
+}
+
+impl RestartBackoff {
+    fn delay(&self, failures: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+        self.base.saturating_mul(factor).min(self.cap)
+    }
+}
+
+/// How often the consumer checkpoints and truncates the database WAL, which
+/// otherwise keeps growing while sync writes faster than readers let go of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalCheckpointCadence {
+    /// Checkpoint after this many blocks have been stored.
+    pub blocks: std::num::NonZeroU64,
+    /// Checkpoint once this much time has passed since the last checkpoint, if
+    /// at least one block has been stored.
+    pub interval: Duration,
+}
+
+impl Default for WalCheckpointCadence {
+    fn default() -> Self {
+        Self {
+            blocks: std::num::NonZeroU64::new(1000).unwrap(),
+            interval: Duration::from_secs(60),
+        }
+    }
+}
+
 pub struct SyncContext<G, E> {
     pub storage: Storage,
     pub ethereum: E,
@@ -82,18 +219,48 @@ pub struct SyncContext<G, E> {
     pub sequencer: G,
     pub state: Arc<SyncState>,
     pub head_poll_interval: Duration,
+    pub head_poll_backoff: l2::PollBackoff,
     pub l1_poll_interval: Duration,
     pub pending_data: WatchSender<PendingData>,
     pub block_validation_mode: l2::BlockValidationMode,
     pub websocket_txs: Option<TopicBroadcasters>,
     pub notifications: Notifications,
     pub block_cache_size: usize,
-    pub restart_delay: Duration,
+    pub restart_backoff: RestartBackoff,
     pub verify_tree_hashes: bool,
     pub gossiper: Gossiper,
     pub sequencer_public_key: PublicKey,
     pub fetch_concurrency: std::num::NonZeroUsize,
     pub fetch_casm_from_fgw: bool,
+    /// How many times a block is re-downloaded after a [StateRootMismatch] or
+    /// a [TransactionCountMismatch] before sync gives up.
+    pub redownload_retries: usize,
+    /// Weight of the latest block in the exponential moving average of block
+    /// processing time. Must be in the range `(0.0, 1.0]`.
+    pub block_time_weight: f32,
+    /// Number of events the L1 and L2 tasks may queue ahead of the consumer.
+    pub event_channel_capacity: std::num::NonZeroUsize,
+    /// Reorgs removing more blocks than this [pause](SyncState::pause) sync
+    /// instead of being applied. The reorg is applied once sync is resumed.
+    /// `None` means there is no limit.
+    pub max_reorg_depth: Option<std::num::NonZeroU64>,
+    /// How long to wait for further reorgs after receiving one. All reorgs
+    /// received in that window are applied as a single reorg to the deepest
+    /// of them.
+    pub reorg_window: Duration,
+    /// Whether transactions, receipts and events are stored. Block headers and
+    /// state are stored regardless.
+    pub store_transactions: bool,
+    pub wal_checkpoint: WalCheckpointCadence,
+    /// Source of time for block timings and task restarts.
+    pub clock: Arc<dyn Clock>,
+    /// Whether queued L1 updates are processed ahead of queued L2 events,
+    /// rather than in the order they arrived.
+    pub prefer_l1: bool,
+    /// Cancelled to shut sync down gracefully. Sync must be spawned with
+    /// [util::task::spawn_uncancelled] for this to take effect, as
+    /// [util::task::spawn] drops it on cancellation instead.
+    pub shutdown: util::task::CancellationToken,
 }
 
 impl<G, E> From<&SyncContext<G, E>> for L1SyncContext<E>
@@ -193,25 +360,43 @@ where
         sequencer,
         state,
         head_poll_interval,
+        head_poll_backoff,
         l1_poll_interval: _,
         pending_data,
         block_validation_mode: _,
         websocket_txs,
         notifications,
         block_cache_size,
-        restart_delay,
+        restart_backoff,
         verify_tree_hashes: _,
         gossiper,
         sequencer_public_key: _,
         fetch_concurrency: _,
         fetch_casm_from_fgw,
+        redownload_retries,
+        block_time_weight,
+        event_channel_capacity,
+        max_reorg_depth,
+        reorg_window,
+        store_transactions,
+        wal_checkpoint,
+        clock,
+        prefer_l1,
+        shutdown,
     } = context;
 
     let mut db_conn = storage
         .connection()
         .context("Creating database connection")?;
 
-    let (event_sender, event_receiver) = mpsc::channel(8);
+    let (event_sender, event_receiver) = event_channel(event_channel_capacity);
+    // L1 updates get a channel of their own if they are to be processed first.
+    let (l1_event_sender, l1_event_receiver) = if prefer_l1 {
+        let (sender, receiver) = event_channel(event_channel_capacity);
+        (sender, Some(receiver))
+    } else {
+        (event_sender.clone(), None)
+    };
 
     // Get the latest block from the database
     let l2_head = tokio::task::block_in_place(|| -> anyhow::Result<_> {
@@ -235,6 +420,7 @@ where
     let mut latest_handle = util::task::spawn(l2::poll_latest(
         sequencer.clone(),
         head_poll_interval,
+        head_poll_backoff,
         tx_latest,
     ));
 
@@ -246,17 +432,19 @@ where
         StateCommitment(Felt::ZERO),
     ));
 
-    let _status_sync = util::task::spawn(update_sync_status_latest(
+    let mut status_handle = util::task::spawn(update_sync_status_latest(
         Arc::clone(&state),
         starting_block_hash,
         starting_block_num,
         rx_latest.clone(),
         gossiper,
+        Arc::clone(&clock),
     ));
 
     // Start L1 producer task. Clone the event sender so that the channel remains
     // open even if the producer task fails.
-    let mut l1_handle = util::task::spawn(l1_sync(event_sender.clone(), l1_context.clone()));
+    let mut l1_handle = util::task::spawn(l1_sync(l1_event_sender.clone(), l1_context.clone()));
+    state.record_task_started(SyncTask::L1);
 
     // Fetch latest blocks from storage
     let latest_blocks = latest_n_blocks(&mut db_conn, block_cache_size)
@@ -273,16 +461,27 @@ where
         block_chain,
         rx_latest.clone(),
     ));
+    state.record_task_started(SyncTask::L2);
 
     let (current_num, current_hash, _) = l2_head.unwrap_or_default();
     let (tx_current, rx_current) = tokio::sync::watch::channel((current_num, current_hash));
+    let (redownload_tx, mut redownload_rx) = mpsc::channel(1);
+    let sync_state = Arc::clone(&state);
     let consumer_context = ConsumerContext {
         storage: storage.clone(),
+        l1_events: l1_event_receiver,
         state,
         pending_data,
         verify_tree_hashes: context.verify_tree_hashes,
         websocket_txs,
         notifications,
+        redownload: redownload_tx,
+        block_time_weight,
+        max_reorg_depth,
+        reorg_window,
+        store_transactions,
+        wal_checkpoint,
+        clock: Arc::clone(&clock),
     };
     let mut consumer_handle =
         util::task::spawn(consumer(event_receiver, consumer_context, tx_current));
@@ -297,8 +496,33 @@ where
         fetch_casm_from_fgw,
     ));
 
+    // Number of consecutive re-downloads requested for the same block.
+    let mut redownloads: Option<(BlockNumber, usize)> = None;
+
     loop {
         tokio::select! {
+            _ = shutdown.cancelled() => {
+                tracing::debug!("Shutting down sync tasks");
+
+                // An aborted task is only dropped once it yields. The consumer writes to
+                // the database without yielding, so its in-flight update is either fully
+                // committed or not at all.
+                l1_handle.abort();
+                l2_handle.abort();
+                consumer_handle.abort();
+                pending_handle.abort();
+                latest_handle.abort();
+                status_handle.abort();
+
+                _ = l1_handle.await;
+                _ = l2_handle.await;
+                _ = consumer_handle.await;
+                _ = pending_handle.await;
+                _ = latest_handle.await;
+                _ = status_handle.await;
+
+                return Ok(());
+            },
             _ = &mut pending_handle => {
                 tracing::error!("Pending tracking task ended unexpectedly");
 
@@ -326,6 +550,7 @@ where
                 _ = consumer_handle.await;
                 _ = pending_handle.await;
 
+                log_resume_point(&mut db_conn);
                 anyhow::bail!("Sync process terminated");
             },
             l1_producer_result = &mut l1_handle => {
@@ -337,10 +562,18 @@ where
                         tracing::warn!("L1 sync process terminated with: {e:?}");
                     }
                 }
-
-                let fut = l1_sync(event_sender.clone(), l1_context.clone());
+                metrics::increment_counter!("sync_task_restarts_total", "task" => "l1");
+
+                // The consumer resets the failures once the task emits an event.
+                let failures = sync_state.record_task_failed(SyncTask::L1);
+                let delay = restart_backoff.delay(failures);
+                tracing::debug!(?delay, "Restarting L1 sync process");
+                let fut = l1_sync(l1_event_sender.clone(), l1_context.clone());
+                let restarted_state = Arc::clone(&sync_state);
+                let sleep = clock.sleep(delay);
                 l1_handle = util::task::spawn(async move {
-                    tokio::time::sleep(RESET_DELAY_ON_FAILURE).await;
+                    sleep.await;
+                    restarted_state.record_task_started(SyncTask::L1);
                     fut.await
                 });
             },
@@ -354,23 +587,79 @@ where
                         tracing::warn!("L2 sync process terminated with: {e:?}");
                     }
                 }
+                metrics::increment_counter!("sync_task_restarts_total", "task" => "l2");
 
-                let l2_head = tokio::task::block_in_place(|| {
-                    let tx = db_conn.transaction()?;
-                    tx.block_header(pathfinder_storage::BlockId::Latest)
-                })
-                .context("Query L2 head from database")?
-                .map(|block| (block.number, block.hash, block.state_commitment));
+                let failures = sync_state.record_task_failed(SyncTask::L2);
+                let delay = restart_backoff.delay(failures);
+                tracing::debug!(?delay, "Restarting L2 sync process");
+                l2_handle = respawn_l2_sync(
+                    l2_sync,
+                    &mut db_conn,
+                    block_cache_size,
+                    event_sender.clone(),
+                    l2_context.clone(),
+                    rx_latest.clone(),
+                    Arc::clone(&sync_state),
+                    clock.sleep(delay),
+                )
+                .await?;
+                tracing::info!("L2 sync process restarted.");
+            },
+            Some(redownload) = redownload_rx.recv() => {
+                let block_number = redownload.block_number();
+                let attempts = match redownloads {
+                    Some((block, attempts)) if block == block_number => attempts + 1,
+                    _ => 1,
+                };
+                redownloads = Some((block_number, attempts));
+
+                if attempts > redownload_retries {
+                    tracing::error!(
+                        %block_number,
+                        %attempts,
+                        reason=%redownload,
+                        "Giving up on block after repeated re-downloads"
+                    );
 
-                let latest_blocks = latest_n_blocks(&mut db_conn, block_cache_size).await.context("Fetching latest blocks from storage")?;
-                let block_chain = BlockChain::with_capacity(1_000, latest_blocks);
-                let fut = l2_sync(event_sender.clone(), l2_context.clone(), l2_head, block_chain, rx_latest.clone());
+                    l1_handle.abort();
+                    l2_handle.abort();
+                    consumer_handle.abort();
+                    pending_handle.abort();
+                    latest_handle.abort();
 
-                l2_handle = util::task::spawn(async move {
-                    tokio::time::sleep(restart_delay).await;
-                    fut.await
-                });
-                tracing::info!("L2 sync process restarted.");
+                    _ = l1_handle.await;
+                    _ = l2_handle.await;
+                    _ = consumer_handle.await;
+                    _ = pending_handle.await;
+                    _ = latest_handle.await;
+
+                    log_resume_point(&mut db_conn);
+                    return Err(redownload).context("Sync process terminated");
+                }
+
+                // The consumer has not committed the block, so restarting the L2 producer
+                // from the database head causes the block to be downloaded again.
+                tracing::warn!(
+                    %block_number,
+                    %attempts,
+                    max_attempts=%redownload_retries,
+                    reason=%redownload,
+                    "Restarting L2 sync to re-download block"
+                );
+                l2_handle.abort();
+                _ = (&mut l2_handle).await;
+
+                l2_handle = respawn_l2_sync(
+                    l2_sync,
+                    &mut db_conn,
+                    block_cache_size,
+                    event_sender.clone(),
+                    l2_context.clone(),
+                    rx_latest.clone(),
+                    Arc::clone(&sync_state),
+                    std::future::ready(()),
+                )
+                .await?;
             },
             consumer_result = &mut consumer_handle => {
                 match consumer_result {
@@ -439,19 +728,125 @@ where
 
                 _ = pending_handle.await;
 
+                log_resume_point(&mut db_conn);
                 anyhow::bail!("Sync process terminated");
             }
         }
     }
 }
 
+/// Spawns the L2 producer from the current database head once `delay` has
+/// completed. The task is only recorded as started after the delay.
+#[allow(clippy::too_many_arguments)]
+async fn respawn_l2_sync<SequencerClient, F2, L2Sync>(
+    l2_sync: L2Sync,
+    db_conn: &mut Connection,
+    block_cache_size: usize,
+    event_sender: mpsc::Sender<SyncEvent>,
+    l2_context: L2SyncContext<SequencerClient>,
+    rx_latest: tokio::sync::watch::Receiver<(BlockNumber, BlockHash)>,
+    state: Arc<SyncState>,
+    delay: impl Future<Output = ()> + Send + 'static,
+) -> anyhow::Result<tokio::task::JoinHandle<anyhow::Result<()>>>
+where
+    F2: Future<Output = anyhow::Result<()>> + Send + 'static,
+    L2Sync: FnOnce(
+        mpsc::Sender<SyncEvent>,
+        L2SyncContext<SequencerClient>,
+        Option<(BlockNumber, BlockHash, StateCommitment)>,
+        BlockChain,
+        tokio::sync::watch::Receiver<(BlockNumber, BlockHash)>,
+    ) -> F2,
+{
+    let l2_head = tokio::task::block_in_place(|| {
+        let tx = db_conn.transaction()?;
+        tx.block_header(pathfinder_storage::BlockId::Latest)
+    })
+    .context("Query L2 head from database")?
+    .map(|block| (block.number, block.hash, block.state_commitment));
+
+    let latest_blocks = latest_n_blocks(db_conn, block_cache_size)
+        .await
+        .context("Fetching latest blocks from storage")?;
+    let block_chain = BlockChain::with_capacity(1_000, latest_blocks);
+    let fut = l2_sync(event_sender, l2_context, l2_head, block_chain, rx_latest);
+
+    Ok(util::task::spawn(async move {
+        delay.await;
+        state.record_task_started(SyncTask::L2);
+        fut.await
+    }))
+}
+
+/// A channel through which the sync tasks queue up to `capacity` events ahead
+/// of the consumer before they are blocked.
+fn event_channel(
+    capacity: std::num::NonZeroUsize,
+) -> (mpsc::Sender<SyncEvent>, Receiver<SyncEvent>) {
+    mpsc::channel(capacity.get())
+}
+
+/// Logs the latest block in the database once sync has terminated with an
+/// error. Every block is committed as it is applied, so the next start resumes
+/// from this block.
+fn log_resume_point(connection: &mut Connection) {
+    let head = tokio::task::block_in_place(|| {
+        let tx = connection.transaction()?;
+        tx.block_id(pathfinder_storage::BlockId::Latest)
+    });
+
+    match head {
+        Ok(Some((number, hash))) => {
+            tracing::info!(%number, %hash, "Sync stopped, it will resume after this block")
+        }
+        Ok(None) => tracing::info!("Sync stopped before storing any blocks"),
+        Err(error) => tracing::warn!(?error, "Failed to query the block sync will resume after"),
+    }
+}
+
 struct ConsumerContext {
     pub storage: Storage,
+    /// L1 updates, received ahead of all other events if set. Otherwise they
+    /// arrive interleaved with the other events.
+    pub l1_events: Option<Receiver<SyncEvent>>,
     pub state: Arc<SyncState>,
     pub pending_data: WatchSender<PendingData>,
     pub verify_tree_hashes: bool,
     pub websocket_txs: Option<TopicBroadcasters>,
     pub notifications: Notifications,
+    /// Blocks failing with a [StateRootMismatch] or a
+    /// [TransactionCountMismatch] are reported here instead of terminating the
+    /// consumer, so that they can be downloaded again.
+    pub redownload: mpsc::Sender<Redownload>,
+    pub block_time_weight: f32,
+    pub max_reorg_depth: Option<std::num::NonZeroU64>,
+    pub reorg_window: Duration,
+    pub store_transactions: bool,
+    pub wal_checkpoint: WalCheckpointCadence,
+    pub clock: Arc<dyn Clock>,
+}
+
+#[cfg(test)]
+impl ConsumerContext {
+    /// A context with defaults, for tests to override the fields they exercise.
+    fn for_tests(storage: Storage) -> Self {
+        Self {
+            storage,
+            l1_events: None,
+            state: Arc::new(SyncState::default()),
+            pending_data: tokio::sync::watch::channel(Default::default()).0,
+            verify_tree_hashes: false,
+            websocket_txs: None,
+            notifications: Default::default(),
+            redownload: mpsc::channel(1).0,
+            block_time_weight: 0.05,
+            max_reorg_depth: None,
+            reorg_window: Duration::ZERO,
+            store_transactions: true,
+            wal_checkpoint: Default::default(),
+            clock: Arc::new(SystemClock),
+        }
+    }
 }
 
 async fn consumer(
@@ -461,42 +856,124 @@ async fn consumer(
 ) -> anyhow::Result<()> {
     let ConsumerContext {
         storage,
+        mut l1_events,
         state,
         pending_data,
         verify_tree_hashes,
         mut websocket_txs,
         mut notifications,
+        redownload,
+        block_time_weight,
+        max_reorg_depth,
+        reorg_window,
+        store_transactions,
+        wal_checkpoint,
+        clock,
     } = context;
 
-    let mut last_block_start = std::time::Instant::now();
+    anyhow::ensure!(
+        block_time_weight > 0.0 && block_time_weight <= 1.0,
+        "Block time weight must be in the range (0.0, 1.0], got {block_time_weight}"
+    );
+
+    let mut last_block_start = clock.now();
     let mut block_time_avg = std::time::Duration::ZERO;
-    const BLOCK_TIME_WEIGHT: f32 = 0.05;
 
     let mut db_conn = storage
         .connection()
         .context("Creating database connection")?;
 
-    let (mut latest_timestamp, mut next_number) = tokio::task::block_in_place(|| {
-        let tx = db_conn
-            .transaction()
-            .context("Creating database transaction")?;
-        let latest = tx
-            .block_header(pathfinder_storage::BlockId::Latest)
-            .context("Fetching latest block header")?
-            .map(|b| (b.timestamp, b.number + 1))
-            .unwrap_or_default();
+    let (mut latest_timestamp, mut next_number, mut l1_l2_head) =
+        tokio::task::block_in_place(|| {
+            let tx = db_conn
+                .transaction()
+                .context("Creating database transaction")?;
+            let (latest_timestamp, next_number) = tx
+                .block_header(pathfinder_storage::BlockId::Latest)
+                .context("Fetching latest block header")?
+                .map(|b| (b.timestamp, b.number + 1))
+                .unwrap_or_default();
+            let l1_l2_head = tx.l1_l2_pointer().context("Fetching L1-L2 head")?;
 
-        anyhow::Ok(latest)
-    })
-    .context("Fetching latest block time")?;
+            anyhow::Ok((latest_timestamp, next_number, l1_l2_head))
+        })
+        .context("Fetching latest block time")?;
+
+    let mut blocks_since_checkpoint = 0u64;
+    let mut last_checkpoint = clock.now();
+
+    // Set after a block failed to be stored until it has been re-downloaded.
+    let mut awaiting_redownload = false;
+
+    // An event read ahead while batching L1 updates, which must be processed
+    // next.
+    let mut next_event = None;
+
+    loop {
+        let event = match next_event.take() {
+            Some(event) => event,
+            None => {
+                let event = match &mut l1_events {
+                    Some(l1_events) => tokio::select! {
+                        biased;
+                        Some(event) = l1_events.recv() => Some(event),
+                        event = events.recv() => event,
+                    },
+                    None => events.recv().await,
+                };
+                match event {
+                    Some(event) => event,
+                    None => break,
+                }
+            }
+        };
+
+        // Checked after receiving so that an event that arrived while paused is
+        // held back as well.
+        if state.is_paused() {
+            tracing::info!("Sync paused");
+            state.wait_while_paused().await;
+            tracing::info!("Sync resumed");
+        }
 
-    while let Some(event) = events.recv().await {
         use SyncEvent::*;
         match event {
             L1Update(update) => {
-                tracing::trace!("Updating L1 sync to block {}", update.block_number);
-                l1_update(&mut db_conn, &update).await?;
-                tracing::info!("L1 sync updated to block {}", update.block_number);
+                state.record_task_event(SyncTask::L1);
+                // L1 updates are emitted in bursts once their L1 block is finalized, so
+                // write all those already queued in a single database transaction.
+                let mut updates = vec![update];
+                let mut queued = || match &mut l1_events {
+                    Some(l1_events) => l1_events.try_recv(),
+                    None => events.try_recv(),
+                };
+                while let Ok(event) = queued() {
+                    match event {
+                        L1Update(update) => updates.push(update),
+                        other => {
+                            next_event = Some(other);
+                            break;
+                        }
+                    }
+                }
+
+                let latest = updates
+                    .iter()
+                    .map(|update| update.block_number)
+                    .max()
+                    .expect("Updates are not empty");
+                tracing::trace!("Updating L1 sync to block {}", latest);
+                let new_l1_l2_head = l1_update(&mut db_conn, &mut updates).await?;
+                tracing::info!("L1 sync updated to block {}", latest);
+
+                if new_l1_l2_head != l1_l2_head {
+                    l1_l2_head = new_l1_l2_head;
+                    if let Syncing::Status(status) = &mut *state.status.write().await {
+                        status.l1_l2_confirmed_block = l1_l2_head;
+                        // Ignore errors in case nobody is listening.
+                        _ = state.updates.send(Syncing::Status(*status));
+                    }
+                }
             }
             Block(
                 (block, (tx_comm, ev_comm, rc_comm)),
@@ -505,114 +982,300 @@ async fn consumer(
                 state_diff_commitment,
                 timings,
             ) => {
+                state.record_task_event(SyncTask::L2);
                 tracing::trace!("Updating L2 state to block {}", block.block_number);
                 if block.block_number < next_number {
                     tracing::debug!("Ignoring duplicate block {}", block.block_number);
                     continue;
                 }
+                if awaiting_redownload && block.block_number > next_number {
+                    // Blocks the previous L2 producer downloaded past the mismatched one.
+                    tracing::debug!(
+                        "Ignoring block {} while waiting for block {} to be re-downloaded",
+                        block.block_number,
+                        next_number
+                    );
+                    continue;
+                }
+
+                let mut blocks = vec![L2Block {
+                    block,
+                    transaction_commitment: tx_comm,
+                    event_commitment: ev_comm,
+                    receipt_commitment: rc_comm,
+                    state_update,
+                    signature,
+                    state_diff_commitment,
+                    timings,
+                }];
+                // Historical ranges contain long runs of blocks with empty state diffs,
+                // which leave the tries untouched. Store those already queued in a single
+                // database transaction.
+                while let Ok(event) = events.try_recv() {
+                    match event {
+                        Block(
+                            (block, (tx_comm, ev_comm, rc_comm)),
+                            state_update,
+                            signature,
+                            state_diff_commitment,
+                            timings,
+                        ) => {
+                            let next = L2Block {
+                                block,
+                                transaction_commitment: tx_comm,
+                                event_commitment: ev_comm,
+                                receipt_commitment: rc_comm,
+                                state_update,
+                                signature,
+                                state_diff_commitment,
+                                timings,
+                            };
+                            let parent = blocks.last().expect("Blocks are not empty");
+                            if next.extends_without_state_change(parent) {
+                                blocks.push(next);
+                            } else {
+                                next_event = Some(Block(
+                                    (next.block, (tx_comm, ev_comm, rc_comm)),
+                                    next.state_update,
+                                    next.signature,
+                                    next.state_diff_commitment,
+                                    next.timings,
+                                ));
+                                break;
+                            }
+                        }
+                        other => {
+                            next_event = Some(other);
+                            break;
+                        }
+                    }
+                }
 
-                let block_number = block.block_number;
-                let block_hash = block.block_hash;
-                let block_timestamp = block.timestamp;
-                let storage_updates: usize = state_update
-                    .contract_updates
+                let block_number = blocks[0].block.block_number;
+                let applied = blocks
+                    .iter()
+                    .map(|b| {
+                        let storage_updates: usize = b
+                            .state_update
+                            .contract_updates
+                            .iter()
+                            .map(|x| x.1.storage.len())
+                            .sum();
+                        (
+                            b.block.block_number,
+                            b.block.block_hash,
+                            b.block.timestamp,
+                            b.timings,
+                            storage_updates,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let spans = applied
                     .iter()
-                    .map(|x| x.1.storage.len())
-                    .sum();
-                let update_t = std::time::Instant::now();
-                l2_update(
+                    .map(|(block_number, ..)| l2_block_span(*block_number))
+                    .collect::<Vec<_>>();
+                let update_t = clock.now();
+                let result = l2_update(
                     &mut db_conn,
-                    *block,
-                    tx_comm,
-                    rc_comm,
-                    ev_comm,
-                    *state_update,
-                    *signature,
-                    *state_diff_commitment,
+                    blocks,
                     verify_tree_hashes,
+                    store_transactions,
                     storage.clone(),
                     &mut websocket_txs,
                     &mut notifications,
+                    &*clock,
+                    &spans[0],
                 )
-                .await
-                .with_context(|| format!("Update L2 state to {block_number}"))?;
-                let block_time = last_block_start.elapsed();
-                let update_t = update_t.elapsed();
-                last_block_start = std::time::Instant::now();
-
-                block_time_avg = block_time_avg.mul_f32(1.0 - BLOCK_TIME_WEIGHT)
-                    + block_time.mul_f32(BLOCK_TIME_WEIGHT);
-
-                // Update sync status
-                match &mut *state.status.write().await {
-                    Syncing::False => {}
-                    Syncing::Status(status) => {
-                        status.current = NumberedBlock::from((block_hash, block_number));
-
-                        metrics::gauge!("current_block", block_number.get() as f64);
-
-                        if status.highest.number <= block_number {
-                            status.highest = status.current;
-                            metrics::gauge!("highest_block", block_number.get() as f64);
+                .await;
+                let new_l1_l2_head = match result {
+                    Ok((_, head)) => head,
+                    Err(error) => {
+                        let request = if let Some(mismatch) =
+                            error.downcast_ref::<StateRootMismatch>().copied()
+                        {
+                            tracing::warn!(
+                                %block_number,
+                                expected=%mismatch.expected,
+                                computed=%mismatch.computed,
+                                "State root mismatch"
+                            );
+                            metrics::increment_counter!("sync_state_root_mismatches_total");
+                            Redownload::StateRootMismatch(mismatch)
+                        } else if let Some(mismatch) =
+                            error.downcast_ref::<TransactionCountMismatch>().copied()
+                        {
+                            tracing::warn!(
+                                block_number=%mismatch.block_number,
+                                transactions=%mismatch.transactions,
+                                receipts=%mismatch.receipts,
+                                "Transactions and receipts mismatch"
+                            );
+                            Redownload::TransactionCountMismatch(mismatch)
+                        } else {
+                            return Err(error.context(format!("Update L2 state to {block_number}")));
+                        };
+
+                        redownload
+                            .send(request)
+                            .await
+                            .map_err(|_| request)
+                            .with_context(|| format!("Update L2 state to {block_number}"))?;
+                        awaiting_redownload = true;
+                        continue;
+                    }
+                };
+                awaiting_redownload = false;
+                l1_l2_head = new_l1_l2_head;
+                // Spread the time spent storing a batch evenly over its blocks.
+                let update_t = clock.elapsed(update_t) / applied.len() as u32;
+                let applied_count = applied.len() as u64;
+
+                for ((block_number, block_hash, block_timestamp, timings, storage_updates), span) in
+                    applied.into_iter().zip(spans)
+                {
+                    let block_time = clock.elapsed(last_block_start);
+                    last_block_start = clock.now();
+
+                    block_time_avg = block_time_avg.mul_f32(1.0 - block_time_weight)
+                        + block_time.mul_f32(block_time_weight);
+
+                    // Update sync status
+                    match &mut *state.status.write().await {
+                        Syncing::False => {}
+                        Syncing::Status(status) => {
+                            status.current = NumberedBlock::from((block_hash, block_number));
+                            status.current_block_time_avg = Some(block_time_avg);
+                            status.l1_l2_confirmed_block = l1_l2_head;
+
+                            metrics::gauge!("current_block", block_number.get() as f64);
+
+                            if status.highest.number <= block_number {
+                                status.highest = status.current;
+                                metrics::gauge!("highest_block", block_number.get() as f64);
+                            }
+
+                            // Ignore errors in case nobody is listening.
+                            _ = state.updates.send(Syncing::Status(*status));
                         }
                     }
-                }
 
-                _ = current.send((block_number, block_hash));
+                    _ = current.send((block_number, block_hash));
 
-                let now_timestamp = time::OffsetDateTime::now_utc().unix_timestamp() as u64;
-                let latency = now_timestamp.saturating_sub(block_timestamp.get());
+                    let now_timestamp = time::OffsetDateTime::now_utc().unix_timestamp() as u64;
+                    let latency = now_timestamp.saturating_sub(block_timestamp.get());
 
-                let download_time = (timings.block_download
-                    + timings.class_declaration
-                    + timings.signature_download)
-                    .as_secs_f64();
+                    let download_time = (timings.block_download
+                        + timings.class_declaration
+                        + timings.signature_download)
+                        .as_secs_f64();
 
-                metrics::gauge!("block_download", download_time);
-                metrics::gauge!("block_processing", update_t.as_secs_f64());
-                metrics::histogram!("block_processing_duration_seconds", update_t);
-                metrics::gauge!("block_latency", latency as f64);
-                metrics::gauge!(
-                    "block_time",
-                    (block_timestamp.get() - latest_timestamp.get()) as f64
-                );
-                latest_timestamp = block_timestamp;
-                next_number += 1;
-
-                // Give a simple log under INFO level, and a more verbose log
-                // with timing information under DEBUG+ level.
-                //
-                // This should be removed if we have a configurable log level.
-                // See the docs for LevelFilter for more information.
-                match tracing::level_filters::LevelFilter::current().into_level() {
-                    None => {}
-                    Some(level) if level <= tracing::Level::INFO => {
-                        tracing::info!("Updated Starknet state with block {}", block_number)
-                    }
-                    Some(_) => {
-                        tracing::debug!(
-                            "Updated Starknet state with block {} after {:2}s ({:2}s avg). \
-                             contracts ({:2}s), {} storage updates ({:2}s). Block downloaded in \
-                             {:2}s, signature in {:2}s",
-                            block_number,
-                            block_time.as_secs_f32(),
-                            block_time_avg.as_secs_f32(),
-                            timings.class_declaration.as_secs_f32(),
-                            storage_updates,
-                            update_t.as_secs_f32(),
-                            timings.block_download.as_secs_f32(),
-                            timings.signature_download.as_secs_f32(),
+                    metrics::gauge!("block_download", download_time);
+                    metrics::gauge!("block_processing", update_t.as_secs_f64());
+                    metrics::histogram!("block_processing_duration_seconds", update_t);
+                    metrics::increment_counter!("sync_blocks_applied_total");
+                    metrics::gauge!("block_latency", latency as f64);
+                    metrics::gauge!(
+                        "block_time",
+                        (block_timestamp.get() - latest_timestamp.get()) as f64
+                    );
+                    latest_timestamp = block_timestamp;
+                    next_number += 1;
+
+                    // Timings are only recorded on the span at DEBUG level, keeping the INFO
+                    // log to the block number.
+                    if tracing::enabled!(tracing::Level::DEBUG) {
+                        span.record("block_time", tracing::field::debug(block_time));
+                        span.record("block_time_avg", tracing::field::debug(block_time_avg));
+                        span.record(
+                            "class_declaration",
+                            tracing::field::debug(timings.class_declaration),
+                        );
+                        span.record("storage_updates", storage_updates);
+                        span.record("update", tracing::field::debug(update_t));
+                        span.record(
+                            "block_download",
+                            tracing::field::debug(timings.block_download),
+                        );
+                        span.record(
+                            "signature_download",
+                            tracing::field::debug(timings.signature_download),
                         );
                     }
+                    span.in_scope(|| tracing::info!("Updated Starknet state"));
+                }
+
+                blocks_since_checkpoint += applied_count;
+                if blocks_since_checkpoint >= wal_checkpoint.blocks.get()
+                    || clock.elapsed(last_checkpoint) >= wal_checkpoint.interval
+                {
+                    match tokio::task::block_in_place(|| storage.wal_checkpoint()) {
+                        Ok(checkpoint) => tracing::debug!(
+                            blocks=%blocks_since_checkpoint,
+                            complete=%checkpoint.is_complete(),
+                            busy=%checkpoint.busy,
+                            log_frames=%checkpoint.log_frames,
+                            checkpointed_frames=%checkpoint.checkpointed_frames,
+                            reclaimed_bytes=%checkpoint.reclaimed_bytes,
+                            "Checkpointed database WAL"
+                        ),
+                        // Sync can carry on with a growing WAL.
+                        Err(error) => tracing::warn!(%error, "Failed to checkpoint database WAL"),
+                    }
+                    blocks_since_checkpoint = 0;
+                    last_checkpoint = clock.now();
                 }
             }
-            Reorg(reorg_tail) => {
+            Reorg(mut reorg_tail) => {
+                state.record_task_event(SyncTask::L2);
+                // The L2 task can report several reorgs in quick succession while it
+                // walks back to the new canonical chain. Applying the deepest of those
+                // received within the window removes the blocks of all of them.
+                let window_end = tokio::time::Instant::now() + reorg_window;
+                while let Ok(Some(event)) = tokio::time::timeout_at(window_end, events.recv()).await
+                {
+                    match event {
+                        Reorg(tail) => reorg_tail = reorg_tail.min(tail),
+                        other => {
+                            next_event = Some(other);
+                            break;
+                        }
+                    }
+                }
                 tracing::trace!("Reorg L2 state to block {}", reorg_tail);
-                l2_reorg(&mut db_conn, reorg_tail, &mut notifications)
+                // Blocks from the reorg tail up to the current head are removed.
+                let depth = next_number.get().saturating_sub(reorg_tail.get());
+                if let Some(max_depth) = max_reorg_depth {
+                    if depth > max_depth.get() {
+                        // The database is left untouched so that an operator can look into
+                        // it while the node keeps serving RPC.
+                        tracing::error!(
+                            %reorg_tail,
+                            %depth,
+                            %max_depth,
+                            "Pausing sync instead of applying a reorg deeper than the configured \
+                             maximum"
+                        );
+                        state.pause();
+                        state.wait_while_paused().await;
+                        tracing::info!(%reorg_tail, %depth, "Sync resumed, applying deep reorg");
+                    }
+                }
+
+                let reorged = l2_reorg(&mut db_conn, reorg_tail, &mut notifications)
                     .await
                     .with_context(|| format!("Reorg L2 state to {reorg_tail:?}"))?;
+                if !reorged {
+                    continue;
+                }
+
+                metrics::increment_counter!("sync_reorgs_total");
+                metrics::histogram!("sync_reorg_depth", depth as f64);
 
                 next_number = reorg_tail;
+                // Purging the blocks also rolls back the L1-L2 head.
+                if l1_l2_head.is_some_and(|head| head >= reorg_tail) {
+                    l1_l2_head = reorg_tail.parent();
+                }
 
                 let new_head = match reorg_tail {
                     BlockNumber::GENESIS => None,
@@ -631,13 +1294,14 @@ async fn consumer(
                     let tx = db_conn
                         .transaction_with_behavior(TransactionBehavior::Immediate)
                         .context("Creating database transaction")?;
-                    tx.insert_cairo_class(hash, &definition)
+                    tx.insert_cairo_class_compressed(hash, &definition)
                         .context("Inserting new cairo class")?;
                     tx.commit().context("Committing database transaction")
                 })
                 .with_context(|| format!("Insert Cairo contract definition with hash: {hash}"))?;
 
                 tracing::debug!(%hash, "Inserted new Cairo class");
+                metrics::increment_counter!("sync_classes_inserted_total", "kind" => "cairo");
             }
             SierraClass {
                 sierra_definition,
@@ -650,7 +1314,7 @@ async fn consumer(
                     let tx = db_conn
                         .transaction_with_behavior(TransactionBehavior::Immediate)
                         .context("Creating database transaction")?;
-                    tx.insert_sierra_class(
+                    tx.insert_sierra_class_compressed(
                         &sierra_hash,
                         &sierra_definition,
                         &casm_hash,
@@ -664,6 +1328,7 @@ async fn consumer(
                 })?;
 
                 tracing::debug!(sierra=%sierra_hash, casm=%casm_hash, "Inserted new Sierra class");
+                metrics::increment_counter!("sync_classes_inserted_total", "kind" => "sierra");
             }
             Pending(pending) => {
                 tracing::trace!("Updating pending data");
@@ -696,6 +1361,22 @@ async fn consumer(
     Ok(())
 }
 
+/// The span of a block being applied by the consumer. Its timings are recorded
+/// once the block has been stored.
+fn l2_block_span(number: BlockNumber) -> tracing::Span {
+    tracing::info_span!(
+        "l2_block",
+        %number,
+        block_time = tracing::field::Empty,
+        block_time_avg = tracing::field::Empty,
+        class_declaration = tracing::field::Empty,
+        storage_updates = tracing::field::Empty,
+        update = tracing::field::Empty,
+        block_download = tracing::field::Empty,
+        signature_download = tracing::field::Empty,
+    )
+}
+
 async fn latest_n_blocks(
     connection: &mut Connection,
     n: usize,
@@ -741,10 +1422,11 @@ async fn update_sync_status_latest(
     starting_block_num: BlockNumber,
     mut latest: tokio::sync::watch::Receiver<(BlockNumber, BlockHash)>,
     gossiper: Gossiper,
+    clock: Arc<dyn Clock>,
 ) {
     let starting = NumberedBlock::from((starting_block_hash, starting_block_num));
 
-    let mut last_propagated = Instant::now();
+    let mut last_propagated = clock.now();
     let mut latest_hash = BlockHash::default();
 
     loop {
@@ -765,12 +1447,16 @@ async fn update_sync_status_latest(
                     starting,
                     current: starting,
                     highest: latest,
+                    current_block_time_avg: None,
+                    l1_l2_confirmed_block: None,
                 });
 
                 metrics::gauge!("current_block", starting.number.get() as f64);
                 metrics::gauge!("highest_block", latest.number.get() as f64);
 
-                propagate_head(&gossiper, &mut last_propagated, latest).await;
+                _ = state.updates.send(sync_status.clone());
+
+                propagate_head(&gossiper, &*clock, &mut last_propagated, latest).await;
 
                 tracing::debug!(
                     status=%sync_status,
@@ -783,7 +1469,9 @@ async fn update_sync_status_latest(
 
                     metrics::gauge!("highest_block", latest.number.get() as f64);
 
-                    propagate_head(&gossiper, &mut last_propagated, latest).await;
+                    _ = state.updates.send(Syncing::Status(*status));
+
+                    propagate_head(&gossiper, &*clock, &mut last_propagated, latest).await;
 
                     tracing::debug!(
                         %status,
@@ -794,311 +1482,398 @@ async fn update_sync_status_latest(
         }
 
         // duplicate_cache_time for gossipsub defaults to 1 minute
-        if last_propagated.elapsed() > Duration::from_secs(120) {
-            propagate_head(&gossiper, &mut last_propagated, latest).await;
+        if clock.elapsed(last_propagated) > Duration::from_secs(120) {
+            propagate_head(&gossiper, &*clock, &mut last_propagated, latest).await;
         }
     }
 
     tracing::info!("Channel closed, exiting latest poll task");
 }
 
-async fn propagate_head(gossiper: &Gossiper, last_propagated: &mut Instant, head: NumberedBlock) {
+async fn propagate_head(
+    gossiper: &Gossiper,
+    clock: &dyn Clock,
+    last_propagated: &mut Instant,
+    head: NumberedBlock,
+) {
     _ = gossiper.propagate_head(head.number, head.hash).await;
-    *last_propagated = Instant::now();
+    *last_propagated = clock.now();
 }
 
+/// Returns the L1-L2 head after the update.
+///
+/// `updates` is sorted by block number first, since the L1-L2 pointer is
+/// advanced in iteration order.
 async fn l1_update(
     connection: &mut Connection,
-    update: &EthereumStateUpdate,
-) -> anyhow::Result<()> {
+    updates: &mut [EthereumStateUpdate],
+) -> anyhow::Result<Option<BlockNumber>> {
+    updates.sort_by_key(|update| update.block_number);
+
     tokio::task::block_in_place(move || {
         let transaction = connection
             .transaction_with_behavior(TransactionBehavior::Immediate)
             .context("Create database transaction")?;
 
         transaction
-            .upsert_l1_state(update)
-            .context("Insert update")?;
+            .upsert_l1_states(updates)
+            .context("Insert updates")?;
 
-        let l2_hash = transaction
-            .block_hash(update.block_number.into())
-            .context("Fetching block hash")?;
+        for update in updates.iter() {
+            let l2_hash = transaction
+                .block_hash(update.block_number.into())
+                .context("Fetching block hash")?;
 
-        if let Some(l2_hash) = l2_hash {
-            if l2_hash == update.block_hash {
-                transaction
-                    .update_l1_l2_pointer(Some(update.block_number))
-                    .context("Updating L1-L2 pointer")?;
-                tracing::info!(block=?update.block_number, "Updated L1/L2 match");
-            } else {
-                tracing::warn!(block_number=?update.block_number, L1=?update.block_hash, L2=?l2_hash, "L1/L2 block hash mismatch");
-                if let Some(matching_block_number) = transaction.l1_l2_pointer()? {
-                    tracing::warn!(block_number=?matching_block_number, "Most recent L1/L2 block hash match")
+            if let Some(l2_hash) = l2_hash {
+                if l2_hash == update.block_hash {
+                    transaction
+                        .update_l1_l2_pointer(Some(update.block_number))
+                        .context("Updating L1-L2 pointer")?;
+                    tracing::info!(block=?update.block_number, "Updated L1/L2 match");
+                } else {
+                    tracing::warn!(block_number=?update.block_number, L1=?update.block_hash, L2=?l2_hash, "L1/L2 block hash mismatch");
+                    if let Some(matching_block_number) = transaction.l1_l2_pointer()? {
+                        tracing::warn!(block_number=?matching_block_number, "Most recent L1/L2 block hash match")
+                    }
                 }
             }
         }
 
+        let l1_l2_head = transaction.l1_l2_pointer().context("Query L1-L2 head")?;
+
         transaction
             .commit()
             .context("Commit database transaction")?;
 
-        Ok(())
+        Ok(l1_l2_head)
     })
 }
 
-/// Returns the new [StateCommitment] after the update.
+/// A downloaded L2 block along with everything needed to store it.
+struct L2Block {
+    block: Box<Block>,
+    transaction_commitment: TransactionCommitment,
+    event_commitment: EventCommitment,
+    receipt_commitment: ReceiptCommitment,
+    state_update: Box<StateUpdate>,
+    signature: Box<BlockCommitmentSignature>,
+    state_diff_commitment: Box<StateDiffCommitment>,
+    timings: l2::Timings,
+}
+
+impl L2Block {
+    /// Whether `self` can be stored in the same database transaction as
+    /// `parent`, without touching the tries again.
+    fn extends_without_state_change(&self, parent: &L2Block) -> bool {
+        self.state_update.is_empty()
+            && self.block.block_number == parent.block.block_number + 1
+            && self.block.parent_block_hash == parent.block.block_hash
+            && self.block.state_commitment == parent.block.state_commitment
+    }
+}
+
+/// Stores `blocks` in a single database transaction.
+///
+/// Only the state diff of the first block is applied to the tries. The blocks
+/// following it must have empty state diffs, see
+/// [L2Block::extends_without_state_change].
+///
+/// The state update is logged within `span`, the [l2_block_span] of the first
+/// block.
+///
+/// Returns the state commitment of the stored blocks, which is shared by all
+/// of them, and the L1-L2 head after the update.
 #[allow(clippy::too_many_arguments)]
 async fn l2_update(
     connection: &mut Connection,
-    block: Block,
-    transaction_commitment: TransactionCommitment,
-    receipt_commitment: ReceiptCommitment,
-    event_commitment: EventCommitment,
-    state_update: StateUpdate,
-    signature: BlockCommitmentSignature,
-    state_diff_commitment: StateDiffCommitment,
+    blocks: Vec<L2Block>,
     verify_tree_hashes: bool,
+    store_transactions: bool,
     // we need this so that we can create extra read-only transactions for
     // parallel contract state updates
     storage: Storage,
     websocket_txs: &mut Option<TopicBroadcasters>,
     notifications: &mut Notifications,
-) -> anyhow::Result<()> {
+    clock: &dyn Clock,
+    span: &tracing::Span,
+) -> anyhow::Result<(StateCommitment, Option<BlockNumber>)> {
     tokio::task::block_in_place(move || {
+        let mut apply_start = clock.now();
         let transaction = connection
             .transaction_with_behavior(TransactionBehavior::Immediate)
             .context("Create database transaction")?;
-        let (storage_commitment, class_commitment) = update_starknet_state(
-            &transaction,
-            (&state_update).into(),
-            verify_tree_hashes,
-            block.block_number,
-            storage,
-        )
-        .context("Updating Starknet state")?;
+
+        let first = blocks.first().context("No blocks to update")?;
+
+        if store_transactions {
+            // A malformed response rather than an invalid block, so it is checked before
+            // touching the database.
+            for L2Block { block, .. } in &blocks {
+                if block.transactions.len() != block.transaction_receipts.len() {
+                    return Err(TransactionCountMismatch {
+                        block_number: block.block_number,
+                        transactions: block.transactions.len(),
+                        receipts: block.transaction_receipts.len(),
+                    }
+                    .into());
+                }
+            }
+        }
+
+        // Applying the state diff on top of the wrong parent state could still
+        // produce the advertised root, e.g. for empty diffs.
+        let expected = transaction
+            .block_number(pathfinder_storage::BlockId::Latest)
+            .context("Querying latest block number")?
+            .map_or(BlockNumber::GENESIS, |head| head + 1);
+        if first.block.block_number != expected {
+            return Err(BlockGap {
+                expected,
+                received: first.block.block_number,
+            }
+            .into());
+        }
+
+        // Blocks after the first don't change the state, so only the first block's
+        // span covers the state update.
+        let state_span = span.enter();
+
+        // Contract state hashes are only collected if they can be logged on a mismatch.
+        let (storage_commitment, class_commitment, contract_hashes) =
+            if tracing::enabled!(tracing::Level::DEBUG) {
+                update_starknet_state_with_contract_hashes(
+                    &transaction,
+                    (&*first.state_update).into(),
+                    verify_tree_hashes,
+                    first.block.block_number,
+                    storage,
+                )
+            } else {
+                update_starknet_state(
+                    &transaction,
+                    (&*first.state_update).into(),
+                    verify_tree_hashes,
+                    first.block.block_number,
+                    storage,
+                )
+                .map(|(storage, class)| (storage, class, Default::default()))
+            }
+            .context("Updating Starknet state")?;
         let state_commitment = StateCommitment::calculate(storage_commitment, class_commitment);
 
-        // Ensure that roots match.. what should we do if it doesn't? For now the whole
-        // sync process ends..
-        anyhow::ensure!(
-            state_commitment == block.state_commitment,
-            "State root mismatch"
-        );
+        // Dropping the transaction discards the partially applied state, leaving the
+        // consumer free to retry the block.
+        if state_commitment != first.block.state_commitment {
+            for (contract, state_hash) in contract_hashes {
+                tracing::debug!(
+                    block_number=%first.block.block_number,
+                    %contract,
+                    %state_hash,
+                    "Computed contract state hash"
+                );
+            }
+            return Err(StateRootMismatch {
+                block_number: first.block.block_number,
+                expected: first.block.state_commitment,
+                computed: state_commitment,
+            }
+            .into());
+        }
+        drop(state_span);
+
+        for (parent, child) in blocks.iter().zip(blocks.iter().skip(1)) {
+            anyhow::ensure!(
+                child.extends_without_state_change(parent),
+                "Block {} cannot be stored along with block {}",
+                child.block.block_number,
+                parent.block.block_number
+            );
+        }
 
-        let transaction_count = block.transactions.len();
-        let event_count = block
-            .transaction_receipts
-            .iter()
-            .map(|(_, events)| events.len())
-            .sum();
-
-        // Update L2 database. These types shouldn't be options at this level,
-        // but for now the unwraps are "safe" in that these should only ever be
-        // None for pending queries to the sequencer, but we aren't using those here.
-        let header = BlockHeader {
-            hash: block.block_hash,
-            parent_hash: block.parent_block_hash,
-            number: block.block_number,
-            timestamp: block.timestamp,
-            // Default value for cairo <0.8.2 is 0
-            eth_l1_gas_price: block.l1_gas_price.price_in_wei,
-            // Default value for Starknet <0.13.0 is zero
-            strk_l1_gas_price: block.l1_gas_price.price_in_fri,
-            // Default value for Starknet <0.13.1 is zero
-            eth_l1_data_gas_price: block.l1_data_gas_price.price_in_wei,
-            // Default value for Starknet <0.13.1 is zero
-            strk_l1_data_gas_price: block.l1_data_gas_price.price_in_fri,
-            eth_l2_gas_price: block.l2_gas_price.unwrap_or_default().price_in_wei,
-            strk_l2_gas_price: block.l2_gas_price.unwrap_or_default().price_in_fri,
-            sequencer_address: block
-                .sequencer_address
-                .unwrap_or(SequencerAddress(Felt::ZERO)),
-            starknet_version: block.starknet_version,
-            event_commitment,
-            state_commitment,
+        let mut l1_l2_head = None;
+        let mut headers = Vec::with_capacity(blocks.len());
+        for L2Block {
+            block,
             transaction_commitment,
-            transaction_count,
-            event_count,
-            l1_da_mode: block.l1_da_mode.into(),
+            event_commitment,
             receipt_commitment,
+            state_update,
+            signature,
             state_diff_commitment,
-            state_diff_length: state_update.state_diff_length(),
-        };
-
-        transaction
-            .insert_block_header(&header)
-            .context("Inserting block header into database")?;
-
-        // Insert the transactions.
-        anyhow::ensure!(
-            block.transactions.len() == block.transaction_receipts.len(),
-            "Transactions and receipts mismatch. There were {} transactions and {} receipts.",
-            block.transactions.len(),
-            block.transaction_receipts.len()
-        );
-        let (transactions_data, events_data): (Vec<_>, Vec<_>) = block
-            .transactions
-            .iter()
-            .cloned()
-            .zip(block.transaction_receipts.iter().cloned())
-            .map(|(tx, (receipt, events))| ((tx, receipt), events))
-            .unzip();
+            timings,
+        } in &blocks
+        {
+            let transaction_count = block.transactions.len();
+            let event_count = block
+                .transaction_receipts
+                .iter()
+                .map(|(_, events)| events.len())
+                .sum();
+
+            // Update L2 database. These types shouldn't be options at this level,
+            // but for now the unwraps are "safe" in that these should only ever be
+            // None for pending queries to the sequencer, but we aren't using those here.
+            let header = BlockHeader {
+                hash: block.block_hash,
+                parent_hash: block.parent_block_hash,
+                number: block.block_number,
+                timestamp: block.timestamp,
+                // Default value for cairo <0.8.2 is 0
+                eth_l1_gas_price: block.l1_gas_price.price_in_wei,
+                // Default value for Starknet <0.13.0 is zero
+                strk_l1_gas_price: block.l1_gas_price.price_in_fri,
+                // Default value for Starknet <0.13.1 is zero
+                eth_l1_data_gas_price: block.l1_data_gas_price.price_in_wei,
+                // Default value for Starknet <0.13.1 is zero
+                strk_l1_data_gas_price: block.l1_data_gas_price.price_in_fri,
+                eth_l2_gas_price: block.l2_gas_price.unwrap_or_default().price_in_wei,
+                strk_l2_gas_price: block.l2_gas_price.unwrap_or_default().price_in_fri,
+                sequencer_address: block
+                    .sequencer_address
+                    .unwrap_or(SequencerAddress(Felt::ZERO)),
+                starknet_version: block.starknet_version,
+                event_commitment: *event_commitment,
+                state_commitment,
+                transaction_commitment: *transaction_commitment,
+                transaction_count,
+                event_count,
+                l1_da_mode: block.l1_da_mode.into(),
+                receipt_commitment: *receipt_commitment,
+                state_diff_commitment: **state_diff_commitment,
+                state_diff_length: state_update.state_diff_length(),
+            };
 
-        transaction
-            .insert_transaction_data(header.number, &transactions_data, Some(&events_data))
-            .context("Insert transaction data into database")?;
+            transaction
+                .insert_block_header(&header)
+                .context("Inserting block header into database")?;
 
-        // Insert state updates
-        transaction
-            .insert_state_update(block.block_number, &state_update)
-            .context("Insert state update into database")?;
+            // Insert the transactions.
+            if store_transactions {
+                let (transactions_data, events_data): (Vec<_>, Vec<_>) = block
+                    .transactions
+                    .iter()
+                    .cloned()
+                    .zip(block.transaction_receipts.iter().cloned())
+                    .map(|(tx, (receipt, events))| ((tx, receipt), events))
+                    .unzip();
 
-        // Insert signature
-        transaction
-            .insert_signature(block.block_number, &signature)
-            .context("Insert signature into database")?;
+                transaction
+                    .insert_transaction_data(header.number, &transactions_data, Some(&events_data))
+                    .context("Insert transaction data into database")?;
+            }
 
-        // Track combined L1 and L2 state.
-        let l1_l2_head = transaction.l1_l2_pointer().context("Query L1-L2 head")?;
-        let expected_next = l1_l2_head
-            .map(|head| head + 1)
-            .unwrap_or(BlockNumber::GENESIS);
-
-        if expected_next == header.number {
-            if let Some(l1_state) = transaction
-                .l1_state_at_number(header.number)
-                .context("Query L1 state")?
-            {
-                if l1_state.block_hash == header.hash {
-                    transaction
-                        .update_l1_l2_pointer(Some(header.number))
-                        .context("Update L1-L2 head")?;
+            // Insert state updates
+            transaction
+                .insert_state_update(block.block_number, state_update)
+                .context("Insert state update into database")?;
+
+            // Insert signature
+            transaction
+                .insert_signature(block.block_number, signature)
+                .context("Insert signature into database")?;
+
+            // Track combined L1 and L2 state.
+            l1_l2_head = transaction.l1_l2_pointer().context("Query L1-L2 head")?;
+            let expected_next = l1_l2_head
+                .map(|head| head + 1)
+                .unwrap_or(BlockNumber::GENESIS);
+
+            if expected_next == header.number {
+                if let Some(l1_state) = transaction
+                    .l1_state_at_number(header.number)
+                    .context("Query L1 state")?
+                {
+                    if l1_state.block_hash == header.hash {
+                        transaction
+                            .update_l1_l2_pointer(Some(header.number))
+                            .context("Update L1-L2 head")?;
+                        l1_l2_head = Some(header.number);
+                    }
                 }
             }
+
+            transaction
+                .insert_block_timings(
+                    header.number,
+                    &BlockTimings {
+                        download: timings.block_download + timings.signature_download,
+                        class_declaration: timings.class_declaration,
+                        apply: clock.elapsed(apply_start),
+                    },
+                )
+                .context("Insert block timings into database")?;
+            apply_start = clock.now();
+
+            headers.push(header);
         }
 
         transaction
             .commit()
             .context("Commit database transaction")?;
 
-        if let Some(sender) = websocket_txs {
-            if let Err(e) = sender.new_head.send_if_receiving(header.clone().into()) {
-                tracing::error!(error=?e, "Failed to send header over websocket broadcaster.");
-                // Disable websocket entirely so that the closed channel doesn't spam this
-                // error. It is unlikely that any error here wouldn't simply repeat
-                // indefinitely.
-                *websocket_txs = None;
-                return Ok(());
-            }
-            if sender.l2_blocks.receiver_count() > 0 {
-                if let Err(e) = sender.l2_blocks.send(block.clone().into()) {
-                    tracing::error!(error=?e, "Failed to send block over websocket broadcaster.");
+        for (header, L2Block { block, .. }) in headers.into_iter().zip(blocks) {
+            if let Some(sender) = websocket_txs {
+                if let Err(e) = sender.new_head.send_if_receiving(header.clone().into()) {
+                    tracing::error!(error=?e, "Failed to send header over websocket broadcaster.");
+                    // Disable websocket entirely so that the closed channel doesn't spam this
+                    // error. It is unlikely that any error here wouldn't simply repeat
+                    // indefinitely.
                     *websocket_txs = None;
-                    return Ok(());
+                    continue;
+                }
+                if sender.l2_blocks.receiver_count() > 0 {
+                    if let Err(e) = sender.l2_blocks.send((*block).clone().into()) {
+                        tracing::error!(error=?e, "Failed to send block over websocket broadcaster.");
+                        *websocket_txs = None;
+                        continue;
+                    }
                 }
             }
-        }
-
-        notifications
-            .block_headers
-            .send(header.into())
-            // Ignore errors in case nobody is listening. New listeners may subscribe in the
-            // future.
-            .ok();
-        notifications
-            .l2_blocks
-            .send(block.into())
-            // Ignore errors in case nobody is listening. New listeners may subscribe in the
-            // future.
-            .ok();
 
-        Ok(())
-    })?;
+            notifications
+                .block_headers
+                .send(header.into())
+                // Ignore errors in case nobody is listening. New listeners may subscribe in
+                // the future.
+                .ok();
+            notifications
+                .l2_blocks
+                .send((*block).into())
+                // Ignore errors in case nobody is listening. New listeners may subscribe in
+                // the future.
+                .ok();
+        }
 
-    Ok(())
+        Ok((state_commitment, l1_l2_head))
+    })
 }
 
+/// Returns `false` without touching the database if `reorg_tail` is above the
+/// current L2 head.
 async fn l2_reorg(
     connection: &mut Connection,
     reorg_tail: BlockNumber,
     notifications: &mut Notifications,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<bool> {
     tokio::task::block_in_place(move || {
         let transaction = connection
             .transaction_with_behavior(TransactionBehavior::Immediate)
             .context("Create database transaction")?;
 
-        let mut head = transaction
+        let head = transaction
             .block_id(pathfinder_storage::BlockId::Latest)
             .context("Querying latest block number")?
-            .context("Latest block number is none during reorg")?
-            .0;
-
-        let reorg_tail_hash = transaction
-            .block_hash(reorg_tail.into())
-            .context("Fetching first block hash")?
-            .context("Expected first block hash to exist")?;
-        let head_hash = transaction
-            .block_hash(head.into())
-            .context("Fetching last block hash")?
-            .context("Expected last block hash to exist")?;
-
-        transaction
-            .increment_reorg_counter()
-            .context("Incrementing reorg counter")?;
-
-        // Roll back Merkle trie updates.
-        //
-        // If we're rolling back genesis then there will be no blocks left so state will
-        // be empty.
-        if let Some(target_block) = reorg_tail.parent() {
-            let target_header = transaction
-                .block_header(target_block.into())
-                .context("Fetching target block header")?
-                .context("Expected target header to exist")?;
-            revert::revert_starknet_state(&transaction, head, target_block, target_header)?;
-        }
-
-        // Purge each block one at a time.
-        //
-        // This is done 1-by-1 to allow sending the reorg'd block data
-        // to websocket subscriptions while keeping a constant memory footprint.
-        //
-        // This is acceptable performance because reorgs are rare and need not be
-        // 100% optimal. However a large reorg could cause a massive memory spike
-        // which is not acceptable.
-        while head >= reorg_tail {
-            transaction
-                .purge_block(head)
-                .with_context(|| format!("Purging block {head} from database"))?;
-
-            // No further blocks to purge if we just purged genesis.
-            if head == BlockNumber::GENESIS {
-                break;
-            }
-
-            head -= 1;
+            .map(|(number, _)| number);
+        if !head.is_some_and(|head| reorg_tail <= head) {
+            tracing::warn!(
+                %reorg_tail,
+                ?head,
+                "Ignoring reorg of blocks beyond the current L2 head"
+            );
+            return Ok(false);
         }
 
-        transaction
-            .reset()
-            .context("Resetting local DB state after reorg")?;
-
-        // Track combined L1 and L2 state.
-        let l1_l2_head = transaction.l1_l2_pointer().context("Query L1-L2 head")?;
-        if let Some(l1_l2_head) = l1_l2_head {
-            if reorg_tail == BlockNumber::GENESIS {
-                // If we purged genesis then unset the L1 L2 pointer as well since there
-                // are now no blocks remaining.
-                transaction
-                    .update_l1_l2_pointer(None)
-                    .context("Unsetting L1-L2 head")?;
-            } else if l1_l2_head >= reorg_tail {
-                transaction
-                    .update_l1_l2_pointer(Some(reorg_tail - 1))
-                    .context("Updating L1-L2 head")?;
-            }
-        }
+        let reorg = purge_l2_blocks(&transaction, reorg_tail)?;
 
         transaction
             .commit()
@@ -1106,20 +1881,139 @@ async fn l2_reorg(
 
         notifications
             .reorgs
-            .send(
-                Reorg {
-                    first_block_number: reorg_tail,
-                    first_block_hash: reorg_tail_hash,
-                    last_block_number: head,
-                    last_block_hash: head_hash,
-                }
-                .into(),
-            )
+            .send(reorg.into())
             // Ignore errors in case nobody is listening. New listeners may subscribe in the
             // future.
             .ok();
 
-        Ok(())
+        Ok(true)
+    })
+}
+
+/// Rolls the node back to `target` in a single database transaction.
+///
+/// All blocks and L1 states above `target` are purged and the L1-L2 head is
+/// moved back to `target` if it was ahead of it. Subscribers are notified of
+/// the reorg once the transaction is committed.
+pub fn reorg_to(
+    connection: &mut Connection,
+    target: BlockNumber,
+    notifications: &Notifications,
+) -> anyhow::Result<()> {
+    let transaction = connection
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .context("Create database transaction")?;
+
+    let head = transaction
+        .block_number(pathfinder_storage::BlockId::Latest)
+        .context("Querying latest block number")?;
+    let reorg = match head {
+        Some(head) if head > target => Some(purge_l2_blocks(&transaction, target + 1)?),
+        _ => None,
+    };
+
+    transaction
+        .purge_l1_states_after(target)
+        .context("Purging L1 states")?;
+
+    transaction
+        .commit()
+        .context("Commit database transaction")?;
+
+    if let Some(reorg) = reorg {
+        notifications
+            .reorgs
+            .send(reorg.into())
+            // Ignore errors in case nobody is listening.
+            .ok();
+    }
+
+    Ok(())
+}
+
+/// Purges all blocks from `reorg_tail` onwards, reverting the Merkle tries and
+/// the L1-L2 head accordingly.
+fn purge_l2_blocks(
+    transaction: &pathfinder_storage::Transaction<'_>,
+    reorg_tail: BlockNumber,
+) -> anyhow::Result<Reorg> {
+    let mut head = transaction
+        .block_id(pathfinder_storage::BlockId::Latest)
+        .context("Querying latest block number")?
+        .context("Latest block number is none during reorg")?
+        .0;
+
+    let reorg_tail_hash = transaction
+        .block_hash(reorg_tail.into())
+        .context("Fetching first block hash")?
+        .context("Expected first block hash to exist")?;
+    let head_hash = transaction
+        .block_hash(head.into())
+        .context("Fetching last block hash")?
+        .context("Expected last block hash to exist")?;
+
+    transaction
+        .increment_reorg_counter()
+        .context("Incrementing reorg counter")?;
+
+    // Roll back Merkle trie updates.
+    //
+    // If we're rolling back genesis then there will be no blocks left so state will
+    // be empty.
+    if let Some(target_block) = reorg_tail.parent() {
+        let target_header = transaction
+            .block_header(target_block.into())
+            .context("Fetching target block header")?
+            .context("Expected target header to exist")?;
+        revert::revert_starknet_state(transaction, head, target_block, target_header)?;
+    }
+
+    // Purge each block one at a time.
+    //
+    // This is done 1-by-1 to allow sending the reorg'd block data
+    // to websocket subscriptions while keeping a constant memory footprint.
+    //
+    // This is acceptable performance because reorgs are rare and need not be
+    // 100% optimal. However a large reorg could cause a massive memory spike
+    // which is not acceptable.
+    while head >= reorg_tail {
+        transaction
+            .purge_block(head)
+            .with_context(|| format!("Purging block {head} from database"))?;
+
+        // No further blocks to purge if we just purged genesis.
+        if head == BlockNumber::GENESIS {
+            break;
+        }
+
+        head -= 1;
+    }
+
+    transaction
+        .reset()
+        .context("Resetting local DB state after reorg")?;
+
+    // Track combined L1 and L2 state.
+    let l1_l2_head = transaction.l1_l2_pointer().context("Query L1-L2 head")?;
+    if let Some(l1_l2_head) = l1_l2_head {
+        if reorg_tail == BlockNumber::GENESIS {
+            // If we purged genesis then unset the L1 L2 pointer as well since there
+            // are now no blocks remaining.
+            transaction
+                .update_l1_l2_pointer(None)
+                .context("Unsetting L1-L2 head")?;
+        } else if l1_l2_head >= reorg_tail {
+            transaction
+                .update_l1_l2_pointer(Some(reorg_tail - 1))
+                .context("Updating L1-L2 head")?;
+        }
+    }
+
+    Ok(Reorg {
+        first_block_number: reorg_tail,
+        first_block_hash: reorg_tail_hash,
+        last_block_number: head,
+        last_block_hash: head_hash,
     })
 }
 
@@ -1136,21 +2030,67 @@ mod tests {
         BlockHeader,
         BlockNumber,
         ClassHash,
+        ContractAddress,
         EventCommitment,
         ReceiptCommitment,
         SierraHash,
         StateCommitment,
         StateDiffCommitment,
         StateUpdate,
+        StorageAddress,
+        StorageValue,
         TransactionCommitment,
     };
     use pathfinder_crypto::Felt;
+    use pathfinder_ethereum::EthereumStateUpdate;
+    use pathfinder_rpc::types::syncing::SyncTask;
     use pathfinder_rpc::SyncState;
     use pathfinder_storage::StorageBuilder;
     use starknet_gateway_types::reply::{self, Block, GasPrices};
 
+    use super::clock::MockClock;
     use super::l2;
-    use crate::state::sync::{consumer, ConsumerContext, SyncEvent};
+    use super::test_utils::FakeGateway;
+    use crate::state::sync::{
+        consumer,
+        event_channel,
+        l1_update,
+        l2_update,
+        purge_l2_blocks,
+        reorg_to,
+        update_sync_status_latest,
+        BlockGap,
+        ConsumerContext,
+        Gossiper,
+        L2Block,
+        Redownload,
+        StateRootMismatch,
+        SyncEvent,
+        SystemClock,
+        TransactionCountMismatch,
+        WalCheckpointCadence,
+    };
+
+    /// Collects formatted log output for tests to inspect.
+    #[derive(Clone, Default)]
+    struct Logs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Logs {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    impl std::io::Write for Logs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
 
     /// Generate some arbitrary block chain data from genesis onwards.
     ///
@@ -1282,15 +2222,7 @@ mod tests {
         // Close the event channel which allows the consumer task to exit.
         drop(event_tx);
 
-        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
-        let context = ConsumerContext {
-            storage,
-            state: Arc::new(SyncState::default()),
-            pending_data: tx,
-            verify_tree_hashes: false,
-            websocket_txs: None,
-            notifications: Default::default(),
-        };
+        let context = ConsumerContext::for_tests(storage);
 
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
         consumer(event_rx, context, tx).await.unwrap();
@@ -1336,15 +2268,7 @@ mod tests {
         // Close the event channel which allows the consumer task to exit.
         drop(event_tx);
 
-        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
-        let context = ConsumerContext {
-            storage,
-            state: Arc::new(SyncState::default()),
-            pending_data: tx,
-            verify_tree_hashes: false,
-            websocket_txs: None,
-            notifications: Default::default(),
-        };
+        let context = ConsumerContext::for_tests(storage);
 
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
         consumer(event_rx, context, tx).await.unwrap();
@@ -1365,10 +2289,7 @@ mod tests {
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn blocks_are_not_skipped_after_a_reorg() {
-        // A bug caused reorg'd block numbers to be skipped. This
-        // was due to the expected block number not being updated
-        // when handling the reorg.
+    async fn reorgs_within_window_are_coalesced() {
         let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
             pathfinder_storage::TriePruneMode::Archive,
             std::num::NonZeroU32::new(5).unwrap(),
@@ -1376,64 +2297,123 @@ mod tests {
         .unwrap();
         let mut connection = storage.connection().unwrap();
 
+        let notifications = pathfinder_rpc::Notifications::default();
+        let mut reorgs = notifications.reorgs.subscribe();
+
+        let context = ConsumerContext {
+            notifications,
+            // Ends early once the event channel is closed.
+            reorg_window: std::time::Duration::from_secs(60),
+            ..ConsumerContext::for_tests(storage)
+        };
+
         let (event_tx, event_rx) = tokio::sync::mpsc::channel(100);
+        let (current_tx, mut current_rx) = tokio::sync::watch::channel(Default::default());
+        let consumer = tokio::spawn(consumer(event_rx, context, current_tx));
 
-        // Send block updates, followed by a reorg removing block 2.
-        // Then republish block 2, which should succeed.
-        let blocks = generate_block_data();
-        let block2 = blocks[2].clone();
-        for (a, b, c, d, e) in blocks {
+        for (a, b, c, d, e) in generate_block_data() {
             event_tx
                 .send(SyncEvent::Block(a, b, c, d, e))
                 .await
                 .unwrap();
         }
-        event_tx
-            .send(SyncEvent::Reorg(block2.0 .0.block_number))
-            .await
-            .unwrap();
-        // This previously failed as the expected next block number was never
-        // updated after a reorg, causing the reorg'd block numbers to be considered
-        // duplicates and skipped - breaking sync.
-        event_tx
-            .send(SyncEvent::Block(
-                block2.0, block2.1, block2.2, block2.3, block2.4,
-            ))
+        current_rx
+            .wait_for(|(number, _)| *number == BlockNumber::new_or_panic(2))
             .await
             .unwrap();
-        // Close the event channel which allows the consumer task to exit.
+
+        // Reorgs discovered while walking back, each deeper than the previous one.
+        // Each is sent once the consumer has received the previous one, so that
+        // none of them are queued together.
+        for tail in [2, 1, 0] {
+            event_tx
+                .send(SyncEvent::Reorg(BlockNumber::new_or_panic(tail)))
+                .await
+                .unwrap();
+            while event_tx.capacity() < event_tx.max_capacity() {
+                tokio::task::yield_now().await;
+            }
+        }
         drop(event_tx);
+        consumer.await.unwrap().unwrap();
 
-        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        // A single reorg removed all blocks.
+        let reorg = reorgs.try_recv().unwrap();
+        assert_eq!(reorg.first_block_number, BlockNumber::GENESIS);
+        assert_eq!(reorg.last_block_number, BlockNumber::new_or_panic(2));
+        assert!(reorgs.try_recv().is_err());
+
+        let tx = connection.transaction().unwrap();
+        assert!(!tx.block_exists(BlockNumber::GENESIS.into()).unwrap());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn paused_sync_stops_writing_blocks() {
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        // A single slot, so that a held back event leaves the channel observably full.
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(1);
+        let mut blocks = generate_block_data().into_iter();
+
+        let state = Arc::new(SyncState::default());
         let context = ConsumerContext {
-            storage,
-            state: Arc::new(SyncState::default()),
-            pending_data: tx,
-            verify_tree_hashes: false,
-            websocket_txs: None,
-            notifications: Default::default(),
+            state: state.clone(),
+            ..ConsumerContext::for_tests(storage)
         };
 
-        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
-        consumer(event_rx, context, tx).await.unwrap();
+        let (current_tx, mut current_rx) = tokio::sync::watch::channel(Default::default());
+        let consumer = tokio::spawn(consumer(event_rx, context, current_tx));
 
-        let tx = connection.transaction().unwrap();
-        let genesis_exists = tx.block_exists(BlockNumber::GENESIS.into()).unwrap();
-        assert!(genesis_exists);
+        let (a, b, c, d, e) = blocks.next().unwrap();
+        event_tx
+            .send(SyncEvent::Block(a, b, c, d, e))
+            .await
+            .unwrap();
+        current_rx.changed().await.unwrap();
 
-        let block_1_exists = tx
+        // Takes effect before the next event is processed.
+        state.pause();
+        // The second send only completes once the consumer has taken the first
+        // block off the channel and is holding it back.
+        for (a, b, c, d, e) in blocks {
+            event_tx
+                .send(SyncEvent::Block(a, b, c, d, e))
+                .await
+                .unwrap();
+        }
+        assert_matches::assert_matches!(
+            event_tx.try_send(SyncEvent::Reorg(BlockNumber::new_or_panic(3))),
+            Err(tokio::sync::mpsc::error::TrySendError::Full(_))
+        );
+        assert!(!current_rx.has_changed().unwrap());
+        let tx = connection.transaction().unwrap();
+        assert!(tx.block_exists(BlockNumber::GENESIS.into()).unwrap());
+        assert!(!tx
             .block_exists(BlockNumber::new_or_panic(1).into())
+            .unwrap());
+        drop(tx);
+
+        state.resume();
+        current_rx
+            .wait_for(|(number, _)| *number == BlockNumber::new_or_panic(2))
+            .await
             .unwrap();
-        assert!(block_1_exists);
-        // Block 2 should actually exist as well again.
-        let block_2_exists = tx
+        drop(event_tx);
+        consumer.await.unwrap().unwrap();
+
+        let tx = connection.transaction().unwrap();
+        assert!(tx
             .block_exists(BlockNumber::new_or_panic(2).into())
-            .unwrap();
-        assert!(block_2_exists);
+            .unwrap());
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn reorg_to_genesis() {
+    async fn reorg_beyond_head_is_ignored() {
         let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
             pathfinder_storage::TriePruneMode::Archive,
             std::num::NonZeroU32::new(5).unwrap(),
@@ -1443,40 +2423,38 @@ mod tests {
 
         let (event_tx, event_rx) = tokio::sync::mpsc::channel(100);
 
-        // Send block updates, followed by a reorg to genesis.
-        for (a, b, c, d, e) in generate_block_data() {
+        let block_data = generate_block_data();
+        let num_blocks = block_data.len();
+        for (a, b, c, d, e) in block_data {
             event_tx
                 .send(SyncEvent::Block(a, b, c, d, e))
                 .await
                 .unwrap();
         }
         event_tx
-            .send(SyncEvent::Reorg(BlockNumber::GENESIS))
+            .send(SyncEvent::Reorg(BlockNumber::new_or_panic(
+                num_blocks as u64 + 1,
+            )))
             .await
             .unwrap();
         // Close the event channel which allows the consumer task to exit.
         drop(event_tx);
 
-        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
-        let context = ConsumerContext {
-            storage,
-            state: Arc::new(SyncState::default()),
-            pending_data: tx,
-            verify_tree_hashes: false,
-            websocket_txs: None,
-            notifications: Default::default(),
-        };
+        let context = ConsumerContext::for_tests(storage);
 
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
         consumer(event_rx, context, tx).await.unwrap();
 
         let tx = connection.transaction().unwrap();
-        let genesis_exists = tx.block_exists(BlockNumber::GENESIS.into()).unwrap();
-        assert!(!genesis_exists);
+        let (head, _) = tx
+            .block_id(pathfinder_storage::BlockId::Latest)
+            .unwrap()
+            .unwrap();
+        assert_eq!(head, BlockNumber::new_or_panic(num_blocks as u64 - 1));
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn new_cairo_contract() {
+    async fn reorg_to_purges_blocks_and_l1_state() {
         let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
             pathfinder_storage::TriePruneMode::Archive,
             std::num::NonZeroU32::new(5).unwrap(),
@@ -1484,125 +2462,1439 @@ mod tests {
         .unwrap();
         let mut connection = storage.connection().unwrap();
 
-        let (event_tx, event_rx) = tokio::sync::mpsc::channel(1);
-
-        let class_hash = class_hash_bytes!(b"class hash");
-        let expected_definition = b"cairo class definition".to_vec();
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(100);
 
-        event_tx
-            .send(SyncEvent::CairoClass {
-                definition: expected_definition.clone(),
-                hash: class_hash,
+        let block_data = generate_block_data();
+        let l1_states = block_data
+            .iter()
+            .map(|((block, _), state_update, ..)| EthereumStateUpdate {
+                state_root: state_update.state_commitment,
+                block_number: block.block_number,
+                block_hash: block.block_hash,
             })
-            .await
-            .unwrap();
-        // This closes the event channel which ends the consumer task.
+            .collect::<Vec<_>>();
+        for (a, b, c, d, e) in block_data {
+            event_tx
+                .send(SyncEvent::Block(a, b, c, d, e))
+                .await
+                .unwrap();
+        }
+        // Close the event channel which allows the consumer task to exit.
         drop(event_tx);
-        // UUT
-        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
-        let context = ConsumerContext {
-            storage,
-            state: Arc::new(SyncState::default()),
-            pending_data: tx,
-            verify_tree_hashes: false,
-            websocket_txs: None,
-            notifications: Default::default(),
-        };
+
+        let context = ConsumerContext::for_tests(storage);
 
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
         consumer(event_rx, context, tx).await.unwrap();
 
         let tx = connection.transaction().unwrap();
-        let definition = tx.class_definition(class_hash).unwrap().unwrap();
+        tx.upsert_l1_states(&l1_states).unwrap();
+        tx.update_l1_l2_pointer(Some(BlockNumber::new_or_panic(2)))
+            .unwrap();
+        tx.commit().unwrap();
 
-        assert_eq!(definition, expected_definition);
-    }
+        let notifications = pathfinder_rpc::Notifications::default();
+        let mut reorgs = notifications.reorgs.subscribe();
 
-    #[tokio::test(flavor = "multi_thread")]
-    async fn new_sierra_contract() {
-        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
-            pathfinder_storage::TriePruneMode::Archive,
-            std::num::NonZeroU32::new(5).unwrap(),
-        )
-        .unwrap();
-        let mut connection = storage.connection().unwrap();
+        let target = BlockNumber::new_or_panic(1);
+        reorg_to(&mut connection, target, &notifications).unwrap();
 
-        let (event_tx, event_rx) = tokio::sync::mpsc::channel(1);
+        let reorg = reorgs.try_recv().unwrap();
+        assert_eq!(reorg.first_block_number, BlockNumber::new_or_panic(2));
+        assert_eq!(reorg.last_block_number, BlockNumber::new_or_panic(2));
 
-        let class_hash = felt_bytes!(b"class hash");
-        let expected_definition = b"sierra class definition".to_vec();
+        let tx = connection.transaction().unwrap();
+        let latest = tx
+            .block_number(pathfinder_storage::BlockId::Latest)
+            .unwrap();
+        assert_eq!(latest, Some(target));
+        let l1_latest = tx.latest_l1_block_number().unwrap();
+        assert_eq!(l1_latest, Some(target));
+        let l1_l2_head = tx.l1_l2_pointer().unwrap();
+        assert_eq!(l1_l2_head, Some(target));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn blocks_are_not_skipped_after_a_reorg() {
+        // A bug caused reorg'd block numbers to be skipped. This
+        // was due to the expected block number not being updated
+        // when handling the reorg.
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(100);
 
+        // Send block updates, followed by a reorg removing block 2.
+        // Then republish block 2, which should succeed.
+        let blocks = generate_block_data();
+        let block2 = blocks[2].clone();
+        for (a, b, c, d, e) in blocks {
+            event_tx
+                .send(SyncEvent::Block(a, b, c, d, e))
+                .await
+                .unwrap();
+        }
         event_tx
-            .send(SyncEvent::SierraClass {
-                sierra_definition: expected_definition.clone(),
-                sierra_hash: SierraHash(class_hash),
-                casm_definition: b"casm definition".to_vec(),
-                casm_hash: casm_hash_bytes!(b"casm hash"),
-            })
+            .send(SyncEvent::Reorg(block2.0 .0.block_number))
             .await
             .unwrap();
-        // This closes the event channel which ends the consumer task.
+        // This previously failed as the expected next block number was never
+        // updated after a reorg, causing the reorg'd block numbers to be considered
+        // duplicates and skipped - breaking sync.
+        event_tx
+            .send(SyncEvent::Block(
+                block2.0, block2.1, block2.2, block2.3, block2.4,
+            ))
+            .await
+            .unwrap();
+        // Close the event channel which allows the consumer task to exit.
+        drop(event_tx);
+
+        let context = ConsumerContext::for_tests(storage);
+
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        consumer(event_rx, context, tx).await.unwrap();
+
+        let tx = connection.transaction().unwrap();
+        let genesis_exists = tx.block_exists(BlockNumber::GENESIS.into()).unwrap();
+        assert!(genesis_exists);
+
+        let block_1_exists = tx
+            .block_exists(BlockNumber::new_or_panic(1).into())
+            .unwrap();
+        assert!(block_1_exists);
+        // Block 2 should actually exist as well again.
+        let block_2_exists = tx
+            .block_exists(BlockNumber::new_or_panic(2).into())
+            .unwrap();
+        assert!(block_2_exists);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reorg_to_genesis() {
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(100);
+
+        // Send block updates, followed by a reorg to genesis.
+        for (a, b, c, d, e) in generate_block_data() {
+            event_tx
+                .send(SyncEvent::Block(a, b, c, d, e))
+                .await
+                .unwrap();
+        }
+        event_tx
+            .send(SyncEvent::Reorg(BlockNumber::GENESIS))
+            .await
+            .unwrap();
+        // Close the event channel which allows the consumer task to exit.
         drop(event_tx);
 
+        let context = ConsumerContext::for_tests(storage);
+
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        consumer(event_rx, context, tx).await.unwrap();
+
+        let tx = connection.transaction().unwrap();
+        let genesis_exists = tx.block_exists(BlockNumber::GENESIS.into()).unwrap();
+        assert!(!genesis_exists);
+    }
+
+    #[test]
+    fn trie_nodes_of_reorged_blocks_are_pruned() {
+        use pathfinder_merkle_tree::starknet_state::update_starknet_state;
+
+        const CONTRACT: ContractAddress = contract_address!("0x1234");
+
+        /// Commits a block updating the storage of [CONTRACT].
+        fn insert_block(
+            connection: &mut pathfinder_storage::Connection,
+            storage: &pathfinder_storage::Storage,
+            parent: Option<&BlockHeader>,
+            value: u64,
+        ) -> BlockHeader {
+            let deployed = match parent {
+                Some(_) => StateUpdate::default(),
+                None => {
+                    StateUpdate::default().with_deployed_contract(CONTRACT, class_hash!("0xc1a55"))
+                }
+            };
+            let state_update = (0..2).fold(deployed, |state_update, key| {
+                state_update.with_storage_update(
+                    CONTRACT,
+                    StorageAddress(Felt::from_u64(key)),
+                    StorageValue(Felt::from_u64(value + key)),
+                )
+            });
+            let number = parent.map_or(BlockNumber::GENESIS, |parent| parent.number + 1);
+
+            let tx = connection.transaction().unwrap();
+            let (storage_commitment, class_commitment) =
+                update_starknet_state(&tx, (&state_update).into(), false, number, storage.clone())
+                    .unwrap();
+            let builder = match parent {
+                Some(parent) => parent.child_builder(),
+                None => BlockHeader::builder(),
+            };
+            let header = builder
+                .calculated_state_commitment(storage_commitment, class_commitment)
+                .finalize_with_hash(BlockHash(Felt::from_u64(100 + value)));
+            tx.insert_block_header(&header).unwrap();
+            tx.insert_state_update(number, &state_update).unwrap();
+            tx.commit().unwrap();
+            header
+        }
+
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Prune { num_blocks_kept: 0 },
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        let genesis = insert_block(&mut connection, &storage, None, 0);
+        let block_1 = insert_block(&mut connection, &storage, Some(&genesis), 10);
+        let reorged = insert_block(&mut connection, &storage, Some(&block_1), 20);
+
+        let tx = connection.transaction().unwrap();
+        let storage_root = tx.storage_root_index(reorged.number).unwrap().unwrap();
+        let contract_root = tx
+            .contract_root_index(reorged.number, CONTRACT)
+            .unwrap()
+            .unwrap();
+        // Reverting the tries marks the nodes of the reorged block for removal at the
+        // reorg target.
+        purge_l2_blocks(&tx, reorged.number).unwrap();
+        assert!(tx.storage_trie_node(storage_root).unwrap().is_some());
+        assert!(tx.contract_trie_node(contract_root).unwrap().is_some());
+        tx.commit().unwrap();
+
+        // They are pruned once the replacing block is stored.
+        let replacing = insert_block(&mut connection, &storage, Some(&block_1), 30);
+
+        let tx = connection.transaction().unwrap();
+        let head = tx.block_id(pathfinder_storage::BlockId::Latest).unwrap();
+        assert_eq!(head, Some((replacing.number, replacing.hash)));
+        assert!(tx.storage_trie_node(storage_root).unwrap().is_none());
+        assert!(tx.contract_trie_node(contract_root).unwrap().is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reorg_deeper_than_max_depth_pauses_sync() {
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(100);
+
+        // Send blocks 0 to 2, followed by a reorg removing all three of them.
+        for (a, b, c, d, e) in generate_block_data() {
+            event_tx
+                .send(SyncEvent::Block(a, b, c, d, e))
+                .await
+                .unwrap();
+        }
+        event_tx
+            .send(SyncEvent::Reorg(BlockNumber::GENESIS))
+            .await
+            .unwrap();
+        drop(event_tx);
+
+        let state = Arc::new(SyncState::default());
         let context = ConsumerContext {
-            storage,
-            state: Arc::new(SyncState::default()),
-            pending_data: tx,
-            verify_tree_hashes: false,
-            websocket_txs: None,
-            notifications: Default::default(),
+            state: state.clone(),
+            max_reorg_depth: Some(std::num::NonZeroU64::new(2).unwrap()),
+            ..ConsumerContext::for_tests(storage)
         };
 
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
-        consumer(event_rx, context, tx).await.unwrap();
+        let consumer = tokio::spawn(consumer(event_rx, context, tx));
 
+        while !state.is_paused() {
+            tokio::task::yield_now().await;
+        }
+        assert!(!consumer.is_finished());
         let tx = connection.transaction().unwrap();
-        let definition = tx.class_definition(ClassHash(class_hash)).unwrap().unwrap();
+        for block in 0..3 {
+            let exists = tx
+                .block_exists(BlockNumber::new_or_panic(block).into())
+                .unwrap();
+            assert!(exists, "Block {block} should not have been purged");
+        }
+        drop(tx);
 
-        assert_eq!(definition, expected_definition);
+        // Resuming applies the reorg.
+        state.resume();
+        consumer.await.unwrap().unwrap();
+
+        let tx = connection.transaction().unwrap();
+        assert!(!tx.block_exists(BlockNumber::GENESIS.into()).unwrap());
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn consumer_should_ignore_duplicate_blocks() {
+    async fn header_only_sync_skips_transactions() {
         let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
             pathfinder_storage::TriePruneMode::Archive,
             std::num::NonZeroU32::new(5).unwrap(),
         )
         .unwrap();
+        let mut connection = storage.connection().unwrap();
 
-        let (event_tx, event_rx) = tokio::sync::mpsc::channel(5);
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(1);
 
-        let blocks = generate_block_data();
-        let (a, b, c, d, e) = blocks[0].clone();
+        let ((mut block, commitments), state_update, signature, state_diff_commitment, timings) =
+            generate_block_data().into_iter().next().unwrap();
+        let transaction_hash = transaction_hash_bytes!(b"transaction hash");
+        block.transactions = vec![pathfinder_common::transaction::Transaction {
+            hash: transaction_hash,
+            variant: Default::default(),
+        }];
+        block.transaction_receipts = vec![(
+            pathfinder_common::receipt::Receipt {
+                transaction_hash,
+                ..Default::default()
+            },
+            vec![],
+        )];
 
         event_tx
             .send(SyncEvent::Block(
-                a.clone(),
-                b.clone(),
-                c.clone(),
-                d.clone(),
-                e,
+                (block, commitments),
+                state_update,
+                signature,
+                state_diff_commitment,
+                timings,
             ))
             .await
             .unwrap();
-        event_tx
-            .send(SyncEvent::Block(a.clone(), b.clone(), c, d, e))
-            .await
-            .unwrap();
         drop(event_tx);
 
+        let context = ConsumerContext {
+            store_transactions: false,
+            ..ConsumerContext::for_tests(storage)
+        };
+
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        consumer(event_rx, context, tx).await.unwrap();
+
+        let tx = connection.transaction().unwrap();
+        assert!(tx.block_exists(BlockNumber::GENESIS.into()).unwrap());
+        let count = tx.transaction_count(BlockNumber::GENESIS.into()).unwrap();
+        assert_eq!(count, 0);
+        assert!(tx.transaction(transaction_hash).unwrap().is_none());
+    }
+
+    #[test]
+    fn event_channel_blocks_producer_only_at_capacity() {
+        use futures::FutureExt;
+
+        let capacity = std::num::NonZeroUsize::new(3).unwrap();
+        let (tx, _rx) = event_channel(capacity);
+
+        for i in 0..capacity.get() {
+            let sent = tx
+                .send(SyncEvent::Reorg(BlockNumber::GENESIS))
+                .now_or_never();
+            assert!(matches!(sent, Some(Ok(()))), "Event {i} should be queued");
+        }
+        let sent = tx
+            .send(SyncEvent::Reorg(BlockNumber::GENESIS))
+            .now_or_never();
+        assert!(sent.is_none(), "Producer should wait for the consumer");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn wal_is_checkpointed_after_configured_number_of_blocks() {
+        let logs = Logs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .finish();
+        // The consumer checkpoints on this thread through `block_in_place`.
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let dir = tempfile::tempdir().unwrap();
+        let database_path = dir.path().join("db.sqlite");
+        let storage = StorageBuilder::file(database_path)
+            .migrate()
+            .unwrap()
+            .create_pool(std::num::NonZeroU32::new(5).unwrap())
+            .unwrap();
+
+        let blocks = generate_block_data();
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(blocks.len());
+        for (a, b, c, d, e) in blocks {
+            event_tx
+                .send(SyncEvent::Block(a, b, c, d, e))
+                .await
+                .unwrap();
+        }
+        drop(event_tx);
+
         let context = ConsumerContext {
-            storage,
-            state: Arc::new(SyncState::default()),
-            pending_data: tx,
-            verify_tree_hashes: false,
-            websocket_txs: None,
-            notifications: Default::default(),
+            wal_checkpoint: WalCheckpointCadence {
+                blocks: std::num::NonZeroU64::new(3).unwrap(),
+                interval: std::time::Duration::from_secs(3600),
+            },
+            ..ConsumerContext::for_tests(storage)
         };
 
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
         consumer(event_rx, context, tx).await.unwrap();
+
+        let logs = logs.contents();
+        let checkpoints = logs
+            .lines()
+            .filter(|line| line.contains("Checkpointed database WAL"))
+            .collect::<Vec<_>>();
+        assert_eq!(checkpoints.len(), 1, "{logs}");
+        // Every frame in the WAL made it back into the database.
+        assert!(checkpoints[0].contains("complete=true"), "{logs}");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn gap_between_blocks_is_detected() {
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(100);
+
+        // Send block 0 followed by block 2.
+        let mut blocks = generate_block_data();
+        blocks.remove(1);
+        for (a, b, c, d, e) in blocks {
+            event_tx
+                .send(SyncEvent::Block(a, b, c, d, e))
+                .await
+                .unwrap();
+        }
+        drop(event_tx);
+
+        let context = ConsumerContext::for_tests(storage);
+
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        let error = consumer(event_rx, context, tx).await.unwrap_err();
+        let gap = error.downcast_ref::<BlockGap>().unwrap();
+        assert_eq!(
+            gap,
+            &BlockGap {
+                expected: BlockNumber::new_or_panic(1),
+                received: BlockNumber::new_or_panic(2),
+            }
+        );
+
+        let tx = connection.transaction().unwrap();
+        assert!(tx.block_exists(BlockNumber::GENESIS.into()).unwrap());
+        let block_2_exists = tx
+            .block_exists(BlockNumber::new_or_panic(2).into())
+            .unwrap();
+        assert!(!block_2_exists);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn apply_timing_is_taken_from_the_clock() {
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+        let mut connection = storage.connection().unwrap();
+        let (
+            (block, (transaction_commitment, event_commitment, receipt_commitment)),
+            state_update,
+            signature,
+            state_diff_commitment,
+            timings,
+        ) = generate_block_data().into_iter().next().unwrap();
+        let block = L2Block {
+            block,
+            transaction_commitment,
+            event_commitment,
+            receipt_commitment,
+            state_update,
+            signature,
+            state_diff_commitment,
+            timings,
+        };
+
+        // The clock is never advanced, so applying the block takes no time at all.
+        l2_update(
+            &mut connection,
+            vec![block],
+            false,
+            true,
+            storage.clone(),
+            &mut None,
+            &mut Default::default(),
+            &MockClock::default(),
+            &tracing::Span::none(),
+        )
+        .await
+        .unwrap();
+
+        let tx = connection.transaction().unwrap();
+        let stored = tx
+            .block_timings_in_range(BlockNumber::GENESIS, BlockNumber::GENESIS)
+            .unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].1.apply, std::time::Duration::ZERO);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn batched_empty_blocks_match_per_block_updates() {
+        let new_storage = || {
+            StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+                pathfinder_storage::TriePruneMode::Archive,
+                std::num::NonZeroU32::new(5).unwrap(),
+            )
+            .unwrap()
+        };
+        let to_l2_block = |(
+            (block, (tx_comm, ev_comm, rc_comm)),
+            state_update,
+            signature,
+            state_diff_commitment,
+            timings,
+        )| L2Block {
+            block,
+            transaction_commitment: tx_comm,
+            event_commitment: ev_comm,
+            receipt_commitment: rc_comm,
+            state_update,
+            signature,
+            state_diff_commitment,
+            timings,
+        };
+
+        // All generated blocks have empty state diffs.
+        let per_block = new_storage();
+        let mut per_block_connection = per_block.connection().unwrap();
+        for data in generate_block_data() {
+            let block = to_l2_block(data);
+            let expected = block.block.state_commitment;
+            let (state_commitment, _) = l2_update(
+                &mut per_block_connection,
+                vec![block],
+                false,
+                true,
+                per_block.clone(),
+                &mut None,
+                &mut Default::default(),
+                &SystemClock,
+                &tracing::Span::none(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(state_commitment, expected);
+        }
+
+        let batched = new_storage();
+        let mut batched_connection = batched.connection().unwrap();
+        let blocks = generate_block_data()
+            .into_iter()
+            .map(to_l2_block)
+            .collect::<Vec<_>>();
+        let expected = blocks[0].block.state_commitment;
+        let (state_commitment, _) = l2_update(
+            &mut batched_connection,
+            blocks,
+            false,
+            true,
+            batched.clone(),
+            &mut None,
+            &mut Default::default(),
+            &SystemClock,
+            &tracing::Span::none(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(state_commitment, expected);
+
+        let per_block = per_block_connection.transaction().unwrap();
+        let batched = batched_connection.transaction().unwrap();
+        assert_eq!(
+            batched
+                .block_id(pathfinder_storage::BlockId::Latest)
+                .unwrap(),
+            per_block
+                .block_id(pathfinder_storage::BlockId::Latest)
+                .unwrap()
+        );
+        for i in 0..3 {
+            let block = BlockNumber::new_or_panic(i);
+            assert_eq!(
+                batched.block_header(block.into()).unwrap(),
+                per_block.block_header(block.into()).unwrap()
+            );
+            assert_eq!(
+                batched.storage_root_index(block).unwrap(),
+                per_block.storage_root_index(block).unwrap()
+            );
+            assert_eq!(
+                batched.class_root_index(block).unwrap(),
+                per_block.class_root_index(block).unwrap()
+            );
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn l2_update_logs_within_block_span() {
+        let logs = Logs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+            .with_max_level(tracing::Level::INFO)
+            .with_ansi(false)
+            .finish();
+        // `l2_update` runs on this thread through `block_in_place`.
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+        let mut connection = storage.connection().unwrap();
+        let (
+            (block, (transaction_commitment, event_commitment, receipt_commitment)),
+            state_update,
+            signature,
+            state_diff_commitment,
+            timings,
+        ) = generate_block_data().into_iter().next().unwrap();
+        let block = L2Block {
+            block,
+            transaction_commitment,
+            event_commitment,
+            receipt_commitment,
+            state_update,
+            signature,
+            state_diff_commitment,
+            timings,
+        };
+
+        l2_update(
+            &mut connection,
+            vec![block],
+            false,
+            true,
+            storage.clone(),
+            &mut None,
+            &mut Default::default(),
+            &SystemClock,
+            &super::l2_block_span(BlockNumber::GENESIS),
+        )
+        .await
+        .unwrap();
+
+        let logs = logs.contents();
+        assert!(logs.contains("l2_block{number=0}"), "{logs}");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn new_cairo_contract() {
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(1);
+
+        let class_hash = class_hash_bytes!(b"class hash");
+        let expected_definition = b"cairo class definition".to_vec();
+
+        event_tx
+            .send(SyncEvent::CairoClass {
+                definition: pathfinder_storage::compress_class_definition(&expected_definition)
+                    .unwrap(),
+                hash: class_hash,
+            })
+            .await
+            .unwrap();
+        // This closes the event channel which ends the consumer task.
+        drop(event_tx);
+        // UUT
+        let context = ConsumerContext::for_tests(storage);
+
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        consumer(event_rx, context, tx).await.unwrap();
+
+        let tx = connection.transaction().unwrap();
+        let definition = tx.class_definition(class_hash).unwrap().unwrap();
+
+        assert_eq!(definition, expected_definition);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn new_sierra_contract() {
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(1);
+
+        let class_hash = felt_bytes!(b"class hash");
+        let expected_definition = b"sierra class definition".to_vec();
+
+        event_tx
+            .send(SyncEvent::SierraClass {
+                sierra_definition: pathfinder_storage::compress_class_definition(
+                    &expected_definition,
+                )
+                .unwrap(),
+                sierra_hash: SierraHash(class_hash),
+                casm_definition: pathfinder_storage::compress_class_definition(b"casm definition")
+                    .unwrap(),
+                casm_hash: casm_hash_bytes!(b"casm hash"),
+            })
+            .await
+            .unwrap();
+        // This closes the event channel which ends the consumer task.
+        drop(event_tx);
+
+        let context = ConsumerContext::for_tests(storage);
+
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        consumer(event_rx, context, tx).await.unwrap();
+
+        let tx = connection.transaction().unwrap();
+        let definition = tx.class_definition(ClassHash(class_hash)).unwrap().unwrap();
+
+        assert_eq!(definition, expected_definition);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn consumer_should_ignore_duplicate_blocks() {
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(5);
+
+        let blocks = generate_block_data();
+        let (a, b, c, d, e) = blocks[0].clone();
+
+        event_tx
+            .send(SyncEvent::Block(
+                a.clone(),
+                b.clone(),
+                c.clone(),
+                d.clone(),
+                e,
+            ))
+            .await
+            .unwrap();
+        event_tx
+            .send(SyncEvent::Block(a.clone(), b.clone(), c, d, e))
+            .await
+            .unwrap();
+        drop(event_tx);
+
+        let context = ConsumerContext::for_tests(storage);
+
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        consumer(event_rx, context, tx).await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn state_root_mismatch_is_reported_and_block_can_be_retried() {
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(100);
+
+        // Send block 2 with a state commitment which its (empty) state diff cannot
+        // produce, followed by the correct block 2 as a re-download would.
+        let mut blocks = generate_block_data();
+        let block2 = blocks.pop().unwrap();
+        let expected = block2.0 .0.state_commitment;
+        for (a, b, c, d, e) in blocks {
+            event_tx
+                .send(SyncEvent::Block(a, b, c, d, e))
+                .await
+                .unwrap();
+        }
+        let mut bad_block = block2.0.clone();
+        bad_block.0.state_commitment = state_commitment!("0x1234");
+        event_tx
+            .send(SyncEvent::Block(
+                bad_block,
+                block2.1.clone(),
+                block2.2.clone(),
+                block2.3.clone(),
+                block2.4,
+            ))
+            .await
+            .unwrap();
+        event_tx
+            .send(SyncEvent::Block(
+                block2.0, block2.1, block2.2, block2.3, block2.4,
+            ))
+            .await
+            .unwrap();
+        // Close the event channel which allows the consumer task to exit.
+        drop(event_tx);
+
+        let (mismatch_tx, mut mismatch_rx) = tokio::sync::mpsc::channel(1);
+        let context = ConsumerContext {
+            redownload: mismatch_tx,
+            ..ConsumerContext::for_tests(storage)
+        };
+
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        consumer(event_rx, context, tx).await.unwrap();
+
+        let mismatch = mismatch_rx.try_recv().unwrap();
+        assert_eq!(
+            mismatch,
+            Redownload::StateRootMismatch(StateRootMismatch {
+                block_number: BlockNumber::new_or_panic(2),
+                expected: state_commitment!("0x1234"),
+                computed: expected,
+            })
+        );
+        assert!(mismatch_rx.try_recv().is_err());
+
+        let tx = connection.transaction().unwrap();
+        let header = tx
+            .block_header(BlockNumber::new_or_panic(2).into())
+            .unwrap()
+            .unwrap();
+        assert_eq!(header.state_commitment, expected);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn transaction_count_mismatch_requests_redownload() {
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(1);
+
+        // Send the genesis block with a transaction but no receipt.
+        let ((mut block, commitments), state_update, signature, state_diff_commitment, timings) =
+            generate_block_data().into_iter().next().unwrap();
+        block.transactions = vec![pathfinder_common::transaction::Transaction {
+            hash: transaction_hash_bytes!(b"transaction hash"),
+            variant: Default::default(),
+        }];
+        event_tx
+            .send(SyncEvent::Block(
+                (block, commitments),
+                state_update,
+                signature,
+                state_diff_commitment,
+                timings,
+            ))
+            .await
+            .unwrap();
+        drop(event_tx);
+
+        let (redownload_tx, mut redownload_rx) = tokio::sync::mpsc::channel(1);
+        let context = ConsumerContext {
+            redownload: redownload_tx,
+            ..ConsumerContext::for_tests(storage)
+        };
+
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        consumer(event_rx, context, tx).await.unwrap();
+
+        let redownload = redownload_rx.try_recv().unwrap();
+        assert_eq!(
+            redownload,
+            Redownload::TransactionCountMismatch(TransactionCountMismatch {
+                block_number: BlockNumber::GENESIS,
+                transactions: 1,
+                receipts: 0,
+            })
+        );
+
+        let tx = connection.transaction().unwrap();
+        assert!(!tx.block_exists(BlockNumber::GENESIS.into()).unwrap());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn sync_status_changes_are_published() {
+        use pathfinder_rpc::types::syncing::{NumberedBlock, Status, Syncing};
+
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+
+        let state = Arc::new(SyncState::default());
+        let mut updates = state.subscribe();
+
+        let starting = NumberedBlock::from((block_hash!("0x0"), BlockNumber::GENESIS));
+        let highest = NumberedBlock::from((block_hash!("0x5"), BlockNumber::new_or_panic(5)));
+
+        // Track a new chain tip, then close the channel to let the task exit.
+        let (latest_tx, latest_rx) =
+            tokio::sync::watch::channel((BlockNumber::GENESIS, BlockHash::ZERO));
+        latest_tx.send((highest.number, highest.hash)).unwrap();
+        drop(latest_tx);
+        update_sync_status_latest(
+            state.clone(),
+            starting.hash,
+            starting.number,
+            latest_rx,
+            Gossiper::default(),
+            Arc::new(SystemClock),
+        )
+        .await;
+
+        assert_eq!(
+            updates.recv().await.unwrap(),
+            Syncing::Status(Status {
+                starting,
+                current: starting,
+                highest,
+                current_block_time_avg: None,
+                l1_l2_confirmed_block: None,
+            })
+        );
+
+        // Process the genesis block.
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(1);
+        let (a, b, c, d, e) = generate_block_data().remove(0);
+        let genesis = NumberedBlock::from((a.0.block_hash, a.0.block_number));
+        event_tx
+            .send(SyncEvent::Block(a, b, c, d, e))
+            .await
+            .unwrap();
+        drop(event_tx);
+
+        let context = ConsumerContext {
+            state,
+            ..ConsumerContext::for_tests(storage)
+        };
+
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        consumer(event_rx, context, tx).await.unwrap();
+
+        let Syncing::Status(status) = updates.recv().await.unwrap() else {
+            panic!("Expected syncing status");
+        };
+        assert_eq!(status.starting, starting);
+        assert_eq!(status.current, genesis);
+        assert_eq!(status.highest, highest);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn cancelling_consumer_leaves_consistent_state() {
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(100);
+
+        // Queue all blocks but keep the channel open so that the consumer only
+        // stops when cancelled.
+        for (a, b, c, d, e) in generate_block_data() {
+            event_tx
+                .send(SyncEvent::Block(a, b, c, d, e))
+                .await
+                .unwrap();
+        }
+
+        let context = ConsumerContext::for_tests(storage);
+
+        let (tx, mut current) = tokio::sync::watch::channel(Default::default());
+        let consumer = tokio::spawn(consumer(event_rx, context, tx));
+
+        // Cancel as soon as the first block has been processed, while further
+        // updates are still queued.
+        current.changed().await.unwrap();
+        consumer.abort();
+        assert!(consumer.await.unwrap_err().is_cancelled());
+
+        // Every stored block must have been stored completely.
+        let tx = connection.transaction().unwrap();
+        let (latest, _) = tx
+            .block_id(pathfinder_storage::BlockId::Latest)
+            .unwrap()
+            .unwrap();
+        for i in 0..=latest.get() {
+            let block = BlockNumber::new_or_panic(i).into();
+            assert!(tx.block_exists(block).unwrap(), "Block {i} should exist");
+            assert!(
+                tx.state_update(block).unwrap().is_some(),
+                "State update {i} should exist"
+            );
+            assert!(
+                tx.signature(block).unwrap().is_some(),
+                "Signature {i} should exist"
+            );
+        }
+
+        drop(event_tx);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn sync_status_tracks_block_time_avg() {
+        use pathfinder_rpc::types::syncing::{NumberedBlock, Status, Syncing};
+
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+
+        let state = Arc::new(SyncState::default());
+        let starting = NumberedBlock::from((block_hash!("0x0"), BlockNumber::GENESIS));
+        *state.status.write().await = Syncing::Status(Status {
+            starting,
+            current: starting,
+            highest: NumberedBlock::from((block_hash!("0x5"), BlockNumber::new_or_panic(5))),
+            current_block_time_avg: None,
+            l1_l2_confirmed_block: None,
+        });
+        let mut updates = state.subscribe();
+        let clock = MockClock::default();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(1);
+        let context = ConsumerContext {
+            state,
+            block_time_weight: 0.5,
+            clock: Arc::new(clock.clone()),
+            ..ConsumerContext::for_tests(storage)
+        };
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        let consumer = tokio::spawn(consumer(event_rx, context, tx));
+
+        let mut block_time_avgs = Vec::new();
+        for (a, b, c, d, e) in generate_block_data().into_iter().take(2) {
+            // Simulate blocks arriving slowly.
+            clock.advance(std::time::Duration::from_millis(100));
+            event_tx
+                .send(SyncEvent::Block(a, b, c, d, e))
+                .await
+                .unwrap();
+
+            let Syncing::Status(status) = updates.recv().await.unwrap() else {
+                panic!("Expected syncing status");
+            };
+            block_time_avgs.push(status.current_block_time_avg.unwrap());
+        }
+        drop(event_tx);
+        consumer.await.unwrap().unwrap();
+
+        // Each block took 100ms, starting from an average of zero. The average is
+        // computed with f32 precision.
+        let block_time_avgs = block_time_avgs
+            .iter()
+            .map(|avg| (avg.as_secs_f64() * 1000.0).round())
+            .collect::<Vec<_>>();
+        assert_eq!(block_time_avgs, vec![50.0, 75.0]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn queued_l1_updates_are_preferred() {
+        use pathfinder_rpc::types::syncing::{NumberedBlock, Status, Syncing};
+
+        /// Queues blocks 0 and 1 followed by the L1 update confirming block 1,
+        /// and returns the L1-L2 head published along with block 1.
+        async fn confirmed_with_block_1(prefer_l1: bool) -> Option<BlockNumber> {
+            let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+                pathfinder_storage::TriePruneMode::Archive,
+                std::num::NonZeroU32::new(5).unwrap(),
+            )
+            .unwrap();
+
+            let state = Arc::new(SyncState::default());
+            let starting = NumberedBlock::from((block_hash!("0x0"), BlockNumber::GENESIS));
+            *state.status.write().await = Syncing::Status(Status {
+                starting,
+                current: starting,
+                highest: NumberedBlock::from((block_hash!("0x5"), BlockNumber::new_or_panic(5))),
+                current_block_time_avg: None,
+                l1_l2_confirmed_block: None,
+            });
+            let mut updates = state.subscribe();
+
+            let (event_tx, event_rx) = tokio::sync::mpsc::channel(10);
+            let (l1_event_tx, l1_event_rx) = tokio::sync::mpsc::channel(10);
+            let mut blocks = generate_block_data()
+                .into_iter()
+                .take(2)
+                .collect::<Vec<_>>();
+            let block_1 = &blocks[1];
+            let l1_update = EthereumStateUpdate {
+                state_root: block_1.0 .0.state_commitment,
+                block_number: block_1.0 .0.block_number,
+                block_hash: block_1.0 .0.block_hash,
+            };
+            for (a, b, c, d, e) in blocks.drain(..) {
+                event_tx
+                    .send(SyncEvent::Block(a, b, c, d, e))
+                    .await
+                    .unwrap();
+            }
+            let l1_tx = if prefer_l1 { &l1_event_tx } else { &event_tx };
+            l1_tx.send(SyncEvent::L1Update(l1_update)).await.unwrap();
+            drop(event_tx);
+            drop(l1_event_tx);
+
+            let context = ConsumerContext {
+                l1_events: prefer_l1.then_some(l1_event_rx),
+                state,
+                ..ConsumerContext::for_tests(storage)
+            };
+            let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+            consumer(event_rx, context, tx).await.unwrap();
+
+            loop {
+                let Syncing::Status(status) = updates.try_recv().unwrap() else {
+                    panic!("Expected syncing status");
+                };
+                if status.current.number == BlockNumber::new_or_panic(1) {
+                    return status.l1_l2_confirmed_block;
+                }
+            }
+        }
+
+        // In arrival order, block 1 is stored before the L1 update is seen.
+        assert_eq!(confirmed_with_block_1(false).await, None);
+        // Preferred, the L1 update is stored first so block 1 is confirmed as soon
+        // as it is stored.
+        assert_eq!(
+            confirmed_with_block_1(true).await,
+            Some(BlockNumber::new_or_panic(1))
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn sync_status_tracks_l1_l2_head() {
+        use pathfinder_rpc::types::syncing::{NumberedBlock, Status, Syncing};
+
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+
+        let state = Arc::new(SyncState::default());
+        let starting = NumberedBlock::from((block_hash!("0x0"), BlockNumber::GENESIS));
+        *state.status.write().await = Syncing::Status(Status {
+            starting,
+            current: starting,
+            highest: NumberedBlock::from((block_hash!("0x5"), BlockNumber::new_or_panic(5))),
+            current_block_time_avg: None,
+            l1_l2_confirmed_block: None,
+        });
+        let mut updates = state.subscribe();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(1);
+        let context = ConsumerContext {
+            state,
+            ..ConsumerContext::for_tests(storage)
+        };
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        let consumer = tokio::spawn(consumer(event_rx, context, tx));
+
+        let (a, b, c, d, e) = generate_block_data().into_iter().next().unwrap();
+        let l1_update = EthereumStateUpdate {
+            state_root: b.state_commitment,
+            block_number: a.0.block_number,
+            block_hash: a.0.block_hash,
+        };
+        event_tx
+            .send(SyncEvent::Block(a, b, c, d, e))
+            .await
+            .unwrap();
+        let Syncing::Status(status) = updates.recv().await.unwrap() else {
+            panic!("Expected syncing status");
+        };
+        assert_eq!(status.l1_l2_confirmed_block, None);
+
+        event_tx.send(SyncEvent::L1Update(l1_update)).await.unwrap();
+        let Syncing::Status(status) = updates.recv().await.unwrap() else {
+            panic!("Expected syncing status");
+        };
+        assert_eq!(status.l1_l2_confirmed_block, Some(BlockNumber::GENESIS));
+
+        drop(event_tx);
+        consumer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn l1_update_sorts_unordered_batch() {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        let genesis =
+            BlockHeader::builder().finalize_with_hash(block_hash_bytes!(b"genesis block hash"));
+        let child = genesis
+            .child_builder()
+            .finalize_with_hash(block_hash_bytes!(b"child block hash"));
+        let tx = connection.transaction().unwrap();
+        tx.insert_block_header(&genesis).unwrap();
+        tx.insert_block_header(&child).unwrap();
+        tx.commit().unwrap();
+
+        let mut updates = [child.clone(), genesis].map(|header| EthereumStateUpdate {
+            state_root: header.state_commitment,
+            block_number: header.number,
+            block_hash: header.hash,
+        });
+
+        let l1_l2_head = l1_update(&mut connection, &mut updates).await.unwrap();
+
+        assert_eq!(l1_l2_head, Some(child.number));
+        assert_eq!(
+            updates.map(|update| update.block_number),
+            [BlockNumber::GENESIS, child.number]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn failed_sync_keeps_committed_blocks() {
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+        let context = super::SyncContext {
+            // Makes the reorg sent after the first two blocks fail the consumer.
+            max_reorg_depth: Some(std::num::NonZeroU64::new(1).unwrap()),
+            ..super::SyncContext::for_tests(storage.clone(), FakeGateway::default())
+        };
+
+        let sync = tokio::spawn(super::sync(
+            context,
+            |_, _| std::future::pending(),
+            |tx, _, _, _, _| async move {
+                for (a, b, c, d, e) in generate_block_data().into_iter().take(2) {
+                    tx.send(SyncEvent::Block(a, b, c, d, e)).await.unwrap();
+                }
+                tx.send(SyncEvent::Reorg(BlockNumber::GENESIS))
+                    .await
+                    .unwrap();
+                std::future::pending().await
+            },
+        ));
+        let result = tokio::time::timeout(std::time::Duration::from_secs(10), sync)
+            .await
+            .expect("Sync should terminate")
+            .unwrap();
+        result.unwrap_err();
+
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        let head = tx.block_id(pathfinder_storage::BlockId::Latest).unwrap();
+        let (block, ..) = generate_block_data().into_iter().nth(1).unwrap();
+        assert_eq!(head, Some((block.0.block_number, block.0.block_hash)));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn cancelled_sync_stops_tasks_and_leaves_consistent_state() {
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+        let alive = Arc::new(());
+        let shutdown = util::task::CancellationToken::new();
+        let context = super::SyncContext {
+            shutdown: shutdown.clone(),
+            ..super::SyncContext::for_tests(
+                storage.clone(),
+                FakeGateway {
+                    alive: alive.clone(),
+                },
+            )
+        };
+
+        let l1_alive = alive.clone();
+        let sync = tokio::spawn(super::sync(
+            context,
+            move |_, _| {
+                let alive = l1_alive.clone();
+                async move {
+                    let _alive = alive;
+                    std::future::pending().await
+                }
+            },
+            |tx, _, _, _, _| async move {
+                // Keep the remaining blocks queued while sync is cancelled.
+                for (a, b, c, d, e) in generate_block_data() {
+                    tx.send(SyncEvent::Block(a, b, c, d, e)).await.unwrap();
+                }
+                std::future::pending().await
+            },
+        ));
+
+        let mut connection = storage.connection().unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(10), async {
+            loop {
+                let tx = connection.transaction().unwrap();
+                if tx.block_exists(BlockNumber::GENESIS.into()).unwrap() {
+                    break;
+                }
+                drop(tx);
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("Genesis should be stored");
+
+        shutdown.cancel();
+        tokio::time::timeout(std::time::Duration::from_secs(10), sync)
+            .await
+            .expect("Sync should shut down")
+            .unwrap()
+            .unwrap();
+
+        // Only this test's reference is left once all tasks have been dropped.
+        assert_eq!(Arc::strong_count(&alive), 1);
+
+        // Every stored block must have been stored completely.
+        let tx = connection.transaction().unwrap();
+        let (latest, _) = tx
+            .block_id(pathfinder_storage::BlockId::Latest)
+            .unwrap()
+            .unwrap();
+        for i in 0..=latest.get() {
+            let block = BlockNumber::new_or_panic(i).into();
+            assert!(tx.block_exists(block).unwrap(), "Block {i} should exist");
+            assert!(
+                tx.state_update(block).unwrap().is_some(),
+                "State update {i} should exist"
+            );
+            assert!(
+                tx.signature(block).unwrap().is_some(),
+                "Signature {i} should exist"
+            );
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn failing_l2_task_is_restarted() {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let clock = MockClock::default();
+        let backoff = super::RestartBackoff {
+            base: std::time::Duration::from_secs(1),
+            cap: std::time::Duration::from_secs(4),
+        };
+        let context = super::SyncContext {
+            restart_backoff: backoff,
+            clock: Arc::new(clock.clone()),
+            ..super::SyncContext::for_tests(storage, FakeGateway::default())
+        };
+        let state = context.state.clone();
+        let shutdown = context.shutdown.clone();
+
+        let sync = tokio::spawn(super::sync(
+            context,
+            |_, _| std::future::pending(),
+            |_, _, _, _, _| async { Err(anyhow::anyhow!("L2 sync failed")) },
+        ));
+
+        let wait_for_failures = |failures: u32| {
+            let state = state.clone();
+            async move {
+                tokio::time::timeout(std::time::Duration::from_secs(10), async {
+                    while state.health().await.l2.consecutive_failures < failures {
+                        tokio::task::yield_now().await;
+                    }
+                })
+                .await
+                .expect("L2 sync should be restarted");
+            }
+        };
+
+        // The initial task fails right away, after which each restart waits for
+        // the backoff to pass on the clock before failing again.
+        wait_for_failures(1).await;
+        for expected in [1, 2, 4, 4] {
+            let delay = std::time::Duration::from_secs(expected);
+            let failures = state.health().await.l2.consecutive_failures;
+            // Let sync start the delay before the clock moves.
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+            clock.advance(delay - std::time::Duration::from_millis(1));
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            assert_eq!(
+                state.health().await.l2.consecutive_failures,
+                failures,
+                "Restarted before {delay:?} had passed"
+            );
+
+            clock.advance(std::time::Duration::from_millis(1));
+            wait_for_failures(failures + 1).await;
+        }
+
+        shutdown.cancel();
+        tokio::time::timeout(std::time::Duration::from_secs(10), sync)
+            .await
+            .expect("Sync should shut down")
+            .unwrap()
+            .unwrap();
+    }
+
+    #[test]
+    fn restart_backoff_resets_once_task_emits_an_event() {
+        let state = SyncState::default();
+        let backoff = super::RestartBackoff {
+            base: std::time::Duration::from_millis(20),
+            cap: std::time::Duration::from_millis(100),
+        };
+
+        // The task fails right after each restart.
+        let delays = (0..4)
+            .map(|_| {
+                backoff
+                    .delay(state.record_task_failed(SyncTask::L2))
+                    .as_millis()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(delays, vec![20, 40, 80, 100]);
+
+        state.record_task_event(SyncTask::L2);
+        assert_eq!(
+            backoff.delay(state.record_task_failed(SyncTask::L2)),
+            backoff.base
+        );
     }
 }
@@ -1,7 +1,13 @@
 use anyhow::Context;
 use pathfinder_common::{ClassHash, SierraHash};
+use pathfinder_storage::compress_class_definition;
 use starknet_gateway_client::GatewayApi;
 
+/// A downloaded and verified class.
+///
+/// Definitions are already compressed using [compress_class_definition] so
+/// that this expensive step happens concurrently with other downloads instead
+/// of while holding the database transaction which stores the class.
 pub enum DownloadedClass {
     Cairo {
         definition: Vec<u8>,
@@ -42,6 +48,8 @@ pub async fn download_class<SequencerClient: GatewayApi>(
                 tracing::warn!(expected=%class_hash, computed=%hash, "Cairo 0 class hash mismatch");
             }
 
+            let (_, definition) = compress(definition).await?;
+
             Ok(DownloadedClass::Cairo { definition, hash })
         }
         ComputedClassHash::Sierra(hash) => {
@@ -52,6 +60,8 @@ pub async fn download_class<SequencerClient: GatewayApi>(
                 class_hash.0
             );
 
+            let (definition, sierra_definition) = compress(definition).await?;
+
             // FIXME(integration reset): work-around for integration containing Sierra
             // classes that are incompatible with production compiler. This will
             // get "fixed" in the future by resetting integration to remove
@@ -61,15 +71,12 @@ pub async fn download_class<SequencerClient: GatewayApi>(
             // replaces the casm definition with empty bytes.
             let span = tracing::Span::current();
 
-            let (sierra_definition, casm_definition) = if fetch_casm_from_fgw {
-                (
-                    definition,
-                    sequencer
-                        .pending_casm_by_hash(class_hash)
-                        .await
-                        .with_context(|| format!("Downloading CASM {}", class_hash.0))?
-                        .to_vec(),
-                )
+            let casm_definition = if fetch_casm_from_fgw {
+                sequencer
+                    .pending_casm_by_hash(class_hash)
+                    .await
+                    .with_context(|| format!("Downloading CASM {}", class_hash.0))?
+                    .to_vec()
             } else {
                 let (send, recv) = tokio::sync::oneshot::channel();
                 rayon::spawn(move || {
@@ -77,12 +84,11 @@ pub async fn download_class<SequencerClient: GatewayApi>(
                     let compile_result = pathfinder_compiler::compile_to_casm(&definition)
                         .context("Compiling Sierra class");
 
-                    let _ = send.send((compile_result, definition));
+                    let _ = send.send(compile_result);
                 });
-                let (casm_definition, sierra_definition) =
-                    recv.await.expect("Panic on rayon thread");
+                let casm_definition = recv.await.expect("Panic on rayon thread");
 
-                let casm_definition = match casm_definition {
+                match casm_definition {
                     Ok(casm_definition) => casm_definition,
                     Err(error) => {
                         tracing::info!(class_hash=%hash, ?error, "CASM compilation failed, falling back to fetching from gateway");
@@ -92,10 +98,11 @@ pub async fn download_class<SequencerClient: GatewayApi>(
                             .with_context(|| format!("Downloading CASM {}", class_hash.0))?
                             .to_vec()
                     }
-                };
-                (sierra_definition, casm_definition)
+                }
             };
 
+            let (_, casm_definition) = compress(casm_definition).await?;
+
             Ok(DownloadedClass::Sierra {
                 sierra_definition,
                 sierra_hash: SierraHash(hash.0),
@@ -104,3 +111,14 @@ pub async fn download_class<SequencerClient: GatewayApi>(
         }
     }
 }
+
+/// Compresses `definition` on the rayon pool, returning it along with its
+/// compressed form.
+async fn compress(definition: Vec<u8>) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
+    let (send, recv) = tokio::sync::oneshot::channel();
+    rayon::spawn(move || {
+        let compressed = compress_class_definition(&definition);
+        let _ = send.send(compressed.map(|compressed| (definition, compressed)));
+    });
+    recv.await.context("Panic on rayon thread")?
+}
@@ -0,0 +1,66 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+/// Source of the current time for sync, so that time-based behavior can be
+/// tested deterministically.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Time elapsed since `earlier`.
+    fn elapsed(&self, earlier: Instant) -> Duration {
+        self.now().saturating_duration_since(earlier)
+    }
+
+    /// Completes once `duration` has passed on this clock.
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// The system's monotonic clock.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock which only moves when [advanced](MockClock::advance).
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct MockClock(std::sync::Arc<tokio::sync::watch::Sender<Instant>>);
+
+#[cfg(test)]
+impl Default for MockClock {
+    fn default() -> Self {
+        Self(std::sync::Arc::new(tokio::sync::watch::Sender::new(
+            Instant::now(),
+        )))
+    }
+}
+
+#[cfg(test)]
+impl MockClock {
+    pub fn advance(&self, duration: Duration) {
+        self.0.send_modify(|now| *now += duration);
+    }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        *self.0.borrow()
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let deadline = self.now() + duration;
+        let mut now = self.0.subscribe();
+        Box::pin(async move {
+            // Wakes up early if the clock has been dropped.
+            _ = now.wait_for(|now| *now >= deadline).await;
+        })
+    }
+}
@@ -339,29 +339,64 @@ where
     }
 }
 
+/// Exponential backoff applied by [poll_latest] while requests for the latest
+/// block keep failing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollBackoff {
+    /// Delay after the first failure, doubled after each consecutive one.
+    pub base: Duration,
+    /// Upper bound for the delay.
+    pub cap: Duration,
+}
+
+impl PollBackoff {
+    /// Consecutive failures after which they are logged as warnings.
+    const WARN_AFTER_FAILURES: u32 = 5;
+
+    fn delay(&self, failures: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+        self.base.saturating_mul(factor).min(self.cap)
+    }
+}
+
 /// Emits the latest block hash and number from the gateway at regular
 /// intervals.
 ///
 /// Exits once all receivers are closed.
-/// Errors are logged and ignored.
+/// Errors are logged and polling is delayed according to `backoff`.
 pub async fn poll_latest(
     gateway: impl GatewayApi,
     interval: Duration,
+    backoff: PollBackoff,
     sender: tokio::sync::watch::Sender<(BlockNumber, BlockHash)>,
 ) {
     let mut interval = tokio::time::interval(interval);
     interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
+    let mut failures = 0;
+
     loop {
         interval.tick().await;
 
-        let Ok(latest) = gateway
+        let latest = match gateway
             .block_header(pathfinder_common::BlockId::Latest)
             .await
-            .inspect_err(|e| tracing::debug!(error=%e, "Error requesting latest block ID"))
-        else {
-            continue;
+        {
+            Ok(latest) => latest,
+            Err(error) => {
+                failures += 1;
+                let delay = backoff.delay(failures);
+                if failures >= PollBackoff::WARN_AFTER_FAILURES {
+                    tracing::warn!(%error, %failures, ?delay, "Error requesting latest block ID");
+                } else {
+                    tracing::debug!(%error, %failures, ?delay, "Error requesting latest block ID");
+                }
+
+                tokio::time::sleep(delay).await;
+                continue;
+            }
         };
+        failures = 0;
 
         if sender.send(latest).is_err() {
             tracing::debug!("Channel closed, exiting");
@@ -1940,6 +1975,44 @@ mod tests {
                      allowed"
                 );
             }
+
+            #[tokio::test(start_paused = true)]
+            async fn poll_latest_backs_off_on_errors() {
+                use std::time::Duration;
+
+                use super::super::super::{poll_latest, PollBackoff};
+
+                let mut mock = MockGatewayApi::new();
+                let mut seq = mockall::Sequence::new();
+
+                for _ in 0..3 {
+                    expect_block_header(
+                        &mut mock,
+                        &mut seq,
+                        BlockId::Latest,
+                        Err(block_not_found()),
+                    );
+                }
+                expect_block_header(
+                    &mut mock,
+                    &mut seq,
+                    BlockId::Latest,
+                    Ok((BLOCK0_NUMBER, BLOCK0_HASH)),
+                );
+
+                // With the receiver gone, polling stops after the first successful request.
+                let (tx_latest, _) = tokio::sync::watch::channel(Default::default());
+                let backoff = PollBackoff {
+                    base: Duration::from_secs(1),
+                    cap: Duration::from_secs(3),
+                };
+
+                let start = tokio::time::Instant::now();
+                poll_latest(mock, Duration::from_millis(100), backoff, tx_latest).await;
+
+                // 1s, doubled to 2s, then capped at 3s.
+                assert_eq!(start.elapsed(), Duration::from_secs(6));
+            }
         }
 
         mod reorg {
@@ -0,0 +1,76 @@
+//! Helpers for tests running [sync](super::sync), shared with the integration
+//! tests.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use pathfinder_common::macro_prelude::*;
+use pathfinder_common::prelude::*;
+use pathfinder_common::{Chain, PublicKey};
+use pathfinder_rpc::SyncState;
+use pathfinder_storage::Storage;
+
+use super::{l2, RestartBackoff, SyncContext, SystemClock};
+
+impl<G> SyncContext<G, pathfinder_ethereum::EthereumClient> {
+    /// A context with defaults, for tests to override the fields they exercise.
+    pub fn for_tests(storage: Storage, sequencer: G) -> Self {
+        Self {
+            storage,
+            ethereum: pathfinder_ethereum::EthereumClient::new("http://localhost").unwrap(),
+            chain: Chain::SepoliaTestnet,
+            chain_id: ChainId::SEPOLIA_TESTNET,
+            core_address: Default::default(),
+            sequencer,
+            state: Arc::new(SyncState::default()),
+            head_poll_interval: Duration::from_secs(1),
+            head_poll_backoff: l2::PollBackoff {
+                base: Duration::from_secs(1),
+                cap: Duration::from_secs(1),
+            },
+            l1_poll_interval: Duration::from_secs(1),
+            pending_data: tokio::sync::watch::channel(Default::default()).0,
+            block_validation_mode: l2::BlockValidationMode::Strict,
+            websocket_txs: None,
+            notifications: Default::default(),
+            block_cache_size: 100,
+            restart_backoff: RestartBackoff {
+                base: Duration::from_millis(20),
+                cap: Duration::from_millis(100),
+            },
+            verify_tree_hashes: false,
+            gossiper: Default::default(),
+            sequencer_public_key: PublicKey::ZERO,
+            fetch_concurrency: std::num::NonZeroUsize::new(1).unwrap(),
+            fetch_casm_from_fgw: false,
+            redownload_retries: 3,
+            block_time_weight: 0.05,
+            event_channel_capacity: std::num::NonZeroUsize::new(8).unwrap(),
+            max_reorg_depth: None,
+            reorg_window: Duration::ZERO,
+            store_transactions: true,
+            wal_checkpoint: Default::default(),
+            clock: Arc::new(SystemClock),
+            prefer_l1: false,
+            shutdown: Default::default(),
+        }
+    }
+}
+
+/// A gateway whose head is far enough ahead of the database for pending data
+/// not to be polled.
+#[derive(Clone, Default)]
+pub struct FakeGateway {
+    /// Every task holding a clone keeps `alive` referenced.
+    pub alive: Arc<()>,
+}
+
+#[async_trait::async_trait]
+impl starknet_gateway_client::GatewayApi for FakeGateway {
+    async fn block_header(
+        &self,
+        _: pathfinder_common::BlockId,
+    ) -> Result<(BlockNumber, BlockHash), starknet_gateway_types::error::SequencerError> {
+        Ok((BlockNumber::new_or_panic(100), block_hash_bytes!(b"latest")))
+    }
+}
@@ -82,8 +82,15 @@ where
     }
 
     async fn handle_recoverable_error(&self, err: &error::SyncError) {
-        // TODO
-        tracing::debug!(%err, "Log and punish as appropriate");
+        match err {
+            // Retrying right away would most likely fail the same way.
+            SyncError::PeersExhausted(_) | SyncError::InvalidPeerData(_) => {
+                tracing::warn!(%err, "Backing off before restarting sync");
+                tokio::time::sleep(RESET_DELAY_ON_FAILURE).await;
+            }
+            // TODO
+            _ => tracing::debug!(%err, "Log and punish as appropriate"),
+        }
     }
 
     /// Retry forever until a valid L1 checkpoint is retrieved
@@ -304,6 +311,7 @@ mod tests {
     use fake::{Fake, Faker};
     use futures::stream;
     use http::header;
+    use p2p::client::peer_agnostic::traits::SyncError as P2PSyncError;
     use p2p::client::types::{
         ClassDefinition,
         ClassDefinitionsError,
@@ -754,10 +762,11 @@ mod tests {
             start: BlockNumber,
             stop: BlockNumber,
             reverse: bool,
-        ) -> impl Stream<Item = PeerData<SignedBlockHeader>> + Send {
+            step: std::num::NonZeroU64,
+        ) -> impl Stream<Item = StreamItem<SignedBlockHeader, P2PSyncError>> + Send {
             let error_trigger = self.error_trigger.clone();
 
-            stream::iter(self.blocks(start, stop, reverse, |mut b| {
+            let headers = self.blocks(start, stop, reverse, |mut b| {
                 let block = b.header.header.number;
 
                 if error_trigger.recoverable(block, 0) || error_trigger.recoverable(block, 5) {
@@ -768,8 +777,14 @@ mod tests {
                     b.header.header = Faker.fake();
                 }
 
-                PeerData::for_tests(b.header)
-            }))
+                Ok(PeerData::for_tests(b.header))
+            });
+
+            stream::iter(
+                headers
+                    .into_iter()
+                    .step_by(step.get().try_into().expect("step fits in usize")),
+            )
         }
     }
 
@@ -778,12 +793,14 @@ mod tests {
             self,
             start: BlockNumber,
             stop: BlockNumber,
+            reverse: bool,
             _: impl Stream<Item = anyhow::Result<usize>> + Send + 'static,
-        ) -> impl Stream<Item = StreamItem<(p2p::client::types::TransactionData, BlockNumber)>> + Send
-        {
+        ) -> impl Stream<
+            Item = StreamItem<(p2p::client::types::TransactionData, BlockNumber), P2PSyncError>,
+        > + Send {
             let error_trigger = self.error_trigger.clone();
 
-            stream::iter(self.blocks(start, stop, false, |mut b| {
+            stream::iter(self.blocks(start, stop, reverse, |mut b| {
                 let block = b.header.header.number;
 
                 if error_trigger.recoverable(block, 1) {
@@ -798,7 +815,9 @@ mod tests {
                     tracing::debug!(%block,
                         "FakeP2PClient::transaction_stream triggering fatal error at",
                     );
-                    return Err(anyhow::anyhow!("Fatal error at block {block}",));
+                    return Err(P2PSyncError::Other(anyhow::anyhow!(
+                        "Fatal error at block {block}",
+                    )));
                 }
 
                 Ok(PeerData::for_tests((
@@ -12,10 +12,10 @@ use p2p::client::peer_agnostic::traits::{
     HeaderStream,
     StateDiffStream,
     StreamItem,
+    SyncError as P2PSyncError,
     TransactionStream,
 };
 use p2p::client::types::{ClassDefinition, EventsForBlockByTransaction, TransactionData};
-use p2p::PeerData;
 use p2p_proto::common::{BlockNumberOrHash, Direction, Iteration};
 use p2p_proto::transaction::{TransactionWithReceipt, TransactionsRequest, TransactionsResponse};
 use pathfinder_block_hashes::BlockHashDb;
@@ -42,7 +42,7 @@ use tracing::Instrument;
 
 use crate::state::block_hash::calculate_transaction_commitment;
 use crate::sync::error::SyncError;
-use crate::sync::stream::{InfallibleSource, Source, SyncReceiver, SyncResult};
+use crate::sync::stream::{Source, SyncReceiver, SyncResult};
 use crate::sync::{class_definitions, events, headers, state_updates, transactions};
 
 /// Provides P2P sync capability for blocks secured by L1.
@@ -169,7 +169,9 @@ where
             tracing::info!(?gap, "Syncing headers");
 
             handle_header_stream(
-                self.p2p.clone().header_stream(gap.tail, gap.head, true),
+                self.p2p
+                    .clone()
+                    .header_stream(gap.tail, gap.head, true, std::num::NonZeroU64::MIN),
                 gap.head(),
                 self.chain_id,
                 self.public_key,
@@ -198,6 +200,7 @@ where
         let transaction_stream = self.p2p.clone().transaction_stream(
             start,
             stop,
+            false,
             transactions::counts_stream(
                 self.storage.clone(),
                 start,
@@ -301,14 +304,14 @@ where
 }
 
 async fn handle_header_stream(
-    stream: impl Stream<Item = PeerData<SignedBlockHeader>> + Send + 'static,
+    stream: impl Stream<Item = StreamItem<SignedBlockHeader, P2PSyncError>> + Send + 'static,
     head: (BlockNumber, BlockHash),
     chain_id: ChainId,
     public_key: PublicKey,
     block_hash_db: Option<pathfinder_block_hashes::BlockHashDb>,
     storage: Storage,
 ) -> Result<(), SyncError> {
-    InfallibleSource::from_stream(stream)
+    Source::from_stream(stream.map_err(Into::into))
         .spawn()
         .pipe(headers::BackwardContinuity::new(head.0, head.1), 10)
         .pipe(
@@ -329,7 +332,9 @@ async fn handle_header_stream(
 }
 
 async fn handle_transaction_stream(
-    stream: impl Stream<Item = StreamItem<(TransactionData, BlockNumber)>> + Send + 'static,
+    stream: impl Stream<Item = StreamItem<(TransactionData, BlockNumber), P2PSyncError>>
+        + Send
+        + 'static,
     storage: Storage,
     chain_id: ChainId,
     start: BlockNumber,
@@ -698,6 +703,7 @@ async fn persist_anchor(storage: Storage, anchor: EthereumStateUpdate) -> anyhow
 
 #[cfg(test)]
 mod tests {
+    use p2p::PeerData;
     use tokio::task::spawn_blocking;
 
     use super::*;
@@ -867,7 +873,7 @@ mod tests {
             } = setup;
 
             handle_header_stream(
-                stream::iter(streamed_headers),
+                stream::iter(streamed_headers.into_iter().map(Ok)),
                 head,
                 ChainId::SEPOLIA_TESTNET,
                 public_key,
@@ -911,7 +917,7 @@ mod tests {
 
             assert_matches!(
                 handle_header_stream(
-                    stream::iter(streamed_headers),
+                    stream::iter(streamed_headers.into_iter().map(Ok)),
                     head,
                     ChainId::SEPOLIA_TESTNET,
                     public_key,
@@ -937,7 +943,7 @@ mod tests {
 
             assert_matches!(
                 handle_header_stream(
-                    stream::iter(streamed_headers),
+                    stream::iter(streamed_headers.into_iter().map(Ok)),
                     head,
                     // Causes mismatches for all block hashes because setup assumes Sepolia
                     ChainId::MAINNET,
@@ -962,7 +968,7 @@ mod tests {
 
             assert_matches!(
                 handle_header_stream(
-                    stream::iter(streamed_headers),
+                    stream::iter(streamed_headers.into_iter().map(Ok)),
                     head,
                     ChainId::SEPOLIA_TESTNET,
                     PublicKey::ZERO, // Invalid public key
@@ -995,7 +1001,7 @@ mod tests {
 
             assert_matches!(
                 handle_header_stream(
-                    stream::iter(streamed_headers),
+                    stream::iter(streamed_headers.into_iter().map(Ok)),
                     head,
                     ChainId::SEPOLIA_TESTNET,
                     public_key,
@@ -1029,7 +1035,8 @@ mod tests {
         use super::*;
 
         struct Setup {
-            pub streamed_transactions: Vec<StreamItem<(TransactionData, BlockNumber)>>,
+            pub streamed_transactions:
+                Vec<StreamItem<(TransactionData, BlockNumber), P2PSyncError>>,
             pub expected_transactions: Vec<Vec<(Transaction, Receipt)>>,
             pub storage: Storage,
         }
@@ -1067,7 +1074,7 @@ mod tests {
             let streamed_transactions = blocks
                 .iter()
                 .map(|block| {
-                    anyhow::Result::Ok(PeerData::for_tests((
+                    Ok(PeerData::for_tests((
                         block
                             .transaction_data
                             .iter()
@@ -1176,7 +1183,9 @@ mod tests {
         async fn stream_failure() {
             assert_matches!(
                 handle_transaction_stream(
-                    stream::once(std::future::ready(Err(anyhow::anyhow!("")))),
+                    stream::once(std::future::ready(Err(P2PSyncError::Other(
+                        anyhow::anyhow!("")
+                    )))),
                     StorageBuilder::in_memory().unwrap(),
                     ChainId::SEPOLIA_TESTNET,
                     BlockNumber::GENESIS,
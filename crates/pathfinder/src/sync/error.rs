@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use p2p::client::peer_agnostic::traits::SyncError as P2PSyncError;
 use p2p::libp2p::PeerId;
 use p2p::PeerData;
 use pathfinder_common::{BlockNumber, ClassHash, SignedBlockHeader};
@@ -42,6 +43,10 @@ pub(super) enum SyncError {
     IncorrectStateDiffCount(PeerId),
     #[error("Invalid data in DTO")]
     InvalidDto(PeerId),
+    #[error("Peers sent invalid data for block {0}")]
+    InvalidPeerData(BlockNumber),
+    #[error("No peer could serve block {0}")]
+    PeersExhausted(BlockNumber),
     #[error("Incorrect sierra definition")]
     SierraDefinitionError(PeerId),
     #[error("State diff commitment mismatch")]
@@ -94,6 +99,8 @@ impl PartialEq for SyncError {
                 x == y
             }
             (SyncError::InvalidDto(x), SyncError::InvalidDto(y)) => x == y,
+            (SyncError::InvalidPeerData(x), SyncError::InvalidPeerData(y)) => x == y,
+            (SyncError::PeersExhausted(x), SyncError::PeersExhausted(y)) => x == y,
             (SyncError::SierraDefinitionError(x), SyncError::SierraDefinitionError(y)) => x == y,
             (
                 SyncError::StateDiffCommitmentMismatch(x),
@@ -119,3 +126,13 @@ impl From<anyhow::Error> for SyncError {
         Self::Fatal(Arc::new(e))
     }
 }
+
+impl From<P2PSyncError> for SyncError {
+    fn from(e: P2PSyncError) -> Self {
+        match e {
+            P2PSyncError::PeersExhausted { block } => Self::PeersExhausted(block),
+            P2PSyncError::Conversion { block, .. } => Self::InvalidPeerData(block),
+            P2PSyncError::Other(e) => Self::Fatal(Arc::new(e)),
+        }
+    }
+}
@@ -209,10 +209,21 @@ impl<L, P> HeaderSource<L, P> {
         util::task::spawn(async move {
             let mut latest_onchain = Box::pin(latest_onchain);
             while let Some(latest_onchain) = latest_onchain.next().await {
-                let mut headers =
-                    Box::pin(p2p.clone().header_stream(start, latest_onchain.0, false));
+                let mut headers = Box::pin(p2p.clone().header_stream(
+                    start,
+                    latest_onchain.0,
+                    false,
+                    std::num::NonZeroU64::MIN,
+                ));
 
                 while let Some(header) = headers.next().await {
+                    let header = match header {
+                        Ok(header) => header,
+                        Err(error) => {
+                            _ = tx.send(Err(error.into())).await;
+                            return;
+                        }
+                    };
                     start = header.data.header.number + 1;
 
                     if tx.send(Ok(header)).await.is_err() {
@@ -0,0 +1,145 @@
+//! This test was separated because the `metrics` crate uses a singleton
+//! recorder, so keeping a test that relies on metric values in a separate
+//! binary makes more sense than using an inter-test locking mechanism.
+
+use std::time::Duration;
+
+use pathfinder_common::test_utils::metrics::{FakeRecorder, ScopedRecorderGuard};
+use pathfinder_common::{
+    BlockCommitmentSignature,
+    BlockHash,
+    BlockHeader,
+    BlockNumber,
+    ClassHash,
+    StateCommitment,
+    StateUpdate,
+};
+use pathfinder_crypto::Felt;
+use pathfinder_lib::state::test_utils::FakeGateway;
+use pathfinder_lib::state::{self, l2, SyncContext, SyncEvent};
+use pathfinder_storage::{StorageBuilder, TriePruneMode};
+use starknet_gateway_types::reply::{self, GasPrices};
+
+/// Blocks 0 to 2 with empty state diffs.
+fn blocks() -> Vec<SyncEvent> {
+    let mut header = BlockHeader::builder()
+        .finalize_with_hash(BlockHash(Felt::from_be_slice(b"genesis").unwrap()));
+    let mut blocks = Vec::new();
+    for i in 0..3u8 {
+        if i > 0 {
+            header = header
+                .child_builder()
+                .finalize_with_hash(BlockHash(Felt::from_be_slice(&[i]).unwrap()));
+        }
+        let block = reply::Block {
+            block_hash: header.hash,
+            block_number: header.number,
+            l1_gas_price: GasPrices::default(),
+            l1_data_gas_price: GasPrices::default(),
+            l2_gas_price: None,
+            parent_block_hash: header.parent_hash,
+            sequencer_address: Some(header.sequencer_address),
+            state_commitment: header.state_commitment,
+            status: reply::Status::AcceptedOnL2,
+            timestamp: header.timestamp,
+            transaction_receipts: vec![],
+            transactions: vec![],
+            starknet_version: header.starknet_version,
+            l1_da_mode: Default::default(),
+            transaction_commitment: header.transaction_commitment,
+            event_commitment: header.event_commitment,
+            receipt_commitment: Some(header.receipt_commitment),
+            state_diff_commitment: Some(header.state_diff_commitment),
+            state_diff_length: Some(0),
+        };
+        blocks.push(SyncEvent::Block(
+            (
+                Box::new(block),
+                (
+                    header.transaction_commitment,
+                    header.event_commitment,
+                    header.receipt_commitment,
+                ),
+            ),
+            Box::new(
+                StateUpdate::default()
+                    .with_block_hash(header.hash)
+                    .with_parent_state_commitment(StateCommitment::ZERO)
+                    .with_state_commitment(header.state_commitment),
+            ),
+            Box::new(BlockCommitmentSignature::default()),
+            Box::new(header.state_diff_commitment),
+            l2::Timings::default(),
+        ));
+    }
+    blocks
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sync_counters() {
+    let recorder = FakeRecorder::default();
+    let handle = recorder.handle();
+    // Automatically deregister the recorder
+    let _guard = ScopedRecorderGuard::new(recorder);
+
+    let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+        TriePruneMode::Archive,
+        std::num::NonZeroU32::new(5).unwrap(),
+    )
+    .unwrap();
+    let notifications = pathfinder_rpc::Notifications::default();
+    let mut reorgs = notifications.reorgs.subscribe();
+    let shutdown = util::task::CancellationToken::new();
+    let context = SyncContext {
+        notifications,
+        shutdown: shutdown.clone(),
+        ..SyncContext::for_tests(storage, FakeGateway::default())
+    };
+
+    let sync = tokio::spawn(state::sync(
+        context,
+        |_, _| std::future::pending(),
+        |tx, _, _, _, _| async move {
+            tx.send(SyncEvent::CairoClass {
+                definition: pathfinder_storage::compress_class_definition(b"definition").unwrap(),
+                hash: ClassHash(Felt::from_be_slice(b"class hash").unwrap()),
+            })
+            .await
+            .unwrap();
+            for block in blocks() {
+                tx.send(block).await.unwrap();
+            }
+            // Removes block 2.
+            tx.send(SyncEvent::Reorg(BlockNumber::new_or_panic(2)))
+                .await
+                .unwrap();
+            std::future::pending().await
+        },
+    ));
+
+    // The consumer updates the counters right after publishing the reorg, before
+    // yielding to the cancellation.
+    tokio::time::timeout(Duration::from_secs(10), reorgs.recv())
+        .await
+        .expect("Reorg should be applied")
+        .unwrap();
+    shutdown.cancel();
+    tokio::time::timeout(Duration::from_secs(10), sync)
+        .await
+        .expect("Sync should shut down")
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        handle.get_counter_value_by_label("sync_blocks_applied_total", []),
+        3
+    );
+    assert_eq!(
+        handle.get_counter_value_by_label("sync_reorgs_total", []),
+        1
+    );
+    assert_eq!(
+        handle.get_counter_value_by_label("sync_classes_inserted_total", [("kind", "cairo")]),
+        1
+    );
+}
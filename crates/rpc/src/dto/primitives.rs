@@ -857,6 +857,9 @@ mod tests {
                 number: pathfinder_common::BlockNumber::GENESIS + 300,
                 hash: block_hash!("0x789"),
             },
+            // Pathfinder specific, so not part of the reply.
+            current_block_time_avg: Some(std::time::Duration::from_millis(1500)),
+            l1_l2_confirmed_block: Some(pathfinder_common::BlockNumber::GENESIS),
         };
 
         let s = Serializer::default();
@@ -818,7 +818,6 @@ mod tests {
         SubscriptionMessage,
     };
     use crate::pending::PendingWatcher;
-    use crate::types::syncing::Syncing;
     use crate::{Notifications, SyncState};
 
     #[tokio::test]
@@ -1012,10 +1011,7 @@ mod tests {
             storage,
             execution_storage: StorageBuilder::in_memory().unwrap(),
             pending_data: PendingWatcher::new(pending_data),
-            sync_status: SyncState {
-                status: Syncing::False.into(),
-            }
-            .into(),
+            sync_status: SyncState::default().into(),
             chain_id: ChainId::MAINNET,
             contract_addresses: EthContractAddresses::new_known(
                 pathfinder_ethereum::core_addr::MAINNET,
@@ -16,8 +16,9 @@ pub mod v07;
 pub mod v08;
 
 use std::net::SocketAddr;
+use std::num::NonZeroUsize;
 use std::result::Result;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 
 use anyhow::Context;
 use axum::error_handling::HandleErrorLayer;
@@ -37,7 +38,7 @@ use tower_http::ServiceBuilderExt;
 use crate::jsonrpc::rpc_handler;
 use crate::jsonrpc::websocket::websocket_handler;
 pub use crate::jsonrpc::websocket::{BlockHeader, TopicBroadcasters};
-use crate::types::syncing::Syncing;
+use crate::types::syncing::{SyncHealth, SyncTask, Syncing, TaskHealth};
 
 const DEFAULT_MAX_CONNECTIONS: usize = 1024;
 
@@ -221,14 +222,131 @@ impl RpcServer {
 
 pub struct SyncState {
     pub status: RwLock<Syncing>,
+    /// Publishes every change made to [status](Self::status) by sync.
+    ///
+    /// This is a bounded broadcast channel: receivers lagging behind by more
+    /// than its capacity drop the intermediate updates and observe
+    /// [RecvError::Lagged](tokio::sync::broadcast::error::RecvError::Lagged)
+    /// before resuming with the oldest update still retained.
+    pub updates: tokio::sync::broadcast::Sender<Syncing>,
+    tasks: std::sync::Mutex<[TaskState; 2]>,
+    created: tokio::time::Instant,
+    /// While set, sync stops processing events, see [pause](Self::pause).
+    paused: AtomicBool,
+    resumed: tokio::sync::Notify,
 }
 
-impl Default for SyncState {
-    fn default() -> Self {
+#[derive(Default, Clone, Copy)]
+struct TaskState {
+    last_event: Option<tokio::time::Instant>,
+    alive: bool,
+    consecutive_failures: u32,
+}
+
+impl SyncState {
+    pub fn with_updates_capacity(capacity: NonZeroUsize) -> Self {
         Self {
             status: RwLock::new(Syncing::False),
+            updates: tokio::sync::broadcast::channel(capacity.get()).0,
+            tasks: Default::default(),
+            created: tokio::time::Instant::now(),
+            paused: AtomicBool::new(false),
+            resumed: tokio::sync::Notify::new(),
         }
     }
+
+    /// Pauses sync once it has finished processing the current event. Events
+    /// arriving in the meantime are held back, until the bounded channels
+    /// stall the tasks producing them.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Resumes a [paused](Self::pause) sync.
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+        self.resumed.notify_waiters();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Returns once sync is not [paused](Self::pause).
+    pub async fn wait_while_paused(&self) {
+        loop {
+            // Created before checking the flag so that a concurrent resume is not missed.
+            let resumed = self.resumed.notified();
+            if !self.is_paused() {
+                return;
+            }
+            resumed.await;
+        }
+    }
+
+    /// Subscribes to changes of the sync status. See [updates](Self::updates).
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<Syncing> {
+        self.updates.subscribe()
+    }
+
+    /// Records that `task` was (re)started.
+    pub fn record_task_started(&self, task: SyncTask) {
+        self.update_task(task, |state| state.alive = true);
+    }
+
+    /// Records that `task` failed and awaits a restart, returning the number
+    /// of times it failed since it last emitted an event.
+    pub fn record_task_failed(&self, task: SyncTask) -> u32 {
+        self.update_task(task, |state| {
+            state.alive = false;
+            state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+            state.consecutive_failures
+        })
+    }
+
+    /// Records that `task` emitted an event.
+    pub fn record_task_event(&self, task: SyncTask) {
+        self.update_task(task, |state| {
+            state.last_event = Some(tokio::time::Instant::now());
+            state.consecutive_failures = 0;
+        });
+    }
+
+    fn update_task<T>(&self, task: SyncTask, f: impl FnOnce(&mut TaskState) -> T) -> T {
+        let mut tasks = self.tasks.lock().unwrap();
+        f(&mut tasks[task as usize])
+    }
+
+    /// Returns a snapshot of the health of sync.
+    pub async fn health(&self) -> SyncHealth {
+        let (current_block, highest_block) = match &*self.status.read().await {
+            Syncing::False => (None, None),
+            Syncing::Status(status) => (Some(status.current.number), Some(status.highest.number)),
+        };
+
+        let tasks = *self.tasks.lock().unwrap();
+        let task_health = |task: SyncTask| {
+            let state = tasks[task as usize];
+            TaskHealth {
+                since_last_event: state.last_event.unwrap_or(self.created).elapsed(),
+                alive: state.alive,
+                consecutive_failures: state.consecutive_failures,
+            }
+        };
+
+        SyncHealth {
+            l1: task_health(SyncTask::L1),
+            l2: task_health(SyncTask::L2),
+            current_block,
+            highest_block,
+        }
+    }
+}
+
+impl Default for SyncState {
+    fn default() -> Self {
+        Self::with_updates_capacity(NonZeroUsize::new(100).unwrap())
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize)]
@@ -845,6 +963,8 @@ mod tests {
                     starting: NumberedBlock::from(("a", 1)),
                     current: NumberedBlock::from(("b", 2)),
                     highest: NumberedBlock::from(("c", 3)),
+                    current_block_time_avg: None,
+                    l1_l2_confirmed_block: None,
                 }),
             ),
         ];
@@ -859,6 +979,37 @@ mod tests {
         }
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn health_reflects_stale_l2_task() {
+        let state = SyncState::default();
+        state.record_task_started(SyncTask::L1);
+        state.record_task_started(SyncTask::L2);
+        state.record_task_event(SyncTask::L2);
+        state.record_task_failed(SyncTask::L2);
+
+        tokio::time::advance(std::time::Duration::from_secs(60)).await;
+        state.record_task_event(SyncTask::L1);
+
+        let health = state.health().await;
+        assert_eq!(
+            health,
+            SyncHealth {
+                l1: TaskHealth {
+                    since_last_event: std::time::Duration::ZERO,
+                    alive: true,
+                    consecutive_failures: 0,
+                },
+                l2: TaskHealth {
+                    since_last_event: std::time::Duration::from_secs(60),
+                    alive: false,
+                    consecutive_failures: 1,
+                },
+                current_block: None,
+                highest_block: None,
+            }
+        );
+    }
+
     #[tokio::test]
     async fn empty_get_on_root_is_ok() {
         // Monitoring bots often get query `/` with no body as a form
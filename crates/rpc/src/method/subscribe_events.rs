@@ -259,7 +259,6 @@ mod tests {
     use crate::jsonrpc::{handle_json_rpc_socket, RpcRouter, RpcSubscriptionFlow};
     use crate::method::subscribe_events::SubscribeEvents;
     use crate::pending::PendingWatcher;
-    use crate::types::syncing::Syncing;
     use crate::{v08, Notifications, Reorg, SyncState};
 
     #[tokio::test]
@@ -725,10 +724,7 @@ mod tests {
             storage,
             execution_storage: StorageBuilder::in_memory().unwrap(),
             pending_data: PendingWatcher::new(pending_data),
-            sync_status: SyncState {
-                status: Syncing::False.into(),
-            }
-            .into(),
+            sync_status: SyncState::default().into(),
             chain_id: ChainId::MAINNET,
             contract_addresses: EthContractAddresses::new_known(
                 pathfinder_ethereum::core_addr::MAINNET,
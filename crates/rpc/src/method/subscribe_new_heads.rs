@@ -179,7 +179,6 @@ mod tests {
     use crate::context::{EthContractAddresses, RpcConfig, RpcContext};
     use crate::jsonrpc::{handle_json_rpc_socket, RpcResponse, RpcRouter};
     use crate::pending::PendingWatcher;
-    use crate::types::syncing::Syncing;
     use crate::{v08, Notifications, Reorg, SubscriptionId, SyncState};
 
     #[tokio::test]
@@ -533,10 +532,7 @@ mod tests {
             storage,
             execution_storage: StorageBuilder::in_memory().unwrap(),
             pending_data: PendingWatcher::new(pending_data),
-            sync_status: SyncState {
-                status: Syncing::False.into(),
-            }
-            .into(),
+            sync_status: SyncState::default().into(),
             chain_id: ChainId::MAINNET,
             contract_addresses: EthContractAddresses::new_known(
                 pathfinder_ethereum::core_addr::MAINNET,
@@ -142,11 +142,13 @@ impl RpcSubscriptionFlow for SubscribeTransactionStatus {
             let storage = state.storage.clone();
             // Check if we have the transaction in our database, and if so, send the
             // relevant transaction status updates.
-            let (l1_state, tx_with_receipt) =
+            let (l1_block_number, tx_with_receipt) =
                 util::task::spawn_blocking(move |_| -> Result<_, RpcError> {
                     let mut conn = storage.connection().map_err(RpcError::InternalError)?;
                     let db = conn.transaction().map_err(RpcError::InternalError)?;
-                    let l1_block_number = db.latest_l1_state().map_err(RpcError::InternalError)?;
+                    let l1_block_number = db
+                        .latest_l1_block_number()
+                        .map_err(RpcError::InternalError)?;
                     let tx_with_receipt = db
                         .transaction_with_receipt(tx_hash)
                         .map_err(RpcError::InternalError)?;
@@ -179,11 +181,11 @@ impl RpcSubscriptionFlow for SubscribeTransactionStatus {
                     // Subscription closing.
                     break;
                 }
-                if let Some(l1_state) = l1_state {
-                    if l1_state.block_number >= block_number {
+                if let Some(l1_block_number) = l1_block_number {
+                    if l1_block_number >= block_number {
                         if sender
                             .send(
-                                l1_state.block_number,
+                                l1_block_number,
                                 FinalityStatus::AcceptedOnL1,
                                 Some(receipt.execution_status.clone()),
                             )
@@ -323,17 +325,17 @@ impl RpcSubscriptionFlow for SubscribeTransactionStatus {
                                 // here because it guarantees that the ACCEPTED_ON_L2 update will be
                                 // sent before the ACCEPTED_ON_L1 update.
                                 let storage = state.storage.clone();
-                                let l1_state = util::task::spawn_blocking(move |_| -> Result<_, RpcError> {
+                                let l1_block_number = util::task::spawn_blocking(move |_| -> Result<_, RpcError> {
                                     let mut conn = storage.connection().map_err(RpcError::InternalError)?;
                                     let db = conn.transaction().map_err(RpcError::InternalError)?;
-                                    let l1_state = db.latest_l1_state().map_err(RpcError::InternalError)?;
-                                    Ok(l1_state)
+                                    let l1_block_number = db.latest_l1_block_number().map_err(RpcError::InternalError)?;
+                                    Ok(l1_block_number)
                                 }).await.map_err(|e| RpcError::InternalError(e.into()))??;
-                                if let Some(l1_state) = l1_state {
-                                    if l1_state.block_number >= sender.last_block_number && sender.last_execution_status.is_some() {
+                                if let Some(l1_block_number) = l1_block_number {
+                                    if l1_block_number >= sender.last_block_number && sender.last_execution_status.is_some() {
                                         if sender
                                             .send(
-                                                l1_state.block_number,
+                                                l1_block_number,
                                                 FinalityStatus::AcceptedOnL1,
                                                 sender.last_execution_status.clone(),
                                             )
@@ -440,7 +442,6 @@ mod tests {
     use crate::dto::{SerializeForVersion, Serializer};
     use crate::jsonrpc::{handle_json_rpc_socket, RpcResponse, RpcRouter};
     use crate::pending::PendingWatcher;
-    use crate::types::syncing::Syncing;
     use crate::{v08, Notifications, PendingData, Reorg, RpcVersion, SubscriptionId, SyncState};
 
     #[tokio::test]
@@ -1139,10 +1140,7 @@ mod tests {
             storage,
             execution_storage: StorageBuilder::in_memory().unwrap(),
             pending_data: PendingWatcher::new(pending_data),
-            sync_status: SyncState {
-                status: Syncing::False.into(),
-            }
-            .into(),
+            sync_status: SyncState::default().into(),
             chain_id: ChainId::MAINNET,
             contract_addresses: EthContractAddresses::new_known(
                 pathfinder_ethereum::core_addr::MAINNET,
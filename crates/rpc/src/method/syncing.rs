@@ -67,6 +67,8 @@ mod tests {
                 hash: block_hash!("0xaaaa"),
                 number: BlockNumber::new_or_panic(0),
             },
+            current_block_time_avg: None,
+            l1_l2_confirmed_block: None,
         });
 
         assert_eq!(syncing(context).await.unwrap().0, Syncing::False);
@@ -89,6 +91,8 @@ mod tests {
                 hash: block_hash!("0xcccc"),
                 number: BlockNumber::new_or_panic(10),
             },
+            current_block_time_avg: None,
+            l1_l2_confirmed_block: None,
         };
 
         *context.sync_status.status.write().await = Syncing::Status(status);
@@ -9,4 +9,6 @@ pub fn register_routes() -> RpcRouterBuilder {
         .register("pathfinder_getProof",             methods::get_proof)
         .register("pathfinder_getClassProof",        methods::get_class_proof)
         .register("pathfinder_getTransactionStatus", methods::get_transaction_status)
+        .register("pathfinder_getL1L2Head",          methods::get_l1_l2_head)
+        .register("pathfinder_getBlockTimeAverage",  methods::get_block_time_average)
 }
@@ -1,5 +1,9 @@
+mod get_block_time_average;
+mod get_l1_l2_head;
 mod get_proof;
 mod get_transaction_status;
 
+pub(crate) use get_block_time_average::get_block_time_average;
+pub(crate) use get_l1_l2_head::get_l1_l2_head;
 pub(crate) use get_proof::{get_class_proof, get_proof};
 pub(crate) use get_transaction_status::get_transaction_status;
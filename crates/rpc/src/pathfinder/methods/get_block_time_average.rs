@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use crate::context::RpcContext;
+use crate::types::syncing::Syncing;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Output(Option<Duration>);
+
+crate::error::generate_rpc_error_subset!(Error:);
+
+/// Get the moving average of the time sync takes to process a block. Returns
+/// `null` if no block has been processed yet.
+pub async fn get_block_time_average(context: RpcContext) -> Result<Output, Error> {
+    let average = match &*context.sync_status.status.read().await {
+        Syncing::False => None,
+        Syncing::Status(status) => status.current_block_time_avg,
+    };
+
+    Ok(Output(average))
+}
+
+impl crate::dto::SerializeForVersion for Output {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        match &self.0 {
+            Some(average) => {
+                serializer.serialize_u64(u64::try_from(average.as_millis()).unwrap_or(u64::MAX))
+            }
+            None => serializer.serialize_unit(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::{block_hash, BlockNumber};
+
+    use super::*;
+    use crate::types::syncing::{NumberedBlock, Status};
+
+    #[tokio::test]
+    async fn not_syncing() {
+        let context = RpcContext::for_tests();
+        *context.sync_status.status.write().await = Syncing::False;
+
+        let average = get_block_time_average(context).await.unwrap();
+
+        assert_eq!(average, Output(None));
+    }
+
+    #[tokio::test]
+    async fn syncing() {
+        let context = RpcContext::for_tests();
+        let block = NumberedBlock {
+            hash: block_hash!("0xaaaa"),
+            number: BlockNumber::GENESIS,
+        };
+        *context.sync_status.status.write().await = Syncing::Status(Status {
+            starting: block,
+            current: block,
+            highest: block,
+            current_block_time_avg: Some(Duration::from_millis(1500)),
+            l1_l2_confirmed_block: None,
+        });
+
+        let average = get_block_time_average(context).await.unwrap();
+
+        assert_eq!(average, Output(Some(Duration::from_millis(1500))));
+    }
+}
@@ -0,0 +1,73 @@
+use anyhow::Context;
+use pathfinder_common::BlockNumber;
+
+use crate::context::RpcContext;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Output(Option<BlockNumber>);
+
+crate::error::generate_rpc_error_subset!(Error:);
+
+/// Get the highest block that is part of the canonical chain on both L1 and
+/// L2. Returns `null` if no such block has been seen yet.
+pub async fn get_l1_l2_head(context: RpcContext) -> Result<Output, Error> {
+    let span = tracing::Span::current();
+    let jh = util::task::spawn_blocking(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Opening database transaction")?;
+
+        let head = tx
+            .l1_l2_pointer()
+            .context("Reading L1-L2 head from database")?;
+
+        anyhow::Ok(Output(head))
+    });
+
+    let head = jh.await.context("Database read panic or shutting down")??;
+
+    Ok(head)
+}
+
+impl crate::dto::SerializeForVersion for Output {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        match &self.0 {
+            Some(block_number) => serializer.serialize(block_number),
+            None => serializer.serialize_unit(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn genesis() {
+        // The test database has the genesis block confirmed on L1.
+        let context = RpcContext::for_tests();
+
+        let head = get_l1_l2_head(context).await.unwrap();
+
+        assert_eq!(head, Output(Some(BlockNumber::GENESIS)));
+    }
+
+    #[tokio::test]
+    async fn unset() {
+        let context = RpcContext::for_tests();
+        let mut db = context.storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        tx.update_l1_l2_pointer(None).unwrap();
+        tx.commit().unwrap();
+
+        let head = get_l1_l2_head(context).await.unwrap();
+
+        assert_eq!(head, Output(None));
+    }
+}
@@ -52,6 +52,14 @@ pub struct Status {
     pub starting: NumberedBlock,
     pub current: NumberedBlock,
     pub highest: NumberedBlock,
+    /// Moving average of the time it takes to process a block, if any blocks
+    /// have been processed yet. Not part of the `starknet_syncing` reply, see
+    /// `pathfinder_getBlockTimeAverage` instead.
+    pub current_block_time_avg: Option<std::time::Duration>,
+    /// The latest block whose hash has been confirmed on Ethereum, if any. Not
+    /// part of the `starknet_syncing` reply, see `pathfinder_getL1L2Head`
+    /// instead.
+    pub l1_l2_confirmed_block: Option<BlockNumber>,
 }
 
 impl std::fmt::Display for Status {
@@ -102,6 +110,8 @@ impl crate::dto::DeserializeForVersion for Status {
                         .deserialize::<U64Hex>("highest_block_num")
                         .map(|num| BlockNumber::new_or_panic(num.0))?,
                 },
+                current_block_time_avg: None,
+                l1_l2_confirmed_block: None,
             })
         })
     }
@@ -142,3 +152,35 @@ impl<'a> From<(&'a str, u64)> for NumberedBlock {
         }
     }
 }
+
+/// The sync tasks tracked by [SyncState::health](crate::SyncState::health).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncTask {
+    L1,
+    L2,
+}
+
+/// A snapshot of the health of sync, see
+/// [SyncState::health](crate::SyncState::health).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SyncHealth {
+    pub l1: TaskHealth,
+    pub l2: TaskHealth,
+    /// The latest block stored by sync. `None` until sync has started.
+    pub current_block: Option<BlockNumber>,
+    /// The latest block known to the sequencer. `None` until sync has started.
+    pub highest_block: Option<BlockNumber>,
+}
+
+/// Health of the L1 or L2 sync task.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TaskHealth {
+    /// Time since the task last emitted an event, or since sync was set up if
+    /// it hasn't emitted any yet.
+    pub since_last_event: std::time::Duration,
+    /// Whether the task is running, as opposed to waiting to be restarted
+    /// after a failure.
+    pub alive: bool,
+    /// Number of failures since the task last emitted an event.
+    pub consecutive_failures: u32,
+}
@@ -34,5 +34,7 @@ pub fn register_routes() -> RpcRouterBuilder {
         .register("starknet_traceTransaction",                    crate::method::trace_transaction)
         .register("starknet_getBlockWithReceipts",                crate::method::get_block_with_receipts)
         .register("pathfinder_getProof",                          crate::pathfinder::methods::get_proof)
+        .register("pathfinder_getL1L2Head",                       crate::pathfinder::methods::get_l1_l2_head)
+        .register("pathfinder_getBlockTimeAverage",               crate::pathfinder::methods::get_block_time_average)
         .register("starknet_specVersion",                         || "0.7.1")
 }
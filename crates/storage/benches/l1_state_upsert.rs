@@ -0,0 +1,55 @@
+//! Compares writing a burst of L1 state updates with one database transaction
+//! per update against writing them all in a single transaction.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use pathfinder_common::{BlockHash, BlockNumber, StateCommitment};
+use pathfinder_crypto::Felt;
+use pathfinder_ethereum::EthereumStateUpdate;
+use pathfinder_storage::StorageBuilder;
+
+/// Number of L1 state updates written per iteration.
+const UPDATES: u64 = 10_000;
+
+fn updates(offset: u64) -> Vec<EthereumStateUpdate> {
+    (offset..offset + UPDATES)
+        .map(|i| EthereumStateUpdate {
+            state_root: StateCommitment(Felt::from_u64(i)),
+            block_number: BlockNumber::new_or_panic(i),
+            block_hash: BlockHash(Felt::from_u64(i)),
+        })
+        .collect()
+}
+
+pub fn bench_l1_state_upsert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("upsert_l1_state");
+    group.sample_size(10);
+
+    let storage = StorageBuilder::in_tempdir().unwrap();
+    let mut connection = storage.connection().unwrap();
+    let mut offset = 0;
+
+    group.bench_function(BenchmarkId::new("transactions", "one_per_update"), |b| {
+        b.iter(|| {
+            for update in updates(offset) {
+                let tx = connection.transaction().unwrap();
+                tx.upsert_l1_state(&update).unwrap();
+                tx.commit().unwrap();
+            }
+            offset += UPDATES;
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("transactions", "single"), |b| {
+        b.iter(|| {
+            let tx = connection.transaction().unwrap();
+            tx.upsert_l1_states(&updates(offset)).unwrap();
+            tx.commit().unwrap();
+            offset += UPDATES;
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_l1_state_upsert);
+criterion_main!(benches);
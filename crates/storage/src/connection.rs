@@ -1,6 +1,8 @@
+use std::cell::{Cell, RefCell};
 use std::sync::{Arc, Mutex};
 
 mod block;
+mod block_timings;
 mod class;
 mod ethereum;
 pub mod event;
@@ -11,6 +13,9 @@ mod state_update;
 pub(crate) mod transaction;
 mod trie;
 
+pub use block::{BlockHeaders, ChainGap};
+pub use block_timings::BlockTimings;
+pub use class::compress_class_definition;
 use event::RunningEventFilter;
 pub use event::{
     EmittedEvent,
@@ -26,6 +31,7 @@ use pathfinder_common::{BlockNumber, TransactionHash};
 pub(crate) use reorg_counter::ReorgCounter;
 // Re-export this so users don't require rusqlite as a direct dep.
 pub use rusqlite::TransactionBehavior;
+pub(crate) use trie::TrieNodeCache;
 pub use trie::{Node, NodeRef, RootIndexUpdate, StoredNode, TrieUpdate};
 
 use crate::bloom::AggregateBloomCache;
@@ -36,6 +42,7 @@ pub struct Connection {
     connection: PooledConnection,
     event_filter_cache: Arc<AggregateBloomCache>,
     running_event_filter: Arc<Mutex<RunningEventFilter>>,
+    trie_node_cache: Arc<TrieNodeCache>,
     trie_prune_mode: TriePruneMode,
 }
 
@@ -44,22 +51,29 @@ impl Connection {
         connection: PooledConnection,
         event_filter_cache: Arc<AggregateBloomCache>,
         running_event_filter: Arc<Mutex<RunningEventFilter>>,
+        trie_node_cache: Arc<TrieNodeCache>,
         trie_prune_mode: TriePruneMode,
     ) -> Self {
         Self {
             connection,
             event_filter_cache,
             running_event_filter,
+            trie_node_cache,
             trie_prune_mode,
         }
     }
 
     pub fn transaction(&mut self) -> anyhow::Result<Transaction<'_>> {
+        let trie_node_cache_generation = self.trie_node_cache.generation();
         let tx = self.connection.transaction()?;
         Ok(Transaction {
             transaction: tx,
             event_filter_cache: self.event_filter_cache.clone(),
             running_event_filter: self.running_event_filter.clone(),
+            trie_node_cache: self.trie_node_cache.clone(),
+            trie_node_cache_generation,
+            inserted_storage_trie_nodes: Cell::new(false),
+            stale_storage_trie_nodes: RefCell::default(),
             trie_prune_mode: self.trie_prune_mode,
         })
     }
@@ -68,11 +82,16 @@ impl Connection {
         &mut self,
         behavior: TransactionBehavior,
     ) -> anyhow::Result<Transaction<'_>> {
+        let trie_node_cache_generation = self.trie_node_cache.generation();
         let tx = self.connection.transaction_with_behavior(behavior)?;
         Ok(Transaction {
             transaction: tx,
             event_filter_cache: self.event_filter_cache.clone(),
             running_event_filter: self.running_event_filter.clone(),
+            trie_node_cache: self.trie_node_cache.clone(),
+            trie_node_cache_generation,
+            inserted_storage_trie_nodes: Cell::new(false),
+            stale_storage_trie_nodes: RefCell::default(),
             trie_prune_mode: self.trie_prune_mode,
         })
     }
@@ -82,6 +101,17 @@ pub struct Transaction<'inner> {
     transaction: rusqlite::Transaction<'inner>,
     event_filter_cache: Arc<AggregateBloomCache>,
     running_event_filter: Arc<Mutex<RunningEventFilter>>,
+    trie_node_cache: Arc<TrieNodeCache>,
+    /// The [`TrieNodeCache::generation`] from before this transaction took its
+    /// snapshot.
+    trie_node_cache_generation: u64,
+    /// Whether this transaction has stored storage trie nodes. Their indices
+    /// may be reused if the transaction is rolled back, so nodes read after
+    /// that point are not cached.
+    inserted_storage_trie_nodes: Cell<bool>,
+    /// Indices of the storage trie nodes this transaction pruned or stored,
+    /// evicted from the [`TrieNodeCache`] on commit.
+    stale_storage_trie_nodes: RefCell<Vec<u64>>,
     trie_prune_mode: TriePruneMode,
 }
 
@@ -110,7 +140,14 @@ impl Transaction<'_> {
     }
 
     pub fn commit(self) -> anyhow::Result<()> {
-        Ok(self.transaction.commit()?)
+        self.transaction.commit()?;
+
+        let stale = self.stale_storage_trie_nodes.into_inner();
+        if !stale.is_empty() {
+            self.trie_node_cache.remove(&stale);
+        }
+
+        Ok(())
     }
 
     pub fn trie_pruning_enabled(&self) -> bool {
@@ -121,6 +158,7 @@ impl Transaction<'_> {
     pub fn reset(&self) -> anyhow::Result<()> {
         self.rebuild_running_event_filter()?;
         self.event_filter_cache.reset();
+        self.trie_node_cache.reset();
 
         Ok(())
     }
@@ -1,21 +1,90 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::num::NonZeroUsize;
+use std::rc::Rc;
 
 use anyhow::Context;
 use pathfinder_common::{
     BlockHash,
     BlockHeader,
     BlockNumber,
+    BlockTimestamp,
     GasPrice,
     StarknetVersion,
     StateCommitment,
     StateDiffCommitment,
     TransactionCommitment,
 };
+use rusqlite::types::Value;
 
 use crate::prelude::*;
 use crate::BlockId;
 
+/// The first break found by [`Transaction::verify_block_chain`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum ChainGap {
+    #[error("block {0} is missing")]
+    Missing(BlockNumber),
+    #[error("parent hash of block {block} is {actual} but the hash of its parent is {expected}")]
+    ParentHashMismatch {
+        block: BlockNumber,
+        expected: BlockHash,
+        actual: BlockHash,
+    },
+}
+
+/// Iterator returned by [`Transaction::block_headers`].
+pub struct BlockHeaders<'a> {
+    tx: &'a Transaction<'a>,
+    /// The first block of the next batch, or `None` once the range is
+    /// exhausted.
+    next: Option<BlockNumber>,
+    to: BlockNumber,
+    batch: VecDeque<BlockHeader>,
+}
+
+impl BlockHeaders<'_> {
+    const BATCH_SIZE: usize = 1000;
+
+    fn fetch_batch(&self, from: BlockNumber) -> anyhow::Result<VecDeque<BlockHeader>> {
+        let mut stmt = self
+            .tx
+            .inner()
+            .prepare_cached(
+                "SELECT * FROM block_headers WHERE number >= ? AND number <= ? ORDER BY number \
+                 ASC LIMIT ?",
+            )
+            .context("Preparing block header query")?;
+        let mut rows = stmt
+            .query(params![&from, &self.to, &Self::BATCH_SIZE])
+            .context("Querying block headers")?;
+
+        let mut batch = VecDeque::with_capacity(Self::BATCH_SIZE);
+        while let Some(row) = rows.next()? {
+            batch.push_back(parse_row_as_header(row)?);
+        }
+        Ok(batch)
+    }
+}
+
+impl Iterator for BlockHeaders<'_> {
+    type Item = anyhow::Result<BlockHeader>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.batch.is_empty() {
+            let from = self.next.take().filter(|from| *from <= self.to)?;
+            match self.fetch_batch(from) {
+                Ok(batch) => self.batch = batch,
+                Err(error) => return Some(Err(error)),
+            }
+            if self.batch.len() == Self::BATCH_SIZE {
+                self.next = self.batch.back().map(|header| header.number + 1);
+            }
+        }
+
+        self.batch.pop_front().map(Ok)
+    }
+}
+
 impl Transaction<'_> {
     pub fn insert_block_header(&self, header: &BlockHeader) -> anyhow::Result<()> {
         // Insert the header
@@ -269,6 +338,37 @@ impl Transaction<'_> {
         }
     }
 
+    /// Hashes of the canonical blocks with the given `numbers`, looked up with
+    /// a single query. The result is aligned with `numbers`, missing blocks
+    /// being `None`.
+    pub fn block_hashes(&self, numbers: &[BlockNumber]) -> anyhow::Result<Vec<Option<BlockHash>>> {
+        let numbers_rarray = Rc::new(
+            numbers
+                .iter()
+                .map(|number| i64::try_from(number.get()).map(Value::from))
+                .collect::<Result<Vec<_>, _>>()
+                .context("Block number out of range")?,
+        );
+
+        let mut stmt = self
+            .inner()
+            .prepare_cached("SELECT number, hash FROM canonical_blocks WHERE number IN rarray(?)")
+            .context("Preparing statement")?;
+        let hashes = stmt
+            // Cannot use crate::params::params![] here because of the rarray.
+            .query_map(rusqlite::params![&numbers_rarray], |row| {
+                Ok((row.get_block_number(0)?, row.get_block_hash(1)?))
+            })
+            .context("Querying block hashes")?
+            .collect::<Result<HashMap<_, _>, _>>()
+            .context("Iterating over block hashes")?;
+
+        Ok(numbers
+            .iter()
+            .map(|number| hashes.get(number).copied())
+            .collect())
+    }
+
     pub fn block_number(&self, block: BlockId) -> anyhow::Result<Option<BlockNumber>> {
         match block {
             BlockId::Latest => self
@@ -336,6 +436,30 @@ impl Transaction<'_> {
             .map_err(|e| e.into())
     }
 
+    /// Returns the block's timestamp without loading the rest of the header.
+    pub fn block_timestamp(&self, block: BlockId) -> anyhow::Result<Option<BlockTimestamp>> {
+        let sql = match block {
+            BlockId::Latest => "SELECT timestamp FROM block_headers ORDER BY number DESC LIMIT 1",
+            BlockId::Number(_) => "SELECT timestamp FROM block_headers WHERE number = ?",
+            BlockId::Hash(_) => "SELECT timestamp FROM block_headers WHERE hash = ?",
+        };
+
+        let mut stmt = self
+            .inner()
+            .prepare_cached(sql)
+            .context("Preparing block timestamp query")?;
+
+        let timestamp = match block {
+            BlockId::Latest => stmt.query_row([], |row| row.get_timestamp(0)),
+            BlockId::Number(number) => stmt.query_row(params![&number], |row| row.get_timestamp(0)),
+            BlockId::Hash(hash) => stmt.query_row(params![&hash], |row| row.get_timestamp(0)),
+        }
+        .optional()
+        .context("Querying for block timestamp")?;
+
+        Ok(timestamp)
+    }
+
     pub fn block_header(&self, block: BlockId) -> anyhow::Result<Option<BlockHeader>> {
         let sql = match block {
             BlockId::Latest => "SELECT * FROM block_headers ORDER BY number DESC LIMIT 1",
@@ -380,6 +504,94 @@ impl Transaction<'_> {
         Ok(headers)
     }
 
+    /// Iterates over the block headers in a range, inclusive on both ends, in
+    /// ascending order. Headers are read in batches, so memory use does not
+    /// grow with the size of the range.
+    pub fn block_headers(&self, from: BlockNumber, to: BlockNumber) -> BlockHeaders<'_> {
+        BlockHeaders {
+            tx: self,
+            next: Some(from),
+            to,
+            batch: VecDeque::new(),
+        }
+    }
+
+    /// Checks that the blocks in the range, inclusive on both ends, are all
+    /// present and that each one's parent hash is the hash of the block before
+    /// it. Returns the first [gap](ChainGap) found, if any.
+    pub fn verify_block_chain(
+        &self,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> anyhow::Result<Option<ChainGap>> {
+        let mut stmt = self
+            .inner()
+            .prepare_cached(
+                "SELECT number, hash, parent_hash FROM block_headers WHERE number >= ? AND number \
+                 <= ? ORDER BY number ASC",
+            )
+            .context("Preparing block hash chain query")?;
+        let mut rows = stmt
+            .query(params![&from, &to])
+            .context("Querying block hash chain")?;
+
+        let mut expected_number = from;
+        let mut previous_hash = None;
+        while let Some(row) = rows.next()? {
+            let number = row.get_block_number(0)?;
+            let hash = row.get_block_hash(1)?;
+            let parent_hash = row.get_block_hash(2)?;
+
+            if number != expected_number {
+                return Ok(Some(ChainGap::Missing(expected_number)));
+            }
+
+            match previous_hash {
+                Some(expected) if expected != parent_hash => {
+                    return Ok(Some(ChainGap::ParentHashMismatch {
+                        block: number,
+                        expected,
+                        actual: parent_hash,
+                    }));
+                }
+                _ => {}
+            }
+
+            expected_number += 1;
+            previous_hash = Some(hash);
+        }
+
+        if expected_number <= to {
+            return Ok(Some(ChainGap::Missing(expected_number)));
+        }
+
+        Ok(None)
+    }
+
+    /// Return all block headers with a timestamp within the range, inclusive
+    /// on both ends. An inverted range yields no headers.
+    pub fn block_headers_by_timestamp_range(
+        &self,
+        from: BlockTimestamp,
+        to: BlockTimestamp,
+    ) -> anyhow::Result<Vec<BlockHeader>> {
+        let sql = "SELECT * FROM block_headers WHERE timestamp >= $1 AND timestamp <= $2 ORDER BY \
+                   number ASC";
+        let mut stmt = self
+            .inner()
+            .prepare_cached(sql)
+            .context("Preparing block header query")?;
+        let mut headers = Vec::new();
+        let mut rows = stmt
+            .query(params![&from, &to])
+            .context("Querying block headers by timestamp")?;
+        while let Some(row) = rows.next()? {
+            let header = parse_row_as_header(row)?;
+            headers.push(header);
+        }
+        Ok(headers)
+    }
+
     pub fn state_commitment(&self, block: BlockId) -> anyhow::Result<Option<StateCommitment>> {
         let sql = match block {
             BlockId::Latest => {
@@ -646,7 +858,7 @@ mod tests {
     use rstest::rstest;
 
     use super::*;
-    use crate::{Connection, StorageBuilder};
+    use crate::{Connection, RootIndexUpdate, StorageBuilder};
 
     // Create test database filled with block headers.
     fn setup() -> (Connection, Vec<BlockHeader>) {
@@ -771,6 +983,32 @@ mod tests {
         assert_eq!(result, None);
     }
 
+    #[test]
+    fn block_hashes() {
+        let (mut connection, headers) = setup();
+        let tx = connection.transaction().unwrap();
+
+        let past_head = headers.last().unwrap().number + 1;
+        let numbers = [
+            headers[2].number,
+            past_head,
+            headers[0].number,
+            headers[2].number,
+        ];
+        let result = tx.block_hashes(&numbers).unwrap();
+        assert_eq!(
+            result,
+            vec![
+                Some(headers[2].hash),
+                None,
+                Some(headers[0].hash),
+                Some(headers[2].hash),
+            ]
+        );
+
+        assert_eq!(tx.block_hashes(&[]).unwrap(), vec![]);
+    }
+
     #[test]
     fn purge_block() {
         let (mut connection, headers) = setup();
@@ -797,6 +1035,32 @@ mod tests {
         assert_eq!(class_exists, None);
     }
 
+    #[test]
+    fn purge_block_removes_contract_state() {
+        let (mut connection, headers) = setup();
+        let tx = connection.transaction().unwrap();
+        let latest = headers.last().unwrap();
+        let parent = latest.number.parent().unwrap();
+
+        let contract = contract_address!("0x1234");
+        tx.insert_contract_state_hash(parent, contract, contract_state_hash!("0x1"))
+            .unwrap();
+        tx.insert_contract_root(parent, contract, RootIndexUpdate::Updated(1))
+            .unwrap();
+        tx.insert_contract_state_hash(latest.number, contract, contract_state_hash!("0x2"))
+            .unwrap();
+        tx.insert_contract_root(latest.number, contract, RootIndexUpdate::Updated(2))
+            .unwrap();
+
+        tx.purge_block(latest.number).unwrap();
+
+        // Lookups at the purged block must fall back to the parent's state.
+        let state_hash = tx.contract_state_hash(latest.number, contract).unwrap();
+        assert_eq!(state_hash, Some(contract_state_hash!("0x1")));
+        let root_index = tx.contract_root_index(latest.number, contract).unwrap();
+        assert_eq!(root_index, Some(1));
+    }
+
     #[test]
     fn block_id() {
         let (mut connection, headers) = setup();
@@ -812,6 +1076,136 @@ mod tests {
         assert_eq!(by_hash, expected);
     }
 
+    #[test]
+    fn block_timestamp() {
+        let (mut connection, headers) = setup();
+        let tx = connection.transaction().unwrap();
+
+        let target = &headers[1];
+        let expected = Some(target.timestamp);
+
+        let by_number = tx.block_timestamp(target.number.into()).unwrap();
+        assert_eq!(by_number, expected);
+
+        let by_hash = tx.block_timestamp(target.hash.into()).unwrap();
+        assert_eq!(by_hash, expected);
+
+        let latest = tx.block_timestamp(BlockId::Latest).unwrap();
+        assert_eq!(latest, Some(headers.last().unwrap().timestamp));
+
+        let missing = tx
+            .block_timestamp(BlockNumber::new_or_panic(100).into())
+            .unwrap();
+        assert_eq!(missing, None);
+    }
+
+    #[test]
+    fn verify_block_chain() {
+        let (mut connection, headers) = setup();
+        let tx = connection.transaction().unwrap();
+
+        let first = headers.first().unwrap().number;
+        let last = headers.last().unwrap().number;
+
+        let result = tx.verify_block_chain(first, last).unwrap();
+        assert_eq!(result, None);
+
+        let result = tx.verify_block_chain(first, last + 1).unwrap();
+        assert_eq!(result, Some(ChainGap::Missing(last + 1)));
+
+        tx.purge_block(headers[1].number).unwrap();
+        let result = tx.verify_block_chain(first, last).unwrap();
+        assert_eq!(result, Some(ChainGap::Missing(headers[1].number)));
+    }
+
+    #[test]
+    fn verify_block_chain_parent_hash_mismatch() {
+        let (mut connection, headers) = setup();
+        let tx = connection.transaction().unwrap();
+
+        let wrong_parent = headers[2]
+            .child_builder()
+            .parent_hash(block_hash_bytes!(b"not block 2"))
+            .finalize_with_hash(block_hash_bytes!(b"block 3 hash"));
+        tx.insert_block_header(&wrong_parent).unwrap();
+
+        let result = tx
+            .verify_block_chain(BlockNumber::GENESIS, wrong_parent.number)
+            .unwrap();
+        assert_eq!(
+            result,
+            Some(ChainGap::ParentHashMismatch {
+                block: wrong_parent.number,
+                expected: headers[2].hash,
+                actual: block_hash_bytes!(b"not block 2"),
+            })
+        );
+    }
+
+    #[test]
+    fn block_headers() {
+        let (mut connection, headers) = setup();
+        let tx = connection.transaction().unwrap();
+
+        let collect = |from, to| {
+            tx.block_headers(
+                BlockNumber::new_or_panic(from),
+                BlockNumber::new_or_panic(to),
+            )
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap()
+        };
+
+        assert_eq!(collect(0, 100), headers);
+        assert_eq!(collect(1, 2), headers[1..].to_vec());
+        assert_eq!(collect(0, 0), headers[..1].to_vec());
+        assert_eq!(collect(3, 100), vec![]);
+        assert_eq!(collect(2, 1), vec![]);
+    }
+
+    #[test]
+    fn block_headers_spanning_batches() {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let count = BlockHeaders::BATCH_SIZE * 2 + 1;
+        let mut header = BlockHeader::builder().finalize_with_hash(block_hash!("0x0"));
+        tx.insert_block_header(&header).unwrap();
+        for i in 1..count {
+            header = header
+                .child_builder()
+                .finalize_with_hash(BlockHash(pathfinder_crypto::Felt::from_u64(i as u64)));
+            tx.insert_block_header(&header).unwrap();
+        }
+
+        let numbers = tx
+            .block_headers(BlockNumber::GENESIS, BlockNumber::MAX)
+            .map(|header| header.unwrap().number.get())
+            .collect::<Vec<_>>();
+        assert_eq!(numbers, (0..count as u64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn block_headers_by_timestamp_range() {
+        let (mut connection, headers) = setup();
+        let tx = connection.transaction().unwrap();
+
+        let range = |from, to| {
+            tx.block_headers_by_timestamp_range(
+                BlockTimestamp::new_or_panic(from),
+                BlockTimestamp::new_or_panic(to),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(range(0, 100), headers);
+        assert_eq!(range(11, 15), headers[1..].to_vec());
+        assert_eq!(range(10, 10), headers[..1].to_vec());
+        assert_eq!(range(13, 14), vec![]);
+        assert_eq!(range(15, 10), vec![]);
+    }
+
     #[test]
     fn block_is_l1_accepted() {
         let (mut connection, headers) = setup();
@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use pathfinder_common::BlockNumber;
+
+use crate::prelude::*;
+
+/// How long syncing a block took, stored with millisecond precision.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BlockTimings {
+    /// Downloading the block, its state update and its signature.
+    pub download: Duration,
+    /// Downloading and declaring the classes the block declares.
+    pub class_declaration: Duration,
+    /// Applying the block to the database.
+    pub apply: Duration,
+}
+
+impl Transaction<'_> {
+    /// Records the timings of a block, replacing any previous ones.
+    pub fn insert_block_timings(
+        &self,
+        block_number: BlockNumber,
+        timings: &BlockTimings,
+    ) -> anyhow::Result<()> {
+        let millis = |duration: Duration| i64::try_from(duration.as_millis()).unwrap_or(i64::MAX);
+
+        self.inner()
+            .execute(
+                r"INSERT OR REPLACE INTO block_timings
+                       ( block_number,  download_ms,  class_declaration_ms,  apply_ms)
+                VALUES (:block_number, :download_ms, :class_declaration_ms, :apply_ms)",
+                named_params! {
+                    ":block_number": &block_number,
+                    ":download_ms": &millis(timings.download),
+                    ":class_declaration_ms": &millis(timings.class_declaration),
+                    ":apply_ms": &millis(timings.apply),
+                },
+            )
+            .context("Inserting block timings")?;
+
+        Ok(())
+    }
+
+    /// Returns the recorded timings of blocks within the range, inclusive on
+    /// both ends, ordered by block number. Blocks without timings are skipped.
+    pub fn block_timings_in_range(
+        &self,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> anyhow::Result<Vec<(BlockNumber, BlockTimings)>> {
+        let mut stmt = self
+            .inner()
+            .prepare_cached(
+                r"SELECT block_number, download_ms, class_declaration_ms, apply_ms
+                FROM block_timings
+                WHERE block_number >= ? AND block_number <= ?
+                ORDER BY block_number ASC",
+            )
+            .context("Preparing block timings query")?;
+
+        let millis = |row: &rusqlite::Row<'_>, index| {
+            row.get_i64(index)
+                .map(|ms| Duration::from_millis(ms.try_into().unwrap_or_default()))
+        };
+        let timings = stmt
+            .query_map(params![&from, &to], |row| {
+                let block_number = row.get_block_number(0)?;
+                let timings = BlockTimings {
+                    download: millis(row, 1)?,
+                    class_declaration: millis(row, 2)?,
+                    apply: millis(row, 3)?,
+                };
+                Ok((block_number, timings))
+            })
+            .context("Querying block timings")?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Iterating over block timings")?;
+
+        Ok(timings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::BlockHeader;
+
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let genesis = BlockHeader::builder()
+            .number(BlockNumber::GENESIS)
+            .finalize_with_hash(block_hash_bytes!(b"genesis"));
+        let block1 = genesis
+            .child_builder()
+            .finalize_with_hash(block_hash_bytes!(b"block 1 hash"));
+        let block2 = block1
+            .child_builder()
+            .finalize_with_hash(block_hash_bytes!(b"block 2 hash"));
+        for header in [&genesis, &block1, &block2] {
+            tx.insert_block_header(header).unwrap();
+        }
+
+        let timings = |ms| BlockTimings {
+            download: Duration::from_millis(ms),
+            class_declaration: Duration::from_millis(ms + 1),
+            apply: Duration::from_millis(ms + 2),
+        };
+        tx.insert_block_timings(genesis.number, &timings(10))
+            .unwrap();
+        // Block 1 has no timings. Block 2's are overwritten.
+        tx.insert_block_timings(block2.number, &timings(20))
+            .unwrap();
+        tx.insert_block_timings(block2.number, &timings(30))
+            .unwrap();
+
+        let result = tx
+            .block_timings_in_range(genesis.number, block2.number)
+            .unwrap();
+        assert_eq!(
+            result,
+            vec![(genesis.number, timings(10)), (block2.number, timings(30))]
+        );
+
+        let result = tx
+            .block_timings_in_range(block1.number, block1.number)
+            .unwrap();
+        assert_eq!(result, vec![]);
+
+        // Timings are removed along with their block.
+        tx.purge_block(block2.number).unwrap();
+        let result = tx
+            .block_timings_in_range(block2.number, block2.number)
+            .unwrap();
+        assert_eq!(result, vec![]);
+    }
+}
@@ -4,6 +4,18 @@ use pathfinder_common::{BlockNumber, CasmHash, ClassCommitmentLeafHash, ClassHas
 use crate::prelude::*;
 use crate::BlockId;
 
+/// Compresses a class definition into the format it is stored in.
+///
+/// Compression is expensive, so callers inserting many classes should do this
+/// up front, outside of the database transaction, and use the `_compressed`
+/// insert variants.
+pub fn compress_class_definition(definition: &[u8]) -> anyhow::Result<Vec<u8>> {
+    zstd::bulk::Compressor::new(10)
+        .context("Creating zstd compressor")?
+        .compress(definition)
+        .context("Compressing class definition")
+}
+
 impl Transaction<'_> {
     pub fn insert_sierra_class(
         &self,
@@ -12,14 +24,28 @@ impl Transaction<'_> {
         casm_hash: &CasmHash,
         casm_definition: &[u8],
     ) -> anyhow::Result<()> {
-        let mut compressor = zstd::bulk::Compressor::new(10).context("Creating zstd compressor")?;
-        let sierra_definition = compressor
-            .compress(sierra_definition)
+        let sierra_definition = compress_class_definition(sierra_definition)
             .context("Compressing sierra definition")?;
-        let casm_definition = compressor
-            .compress(casm_definition)
-            .context("Compressing casm definition")?;
+        let casm_definition =
+            compress_class_definition(casm_definition).context("Compressing casm definition")?;
+
+        self.insert_sierra_class_compressed(
+            sierra_hash,
+            &sierra_definition,
+            casm_hash,
+            &casm_definition,
+        )
+    }
 
+    /// Same as [insert_sierra_class](Self::insert_sierra_class) but takes
+    /// definitions already compressed with [compress_class_definition].
+    pub fn insert_sierra_class_compressed(
+        &self,
+        sierra_hash: &SierraHash,
+        sierra_definition: &[u8],
+        casm_hash: &CasmHash,
+        casm_definition: &[u8],
+    ) -> anyhow::Result<()> {
         self.inner()
             .execute(
                 r"INSERT OR IGNORE INTO class_definitions (hash,  definition) VALUES (?, ?)",
@@ -85,11 +111,19 @@ impl Transaction<'_> {
         cairo_hash: ClassHash,
         definition: &[u8],
     ) -> anyhow::Result<()> {
-        let mut compressor = zstd::bulk::Compressor::new(10).context("Creating zstd compressor")?;
-        let definition = compressor
-            .compress(definition)
-            .context("Compressing cairo definition")?;
+        let definition =
+            compress_class_definition(definition).context("Compressing cairo definition")?;
+
+        self.insert_cairo_class_compressed(cairo_hash, &definition)
+    }
 
+    /// Same as [insert_cairo_class](Self::insert_cairo_class) but takes a
+    /// definition already compressed with [compress_class_definition].
+    pub fn insert_cairo_class_compressed(
+        &self,
+        cairo_hash: ClassHash,
+        definition: &[u8],
+    ) -> anyhow::Result<()> {
         self.inner()
             .execute(
                 r"INSERT OR IGNORE INTO class_definitions (hash,  definition) VALUES (?, ?)",
@@ -556,6 +590,27 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn class_existence_beyond_sqlite_variable_limit() {
+        let mut connection = crate::StorageBuilder::in_memory()
+            .unwrap()
+            .connection()
+            .unwrap();
+        let transaction = connection.transaction().unwrap();
+
+        // Every third class is stored, so that a misaligned result is noticed.
+        let classes = (0..2500u64)
+            .map(|i| ClassHash(Felt::from_u64(i)))
+            .collect::<Vec<_>>();
+        for class in classes.iter().step_by(3) {
+            transaction.insert_cairo_class(*class, b"{}").unwrap();
+        }
+
+        let result = transaction.class_definitions_exist(&classes).unwrap();
+        let expected = (0..classes.len()).map(|i| i % 3 == 0).collect::<Vec<_>>();
+        assert_eq!(result, expected);
+    }
+
     #[test]
     fn insert_cairo() {
         let mut connection = crate::StorageBuilder::in_memory()
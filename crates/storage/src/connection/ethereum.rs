@@ -1,3 +1,4 @@
+use anyhow::Context;
 use pathfinder_common::BlockNumber;
 use pathfinder_ethereum::EthereumStateUpdate;
 
@@ -5,8 +6,15 @@ use crate::prelude::*;
 
 impl Transaction<'_> {
     pub fn upsert_l1_state(&self, update: &EthereumStateUpdate) -> anyhow::Result<()> {
-        self.inner().execute(
-            r"INSERT OR REPLACE INTO l1_state (
+        self.upsert_l1_states(std::slice::from_ref(update))
+    }
+
+    /// Upserts multiple L1 states, reusing a single prepared statement.
+    pub fn upsert_l1_states(&self, updates: &[EthereumStateUpdate]) -> anyhow::Result<()> {
+        let mut stmt = self
+            .inner()
+            .prepare_cached(
+                r"INSERT OR REPLACE INTO l1_state (
                     starknet_block_number,
                     starknet_block_hash,
                     starknet_state_root
@@ -15,12 +23,17 @@ impl Transaction<'_> {
                     :starknet_block_hash,
                     :starknet_state_root
                 )",
-            named_params! {
+            )
+            .context("Preparing L1 state upsert statement")?;
+
+        for update in updates {
+            stmt.execute(named_params! {
                 ":starknet_block_number": &update.block_number,
                 ":starknet_block_hash": &update.block_hash,
                 ":starknet_state_root": &update.state_root,
-            },
-        )?;
+            })
+            .context("Upserting L1 state")?;
+        }
 
         Ok(())
     }
@@ -72,6 +85,39 @@ impl Transaction<'_> {
         .optional()
         .map_err(|e| e.into())
     }
+
+    /// The number of the latest L1 accepted block, without loading the rest of
+    /// its L1 state.
+    pub fn latest_l1_block_number(&self) -> anyhow::Result<Option<BlockNumber>> {
+        self.inner()
+            .query_row(
+                "SELECT starknet_block_number FROM l1_state ORDER BY starknet_block_number DESC \
+                 LIMIT 1",
+                [],
+                |row| row.get_block_number(0),
+            )
+            .optional()
+            .context("Querying latest L1 block number")
+    }
+
+    /// Deletes all L1 states for blocks after `block`.
+    pub fn purge_l1_states_after(&self, block: BlockNumber) -> anyhow::Result<()> {
+        self.inner()
+            .execute(
+                "DELETE FROM l1_state WHERE starknet_block_number > ?",
+                params![&block],
+            )
+            .context("Deleting L1 states")?;
+
+        Ok(())
+    }
+
+    /// The number of L1 states stored.
+    pub fn l1_state_count(&self) -> anyhow::Result<u64> {
+        self.inner()
+            .query_row("SELECT COUNT(*) FROM l1_state", [], |row| row.get(0))
+            .context("Counting L1 states")
+    }
 }
 
 #[cfg(test)]
@@ -108,6 +154,12 @@ mod tests {
 
         let result = tx.latest_l1_state().unwrap();
         assert_eq!(result, None);
+
+        let result = tx.latest_l1_block_number().unwrap();
+        assert_eq!(result, None);
+
+        let result = tx.l1_state_count().unwrap();
+        assert_eq!(result, 0);
     }
 
     #[test]
@@ -124,6 +176,12 @@ mod tests {
 
         let result = tx.latest_l1_state().unwrap().unwrap();
         assert_eq!(result, expected);
+
+        let result = tx.latest_l1_block_number().unwrap();
+        assert_eq!(result, Some(expected.block_number));
+
+        let result = tx.l1_state_count().unwrap();
+        assert_eq!(result, 3);
     }
 
     #[test]
@@ -144,6 +202,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn upsert_many() {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let updates = create_updates();
+        tx.upsert_l1_states(&updates).unwrap();
+
+        for expected in updates {
+            let result = tx.l1_state_at_number(expected.block_number).unwrap();
+
+            assert_eq!(result, Some(expected));
+        }
+    }
+
+    #[test]
+    fn purge_after() {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let updates = create_updates();
+        tx.upsert_l1_states(&updates).unwrap();
+
+        tx.purge_l1_states_after(updates[0].block_number).unwrap();
+
+        let result = tx.latest_l1_state().unwrap();
+        assert_eq!(result, Some(updates[0]));
+        let result = tx.l1_state_count().unwrap();
+        assert_eq!(result, 1);
+    }
+
     #[test]
     fn upsert_overwrites() {
         let storage = crate::StorageBuilder::in_memory().unwrap();
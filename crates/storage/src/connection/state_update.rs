@@ -1467,6 +1467,37 @@ mod tests {
             assert_eq!(invalid_by_number, None);
         }
 
+        #[test]
+        fn contract_nonce_history() {
+            let (mut db, _state_update, header) = setup();
+            let tx = db.transaction().unwrap();
+
+            let contract = contract_address_bytes!(b"contract address");
+            let header2 = header
+                .child_builder()
+                .finalize_with_hash(block_hash_bytes!(b"hash 2"));
+            let state_update2 = StateUpdate::default()
+                .with_contract_nonce(contract, contract_nonce_bytes!(b"nonce value 3"));
+            tx.insert_block_header(&header2).unwrap();
+            tx.insert_state_update(header2.number, &state_update2)
+                .unwrap();
+
+            let at_first = tx.contract_nonce(contract, header.number.into()).unwrap();
+            assert_eq!(at_first, Some(contract_nonce_bytes!(b"nonce value")));
+
+            let at_second = tx.contract_nonce(contract, header2.number.into()).unwrap();
+            assert_eq!(at_second, Some(contract_nonce_bytes!(b"nonce value 3")));
+
+            // Contracts without a newer update keep their previous nonce.
+            let unchanged = tx
+                .contract_nonce(
+                    contract_address_bytes!(b"contract address 2"),
+                    header2.number.into(),
+                )
+                .unwrap();
+            assert_eq!(unchanged, Some(contract_nonce_bytes!(b"nonce value 2")));
+        }
+
         #[test]
         fn get_storage_value() {
             let (mut db, state_update, header) = setup();
@@ -1,8 +1,10 @@
 use std::collections::HashMap;
+use std::sync::Mutex;
 
 use anyhow::Context;
 use bitvec::prelude::Msb0;
 use bitvec::vec::BitVec;
+use cached::{Cached, SizedCache};
 use pathfinder_common::prelude::*;
 use pathfinder_crypto::Felt;
 
@@ -353,11 +355,26 @@ impl Transaction<'_> {
         update: &TrieUpdate,
         block_number: BlockNumber,
     ) -> anyhow::Result<RootIndexUpdate> {
+        if !update.nodes_added.is_empty() {
+            self.inserted_storage_trie_nodes.set(true);
+        }
         self.insert_trie(update, block_number, "trie_storage")
     }
 
     pub fn storage_trie_node(&self, index: u64) -> anyhow::Result<Option<StoredNode>> {
-        self.trie_node(index, "trie_storage")
+        if let Some(node) = self.trie_node_cache.get(index) {
+            return Ok(Some(node));
+        }
+
+        let node = self.trie_node(index, "trie_storage")?;
+        if let Some(node) = &node {
+            if !self.inserted_storage_trie_nodes.get() {
+                self.trie_node_cache
+                    .set(index, node.clone(), self.trie_node_cache_generation);
+            }
+        }
+
+        Ok(node)
     }
 
     pub fn storage_trie_node_hash(&self, index: u64) -> anyhow::Result<Option<Felt>> {
@@ -468,6 +485,11 @@ impl Transaction<'_> {
                 for idx in indices.iter() {
                     delete_stmt.execute(params![idx]).context("Deleting node")?;
                 }
+                if table == "trie_storage" {
+                    self.stale_storage_trie_nodes
+                        .borrow_mut()
+                        .extend_from_slice(&indices);
+                }
                 metrics::counter!(METRIC_TRIE_NODES_REMOVED, indices.len() as u64, "table" => table);
             }
 
@@ -565,6 +587,9 @@ impl Transaction<'_> {
                 .context("Inserting node")?;
 
             indices.insert(idx, storage_idx);
+            if table == "trie_storage" {
+                self.stale_storage_trie_nodes.borrow_mut().push(storage_idx);
+            }
 
             metrics::increment_counter!(METRIC_TRIE_NODES_ADDED, "table" => table);
         }
@@ -614,6 +639,84 @@ impl Transaction<'_> {
     }
 }
 
+/// A cache for nodes of the global storage trie, shared by all connections of
+/// a [`Storage`](crate::Storage).
+///
+/// Stored nodes never change, but pruning deletes them and SQLite may assign a
+/// deleted index to a new node. So the indices a transaction prunes or stores
+/// are evicted once it commits. A reader whose snapshot predates that commit
+/// could put a pruned node back, which the [generation](Self::generation)
+/// passed to [`set`](Self::set) prevents. Nodes read by a transaction after it
+/// stored nodes itself are not cached either, since a rollback frees their
+/// indices.
+pub(crate) struct TrieNodeCache(Option<Mutex<CachedNodes>>);
+
+struct CachedNodes {
+    nodes: SizedCache<u64, StoredNode>,
+    /// Incremented on every eviction.
+    generation: u64,
+}
+
+impl TrieNodeCache {
+    /// Create a new cache with the given size. A size of zero disables caching.
+    pub fn with_size(size: usize) -> Self {
+        Self((size > 0).then(|| {
+            Mutex::new(CachedNodes {
+                nodes: SizedCache::with_size(size),
+                generation: 0,
+            })
+        }))
+    }
+
+    /// The current generation of the cache. Must be read before the
+    /// transaction the nodes are read with takes its snapshot.
+    pub fn generation(&self) -> u64 {
+        self.0
+            .as_ref()
+            .map_or(0, |cache| cache.lock().unwrap().generation)
+    }
+
+    pub fn get(&self, index: u64) -> Option<StoredNode> {
+        self.0
+            .as_ref()?
+            .lock()
+            .unwrap()
+            .nodes
+            .cache_get(&index)
+            .cloned()
+    }
+
+    /// Caches `node` unless there were evictions since `generation`, in which
+    /// case it may have been read from a snapshot older than them.
+    pub fn set(&self, index: u64, node: StoredNode, generation: u64) {
+        if let Some(cache) = &self.0 {
+            let mut cache = cache.lock().unwrap();
+            if cache.generation == generation {
+                cache.nodes.cache_set(index, node);
+            }
+        }
+    }
+
+    pub fn remove(&self, indices: &[u64]) {
+        if let Some(cache) = &self.0 {
+            let mut cache = cache.lock().unwrap();
+            for index in indices {
+                cache.nodes.cache_remove(index);
+            }
+            cache.generation += 1;
+        }
+    }
+
+    /// Reset the cache. Removes all entries and frees the memory.
+    pub fn reset(&self) {
+        if let Some(cache) = &self.0 {
+            let mut cache = cache.lock().unwrap();
+            cache.nodes.cache_reset();
+            cache.generation += 1;
+        }
+    }
+}
+
 const METRIC_TRIE_NODES_REMOVED: &str = "pathfinder_storage_trie_nodes_deleted_total";
 const METRIC_TRIE_NODES_ADDED: &str = "pathfinder_storage_trie_nodes_added_total";
 
@@ -1724,4 +1827,77 @@ mod tests {
             Some(2)
         );
     }
+
+    #[test]
+    fn storage_trie_node_cache_is_not_stale_after_prune_and_reinsert() {
+        let storage = crate::StorageBuilder::in_tempdir_with_trie_node_cache(
+            TriePruneMode::Prune { num_blocks_kept: 0 },
+            16,
+        )
+        .unwrap();
+        let mut writer = storage.connection().unwrap();
+        let mut reader = storage.connection().unwrap();
+
+        let binary = |left, right| TrieUpdate {
+            nodes_added: vec![(
+                Felt::from_u64(left),
+                Node::Binary {
+                    left: NodeRef::StorageIndex(left),
+                    right: NodeRef::StorageIndex(right),
+                },
+            )],
+            nodes_removed: vec![],
+            root_commitment: Felt::ONE,
+        };
+
+        let tx = writer.transaction().unwrap();
+        let RootIndexUpdate::Updated(idx) = tx
+            .insert_storage_trie(&binary(1, 2), BlockNumber::GENESIS)
+            .unwrap()
+        else {
+            panic!("Node should be stored");
+        };
+        tx.commit().unwrap();
+
+        // This reader's snapshot predates the pruning below.
+        let stale_reader = reader.transaction().unwrap();
+        assert_eq!(
+            stale_reader.storage_trie_node(idx).unwrap(),
+            Some(StoredNode::Binary { left: 1, right: 2 })
+        );
+
+        // The node is marked as removed in block 1 and pruned in block 2. It has the
+        // largest index, so SQLite assigns it to the node stored in block 2.
+        let tx = writer.transaction().unwrap();
+        tx.insert_storage_trie(
+            &TrieUpdate {
+                nodes_removed: vec![idx],
+                root_commitment: Felt::ZERO,
+                ..Default::default()
+            },
+            BlockNumber::new_or_panic(1),
+        )
+        .unwrap();
+        tx.commit().unwrap();
+        let tx = writer.transaction().unwrap();
+        assert_eq!(
+            tx.insert_storage_trie(&binary(3, 4), BlockNumber::new_or_panic(2))
+                .unwrap(),
+            RootIndexUpdate::Updated(idx)
+        );
+        tx.commit().unwrap();
+
+        // Reading the pruned node from the old snapshot must not cache it again.
+        assert_eq!(
+            stale_reader.storage_trie_node(idx).unwrap(),
+            Some(StoredNode::Binary { left: 1, right: 2 })
+        );
+        drop(stale_reader);
+
+        let tx = reader.transaction().unwrap();
+        assert_eq!(
+            tx.storage_trie_node(idx).unwrap(),
+            Some(StoredNode::Binary { left: 3, right: 4 })
+        );
+    }
 }
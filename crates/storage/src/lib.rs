@@ -19,6 +19,7 @@ use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 use anyhow::Context;
+use connection::TrieNodeCache;
 pub use connection::*;
 use event::RunningEventFilter;
 pub use event::EVENT_KEY_FILTER_LIMIT;
@@ -94,6 +95,7 @@ struct Inner {
     pool: Pool<SqliteConnectionManager>,
     event_filter_cache: Arc<AggregateBloomCache>,
     running_event_filter: Arc<Mutex<RunningEventFilter>>,
+    trie_node_cache: Arc<TrieNodeCache>,
     trie_prune_mode: TriePruneMode,
 }
 
@@ -102,6 +104,7 @@ pub struct StorageManager {
     journal_mode: JournalMode,
     event_filter_cache: Arc<AggregateBloomCache>,
     running_event_filter: Arc<Mutex<RunningEventFilter>>,
+    trie_node_cache: Arc<TrieNodeCache>,
     trie_prune_mode: TriePruneMode,
 }
 
@@ -134,6 +137,7 @@ impl StorageManager {
             pool,
             event_filter_cache: self.event_filter_cache.clone(),
             running_event_filter: self.running_event_filter.clone(),
+            trie_node_cache: self.trie_node_cache.clone(),
             trie_prune_mode: self.trie_prune_mode,
         }))
     }
@@ -142,6 +146,12 @@ impl StorageManager {
         self.create_pool_with_flags(capacity, OpenFlags::default())
     }
 
+    /// Creates a pool of connections opened with `SQLITE_OPEN_READ_ONLY`.
+    ///
+    /// In [WAL](JournalMode::WAL) mode, transactions on these connections read
+    /// from a snapshot and neither block nor are blocked by an open write
+    /// transaction. In rollback journal mode, readers and the writer still
+    /// contend for the database lock.
     pub fn create_read_only_pool(&self, capacity: NonZeroU32) -> anyhow::Result<Storage> {
         let flags = OpenFlags::SQLITE_OPEN_READ_ONLY
             | OpenFlags::SQLITE_OPEN_NO_MUTEX
@@ -154,6 +164,7 @@ pub struct StorageBuilder {
     database_path: PathBuf,
     journal_mode: JournalMode,
     event_filter_cache_size: usize,
+    trie_node_cache_size: usize,
     trie_prune_mode: Option<TriePruneMode>,
 }
 
@@ -163,6 +174,7 @@ impl StorageBuilder {
             database_path,
             journal_mode: JournalMode::WAL,
             event_filter_cache_size: 16,
+            trie_node_cache_size: 0,
             trie_prune_mode: None,
         }
     }
@@ -177,6 +189,13 @@ impl StorageBuilder {
         self
     }
 
+    /// The number of global storage trie nodes to cache in memory. Zero, the
+    /// default, disables the cache.
+    pub fn trie_node_cache_size(mut self, trie_node_cache_size: usize) -> Self {
+        self.trie_node_cache_size = trie_node_cache_size;
+        self
+    }
+
     pub fn trie_prune_mode(mut self, trie_prune_mode: Option<TriePruneMode>) -> Self {
         self.trie_prune_mode = trie_prune_mode;
         self
@@ -253,6 +272,23 @@ impl StorageBuilder {
             .create_pool(NonZeroU32::new(32).unwrap())
     }
 
+    /// Convenience function for tests to create an in-tempdir database with a
+    /// specific trie prune mode and trie node cache size.
+    pub fn in_tempdir_with_trie_node_cache(
+        trie_prune_mode: TriePruneMode,
+        trie_node_cache_size: usize,
+    ) -> anyhow::Result<Storage> {
+        let db_dir = tempfile::TempDir::new()?;
+        let mut db_path = PathBuf::from(db_dir.path());
+        db_path.push("db.sqlite");
+        crate::StorageBuilder::file(db_path)
+            .trie_prune_mode(Some(trie_prune_mode))
+            .trie_node_cache_size(trie_node_cache_size)
+            .migrate()
+            .unwrap()
+            .create_pool(NonZeroU32::new(32).unwrap())
+    }
+
     /// Convenience function for tests to create an in-tempdir database with a
     /// specific trie prune mode.
     pub fn in_tempdir_with_trie_pruning_and_pool_size(
@@ -328,6 +364,7 @@ impl StorageBuilder {
                 self.event_filter_cache_size,
             )),
             running_event_filter: Arc::new(Mutex::new(running_event_filter)),
+            trie_node_cache: Arc::new(TrieNodeCache::with_size(self.trie_node_cache_size)),
             trie_prune_mode,
         })
     }
@@ -400,6 +437,7 @@ impl Storage {
             conn,
             self.0.event_filter_cache.clone(),
             self.0.running_event_filter.clone(),
+            self.0.trie_node_cache.clone(),
             self.0.trie_prune_mode,
         ))
     }
@@ -407,6 +445,119 @@ impl Storage {
     pub fn path(&self) -> &Path {
         &self.0.database_path
     }
+
+    /// Moves the content of the WAL into the database and truncates the WAL
+    /// file, using `PRAGMA wal_checkpoint(TRUNCATE)`.
+    ///
+    /// This waits for concurrent writers and readers to finish. It has no
+    /// effect in [rollback](JournalMode::Rollback) mode.
+    pub fn wal_checkpoint(&self) -> anyhow::Result<WalCheckpoint> {
+        let mut wal_path = self.0.database_path.as_os_str().to_owned();
+        wal_path.push("-wal");
+        let wal_size = || std::fs::metadata(&wal_path).map_or(0, |metadata| metadata.len());
+
+        let conn = self.0.pool.get().context("Getting database connection")?;
+        let size_before = wal_size();
+        let (busy, log_frames, checkpointed_frames) = conn
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })
+            .context("Checkpointing WAL")?;
+
+        // Frame counts are -1 outside of WAL mode, where there is nothing to
+        // checkpoint.
+        let frames = |count: i64| u64::try_from(count).unwrap_or_default();
+        Ok(WalCheckpoint {
+            busy: busy != 0,
+            log_frames: frames(log_frames),
+            checkpointed_frames: frames(checkpointed_frames),
+            reclaimed_bytes: size_before.saturating_sub(wal_size()),
+        })
+    }
+
+    /// Copies the database into a new file at `destination` using SQLite's
+    /// [online backup API](https://www.sqlite.org/backup.html), without
+    /// stopping other connections.
+    ///
+    /// The database is copied a few pages at a time, pausing in between so
+    /// that writers are not blocked for long. The copy is a transactionally
+    /// consistent snapshot: should another connection commit while the backup
+    /// is in progress, SQLite restarts it so that the changes are included.
+    /// Under a constant stream of writes, such as sync catching up, the backup
+    /// may therefore take a long time to complete.
+    ///
+    /// This requires [WAL](JournalMode::WAL) mode for writers to keep going
+    /// while pages are being copied. In rollback journal mode each step
+    /// blocks writers.
+    ///
+    /// The copy is written to a temporary file next to `destination`, which is
+    /// only renamed to `destination` once the backup is complete. A failed
+    /// backup therefore leaves nothing behind.
+    ///
+    /// This blocks the calling thread until the backup is complete, so async
+    /// callers should run it using [tokio::task::spawn_blocking].
+    pub fn backup_to(&self, destination: &Path) -> anyhow::Result<()> {
+        const PAGES_PER_STEP: std::ffi::c_int = 1024;
+        const PAUSE_BETWEEN_STEPS: std::time::Duration = std::time::Duration::from_millis(10);
+
+        anyhow::ensure!(
+            !destination.exists(),
+            "Backup destination {} already exists",
+            destination.display()
+        );
+
+        let directory = match destination.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        // Removed on drop unless persisted.
+        let temp_file = tempfile::Builder::new()
+            .prefix(".backup-")
+            .tempfile_in(directory)
+            .context("Creating temporary backup file")?;
+
+        let source = self.0.pool.get().context("Getting database connection")?;
+        let mut target =
+            rusqlite::Connection::open(temp_file.path()).context("Creating backup database")?;
+        rusqlite::backup::Backup::new(&source, &mut target)
+            .context("Starting database backup")?
+            .run_to_completion(PAGES_PER_STEP, PAUSE_BETWEEN_STEPS, None)
+            .context("Backing up database")?;
+        target
+            .close()
+            .map_err(|(_, e)| e)
+            .context("Closing backup database")?;
+
+        temp_file
+            .persist_noclobber(destination)
+            .context("Moving backup to its destination")?;
+
+        Ok(())
+    }
+}
+
+/// Outcome of a [WAL checkpoint](Storage::wal_checkpoint).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalCheckpoint {
+    /// Whether the checkpoint could not complete because of other connections.
+    pub busy: bool,
+    /// Frames in the WAL, as reported by SQLite. A truncated WAL has none.
+    pub log_frames: u64,
+    /// Frames of the WAL copied back into the database, as reported by SQLite.
+    pub checkpointed_frames: u64,
+    /// By how much the WAL file shrank.
+    pub reclaimed_bytes: u64,
+}
+
+impl WalCheckpoint {
+    /// Whether every frame of the WAL was copied back into the database.
+    pub fn is_complete(&self) -> bool {
+        !self.busy && self.checkpointed_frames == self.log_frames
+    }
 }
 
 fn setup_journal_mode(
@@ -686,6 +837,75 @@ mod tests {
         );
     }
 
+    #[test]
+    fn read_only_pool_reads_while_writing() {
+        let db_dir = tempfile::TempDir::new().unwrap();
+        let manager = StorageBuilder::file(db_dir.path().join("db.sqlite"))
+            .migrate()
+            .unwrap();
+        let storage = manager.create_pool(NonZeroU32::new(1).unwrap()).unwrap();
+        let read_only = manager
+            .create_read_only_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+
+        let mut writer = storage.connection().unwrap();
+        let write_tx = writer
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .unwrap();
+        write_tx
+            .insert_block_header(&pathfinder_common::BlockHeader::default())
+            .unwrap();
+
+        // Reads succeed while the write transaction is open, without seeing its
+        // uncommitted changes.
+        let mut reader = read_only.connection().unwrap();
+        let read_tx = reader.transaction().unwrap();
+        assert!(!read_tx.block_exists(BlockNumber::GENESIS.into()).unwrap());
+        drop(read_tx);
+
+        write_tx.commit().unwrap();
+
+        let read_tx = reader.transaction().unwrap();
+        assert!(read_tx.block_exists(BlockNumber::GENESIS.into()).unwrap());
+    }
+
+    #[test]
+    fn backup_to_file() {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let headers = create_blocks(3);
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        for header in &headers {
+            tx.insert_block_header(header).unwrap();
+        }
+        tx.commit().unwrap();
+        drop(connection);
+
+        let backup_dir = tempfile::TempDir::new().unwrap();
+        let backup_path = backup_dir.path().join("backup.sqlite");
+        storage.backup_to(&backup_path).unwrap();
+        // No temporary files are left behind.
+        let files = std::fs::read_dir(backup_dir.path()).unwrap().count();
+        assert_eq!(files, 1);
+
+        let backup = StorageBuilder::file(backup_path.clone())
+            .migrate()
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+        let mut connection = backup.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        for header in &headers {
+            assert_eq!(
+                tx.block_header(header.number.into()).unwrap(),
+                Some(header.clone())
+            );
+        }
+
+        // Existing files are not overwritten.
+        storage.backup_to(&backup_path).unwrap_err();
+    }
+
     #[test]
     fn running_event_filter_rebuilt_after_shutdown() {
         let n_blocks = 6;
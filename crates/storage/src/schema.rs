@@ -27,6 +27,8 @@ mod revision_0064;
 mod revision_0065;
 mod revision_0066;
 mod revision_0067;
+mod revision_0068;
+mod revision_0069;
 
 pub(crate) use base::base_schema;
 
@@ -62,6 +64,8 @@ pub fn migrations() -> &'static [MigrationFn] {
         revision_0065::migrate,
         revision_0066::migrate,
         revision_0067::migrate,
+        revision_0068::migrate,
+        revision_0069::migrate,
     ]
 }
 
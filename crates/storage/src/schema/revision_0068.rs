@@ -0,0 +1,13 @@
+use anyhow::Context;
+
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tracing::info!("Adding timestamp index to block_headers");
+
+    tx.execute(
+        "CREATE INDEX block_headers_timestamp ON block_headers(timestamp)",
+        [],
+    )
+    .context("Creating block_headers timestamp index")?;
+
+    Ok(())
+}
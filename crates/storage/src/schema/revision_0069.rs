@@ -0,0 +1,19 @@
+use anyhow::Context;
+
+/// Adds a table recording how long syncing each block took.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tracing::info!("Adding block_timings table");
+
+    tx.execute(
+        r"CREATE TABLE block_timings (
+            block_number INTEGER PRIMARY KEY REFERENCES block_headers(number) ON DELETE CASCADE,
+            download_ms INTEGER NOT NULL,
+            class_declaration_ms INTEGER NOT NULL,
+            apply_ms INTEGER NOT NULL
+        )",
+        [],
+    )
+    .context("Creating block_timings table")?;
+
+    Ok(())
+}
@@ -1,7 +1,7 @@
 use std::future::Future;
 use std::sync::LazyLock;
 
-use tokio_util::sync::CancellationToken;
+pub use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
 
 pub trait FutureOutputExt {
@@ -44,6 +44,20 @@ where
     })
 }
 
+/// Spawns a future on the `tokio` runtime through a
+/// [`tokio_util::task::TaskTracker`] like [`spawn`], but without cancelling it
+/// upon graceful shutdown. Graceful shutdown waits for the future to complete,
+/// so it must observe [`cancellation_token`] itself.
+pub fn spawn_uncancelled<F>(future: F) -> tokio::task::JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let Handle { task_tracker, .. } = HANDLE.clone();
+
+    task_tracker.spawn(future)
+}
+
 /// Runs the provided closure on a `tokio` thread where blocking is acceptable,
 /// similarly to [`tokio::task::spawn_blocking`], however internally the closure
 /// is spawned through a [`tokio_util::task::TaskTracker`] to ensure that it